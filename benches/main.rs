@@ -128,193 +128,456 @@ macro_rules! bench_ibe {
 }
 
 fn bench_abe_rwac_cca_this_paper(criterion: &mut Criterion) {
-    use cca_transforms::kem::rwac::{gen_a, AccessPolicy, RWAC};
-    use group::ff::Field;
-    use irmaseal_curve::Scalar;
+    use cca_transforms::kem::rwac::{
+        gen_a, AccessPolicy, Attribute, PreparedPublicKey, PreparedUserSecretKey, RWAC,
+    };
+    use cca_transforms::kem::ABKEM;
+    use cca_transforms::Derive;
 
     for n in [1, 10, 100] {
         let mut rng = rand::thread_rng();
         let (mpk, msk) = RWAC::setup(&mut rng);
 
-        let s: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
-        let usk_s = RWAC::extract_usk(&msk, &s[..], &mut rng);
+        let s: Vec<Attribute> = (0..n)
+            .map(|i| Attribute::derive_str(&format!("attr:{}", i)))
+            .collect();
+        let usk_s = RWAC::extract_usk(None, &msk, &s[..], &mut rng);
 
         let a = gen_a(n);
         let rho = s.clone();
         let ap = AccessPolicy { a, rho };
 
-        let (ct, _) = RWAC::encaps(&mpk, &ap, &mut rng);
+        let (ct, _) = RWAC::encaps(&mpk, &ap, &mut rng).unwrap();
+
+        let prepared = PreparedUserSecretKey::from(&usk_s);
+        let prepared_pk = PreparedPublicKey::from(&mpk);
+        let (ap_c, ct_c) = (ap.clone(), ct.clone());
+        let (msk_c, s_c) = (msk.clone(), s.clone());
 
-        criterion.bench_function(&format!("RWAC CCA setup, n = {}", n.to_string()), |b| {
+        criterion.bench_function(&format!("RWAC CCA setup, n = {}", n), |b| {
             let mut rng = rand::thread_rng();
             b.iter(|| RWAC::setup(&mut rng))
         });
+        criterion.bench_function(&format!("RWAC CCA extract, n = {}", n), move |b| {
+            let mut rng = rand::thread_rng();
+            b.iter(|| {
+                RWAC::extract_usk(
+                    None,
+                    black_box(&msk),
+                    black_box(&s[..]),
+                    black_box(&mut rng),
+                )
+            })
+        });
         criterion.bench_function(
-            &format!("RWAC CCA extract, n = {}", n.to_string()),
+            &format!("RWAC CCA extract batch, 16 keys, n = {}", n),
             move |b| {
                 let mut rng = rand::thread_rng();
-                b.iter(|| {
-                    RWAC::extract_usk(black_box(&msk), black_box(&s[..]), black_box(&mut rng))
-                })
+                let sets = [&s_c[..]; 16];
+                b.iter(|| RWAC::extract_usk_batch(black_box(&msk_c), black_box(&sets), &mut rng))
             },
         );
-        criterion.bench_function(
-            &format!("RWAC CCA encrypt, n = {}", n.to_string()),
-            move |b| b.iter(|| RWAC::encaps(black_box(&mpk), black_box(&ap), black_box(&mut rng))),
-        );
-        criterion.bench_function(
-            &format!("RWAC CCA decrypt, n = {}", n.to_string()),
-            move |b| b.iter(|| RWAC::decaps(black_box(&usk_s), black_box(&ct))),
-        );
+        criterion.bench_function(&format!("RWAC CCA encrypt, n = {}", n), move |b| {
+            b.iter(|| RWAC::encaps(black_box(&mpk), black_box(&ap), black_box(&mut rng)))
+        });
+        criterion.bench_function(&format!("RWAC CCA encrypt prepared, n = {}", n), move |b| {
+            let mut rng = rand::thread_rng();
+            b.iter(|| RWAC::encaps_prepared(black_box(&prepared_pk), black_box(&ap_c), &mut rng))
+        });
+        criterion.bench_function(&format!("RWAC CCA decrypt, n = {}", n), move |b| {
+            b.iter(|| RWAC::decaps(None, black_box(&usk_s), black_box(&ct)))
+        });
+        criterion.bench_function(&format!("RWAC CCA decrypt prepared, n = {}", n), move |b| {
+            b.iter(|| RWAC::decaps_prepared(None, black_box(&prepared), black_box(&ct_c)))
+        });
     }
 }
 
 fn bench_abe_rwac_cpa(criterion: &mut Criterion) {
-    use cca_transforms::kem::rwac_cpa::{gen_a, AccessPolicy, RWACCPA};
-    use group::ff::Field;
-    use irmaseal_curve::Scalar;
+    use cca_transforms::kem::rwac_cpa::{gen_a, AccessPolicy, Attribute, RWACCPA};
+    use cca_transforms::kem::ABKEM;
+    use cca_transforms::Derive;
 
     for n in [1, 10, 100] {
         let mut rng = rand::thread_rng();
         let (mpk, msk) = RWACCPA::setup(&mut rng);
 
-        let s: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
-        let usk_s = RWACCPA::extract_usk(&msk, &s[..], &mut rng);
+        let s: Vec<Attribute> = (0..n)
+            .map(|i| Attribute::derive_str(&format!("attr:{}", i)))
+            .collect();
+        let usk_s = RWACCPA::extract_usk(None, &msk, &s[..], &mut rng);
 
         let a = gen_a(n);
         let rho = s.clone();
         let ap = AccessPolicy { a, rho };
 
-        let (ct, _) = RWACCPA::encaps(&mpk, &ap, &mut rng);
+        let (ct, _) = RWACCPA::encaps(&mpk, &ap, &mut rng).unwrap();
 
-        criterion.bench_function(&format!("RWAC CPA setup, n = {}", n.to_string()), |b| {
+        criterion.bench_function(&format!("RWAC CPA setup, n = {}", n), |b| {
             let mut rng = rand::thread_rng();
             b.iter(|| RWACCPA::setup(&mut rng))
         });
-        criterion.bench_function(
-            &format!("RWAC CPA extract, n = {}", n.to_string()),
-            move |b| {
-                let mut rng = rand::thread_rng();
-                b.iter(|| {
-                    RWACCPA::extract_usk(black_box(&msk), black_box(&s[..]), black_box(&mut rng))
-                })
-            },
-        );
-        criterion.bench_function(
-            &format!("RWAC CPA encrypt, n = {}", n.to_string()),
-            move |b| {
-                b.iter(|| RWACCPA::encaps(black_box(&mpk), black_box(&ap), black_box(&mut rng)))
-            },
-        );
-        criterion.bench_function(
-            &format!("RWAC CPA decrypt, n = {}", n.to_string()),
-            move |b| b.iter(|| RWACCPA::decaps(black_box(&usk_s), black_box(&ct))),
-        );
+        criterion.bench_function(&format!("RWAC CPA extract, n = {}", n), move |b| {
+            let mut rng = rand::thread_rng();
+            b.iter(|| {
+                RWACCPA::extract_usk(
+                    None,
+                    black_box(&msk),
+                    black_box(&s[..]),
+                    black_box(&mut rng),
+                )
+            })
+        });
+        criterion.bench_function(&format!("RWAC CPA encrypt, n = {}", n), move |b| {
+            b.iter(|| RWACCPA::encaps(black_box(&mpk), black_box(&ap), black_box(&mut rng)))
+        });
+        criterion.bench_function(&format!("RWAC CPA decrypt, n = {}", n), move |b| {
+            b.iter(|| RWACCPA::decaps(None, black_box(&usk_s), black_box(&ct)))
+        });
+    }
+}
+
+fn bench_abe_rwac_cca_fo(criterion: &mut Criterion) {
+    use cca_transforms::kem::rwac_fo::{gen_a, AccessPolicy, Attribute, RWACFO};
+    use cca_transforms::kem::ABKEM;
+    use cca_transforms::Derive;
+
+    for n in [1, 10, 100] {
+        let mut rng = rand::thread_rng();
+        let (mpk, msk) = RWACFO::setup(&mut rng);
+
+        let s: Vec<Attribute> = (0..n)
+            .map(|i| Attribute::derive_str(&format!("attr:{}", i)))
+            .collect();
+        let usk_s = RWACFO::extract_usk(None, &msk, &s[..], &mut rng);
+
+        let a = gen_a(n);
+        let rho = s.clone();
+        let ap = AccessPolicy { a, rho };
+
+        let (ct, _) = RWACFO::encaps(&mpk, &ap, &mut rng).unwrap();
+
+        criterion.bench_function(&format!("RWAC FO setup, n = {}", n), |b| {
+            let mut rng = rand::thread_rng();
+            b.iter(|| RWACFO::setup(&mut rng))
+        });
+        criterion.bench_function(&format!("RWAC FO extract, n = {}", n), move |b| {
+            let mut rng = rand::thread_rng();
+            b.iter(|| {
+                RWACFO::extract_usk(
+                    None,
+                    black_box(&msk),
+                    black_box(&s[..]),
+                    black_box(&mut rng),
+                )
+            })
+        });
+        let mpk_c = mpk.clone();
+        criterion.bench_function(&format!("RWAC FO encrypt, n = {}", n), move |b| {
+            b.iter(|| RWACFO::encaps(black_box(&mpk_c), black_box(&ap), black_box(&mut rng)))
+        });
+        criterion.bench_function(&format!("RWAC FO decrypt, n = {}", n), move |b| {
+            b.iter(|| RWACFO::decaps(Some(&mpk), black_box(&usk_s), black_box(&ct)))
+        });
+    }
+}
+
+fn bench_abe_rwac_cca_del(criterion: &mut Criterion) {
+    use cca_transforms::kem::rwac_del::{gen_a, AccessPolicy, Attribute, RWACDEL};
+    use cca_transforms::kem::ABKEM;
+    use cca_transforms::Derive;
+
+    for n in [1, 10, 100] {
+        let mut rng = rand::thread_rng();
+        let (mpk, msk) = RWACDEL::setup(&mut rng);
+
+        let s: Vec<Attribute> = (0..n)
+            .map(|i| Attribute::derive_str(&format!("attr:{}", i)))
+            .collect();
+        let usk_s = RWACDEL::extract_usk(None, &msk, &s[..], &mut rng);
+
+        let a = gen_a(n);
+        let rho = s.clone();
+        let ap = AccessPolicy { a, rho };
+
+        let (ct, _) = RWACDEL::encaps(&mpk, &ap, &mut rng).unwrap();
+
+        criterion.bench_function(&format!("RWAC del setup, n = {}", n), |b| {
+            let mut rng = rand::thread_rng();
+            b.iter(|| RWACDEL::setup(&mut rng))
+        });
+        criterion.bench_function(&format!("RWAC del extract, n = {}", n), move |b| {
+            let mut rng = rand::thread_rng();
+            b.iter(|| {
+                RWACDEL::extract_usk(
+                    None,
+                    black_box(&msk),
+                    black_box(&s[..]),
+                    black_box(&mut rng),
+                )
+            })
+        });
+        criterion.bench_function(&format!("RWAC del encrypt, n = {}", n), move |b| {
+            b.iter(|| RWACDEL::encaps(black_box(&mpk), black_box(&ap), black_box(&mut rng)))
+        });
+        criterion.bench_function(&format!("RWAC del decrypt, n = {}", n), move |b| {
+            b.iter(|| RWACDEL::decaps(None, black_box(&usk_s), black_box(&ct)))
+        });
     }
 }
 
 /// Estimates cost of CCA by delegability by using RWAC CPA.
 fn bench_abe_rwac_cca_del_est(criterion: &mut Criterion) {
-    use cca_transforms::kem::rwac_cpa::{gen_a, AccessPolicy, RWACCPA};
-    use group::ff::Field;
-    use irmaseal_curve::Scalar;
+    use cca_transforms::kem::rwac_cpa::{gen_a, AccessPolicy, Attribute, RWACCPA};
+    use cca_transforms::kem::ABKEM;
+    use cca_transforms::Derive;
 
     for n in [1, 10, 100] {
         let mut rng = rand::thread_rng();
         let (mpk, msk) = RWACCPA::setup(&mut rng);
 
         // setsize + 256
-        let s: Vec<Scalar> = (0..n + 256).map(|_| Scalar::random(&mut rng)).collect();
-        let usk_s = RWACCPA::extract_usk(&msk, &s[..], &mut rng);
+        let s: Vec<Attribute> = (0..n + 256)
+            .map(|i| Attribute::derive_str(&format!("attr:{}", i)))
+            .collect();
+        let usk_s = RWACCPA::extract_usk(None, &msk, &s[..], &mut rng);
 
         let a = gen_a(n + 128);
         // attribute size in policy = n + 128
         let rho = s[..n + 128].to_vec();
         let ap = AccessPolicy { a, rho };
 
-        let (ct, _) = RWACCPA::encaps(&mpk, &ap, &mut rng);
+        let (ct, _) = RWACCPA::encaps(&mpk, &ap, &mut rng).unwrap();
 
-        criterion.bench_function(&format!("RWAC del est setup, n = {}", n.to_string()), |b| {
+        criterion.bench_function(&format!("RWAC del est setup, n = {}", n), |b| {
             let mut rng = rand::thread_rng();
             b.iter(|| RWACCPA::setup(&mut rng))
         });
-        criterion.bench_function(
-            &format!("RWAC del est extract, n = {}", n.to_string()),
-            move |b| {
-                let mut rng = rand::thread_rng();
-                b.iter(|| {
-                    RWACCPA::extract_usk(black_box(&msk), black_box(&s[..]), black_box(&mut rng))
-                })
-            },
-        );
-        criterion.bench_function(
-            &format!("RWAC del est encrypt, n = {}", n.to_string()),
-            move |b| {
-                b.iter(|| RWACCPA::encaps(black_box(&mpk), black_box(&ap), black_box(&mut rng)))
-            },
-        );
-        criterion.bench_function(
-            &format!("RWAC del est decrypt, n = {}", n.to_string()),
-            move |b| b.iter(|| RWACCPA::decaps(black_box(&usk_s), black_box(&ct))),
-        );
+        criterion.bench_function(&format!("RWAC del est extract, n = {}", n), move |b| {
+            let mut rng = rand::thread_rng();
+            b.iter(|| {
+                RWACCPA::extract_usk(
+                    None,
+                    black_box(&msk),
+                    black_box(&s[..]),
+                    black_box(&mut rng),
+                )
+            })
+        });
+        criterion.bench_function(&format!("RWAC del est encrypt, n = {}", n), move |b| {
+            b.iter(|| RWACCPA::encaps(black_box(&mpk), black_box(&ap), black_box(&mut rng)))
+        });
+        criterion.bench_function(&format!("RWAC del est decrypt, n = {}", n), move |b| {
+            b.iter(|| RWACCPA::decaps(None, black_box(&usk_s), black_box(&ct)))
+        });
+    }
+}
+
+fn bench_abe_rwac_cca_ver(criterion: &mut Criterion) {
+    use cca_transforms::kem::rwac_ver::{gen_a, AccessPolicy, Attribute, RWACVER};
+    use cca_transforms::kem::ABKEM;
+    use cca_transforms::Derive;
+
+    for n in [1, 10, 100] {
+        let mut rng = rand::thread_rng();
+        let (mpk, msk) = RWACVER::setup(&mut rng);
+
+        let s: Vec<Attribute> = (0..n)
+            .map(|i| Attribute::derive_str(&format!("attr:{}", i)))
+            .collect();
+        let usk_s = RWACVER::extract_usk(None, &msk, &s[..], &mut rng);
+
+        let a = gen_a(n);
+        let rho = s.clone();
+        let ap = AccessPolicy { a, rho };
+
+        let (ct, _) = RWACVER::encaps(&mpk, &ap, &mut rng).unwrap();
+
+        criterion.bench_function(&format!("RWAC ver setup, n = {}", n), |b| {
+            let mut rng = rand::thread_rng();
+            b.iter(|| RWACVER::setup(&mut rng))
+        });
+        criterion.bench_function(&format!("RWAC ver extract, n = {}", n), move |b| {
+            let mut rng = rand::thread_rng();
+            b.iter(|| {
+                RWACVER::extract_usk(
+                    None,
+                    black_box(&msk),
+                    black_box(&s[..]),
+                    black_box(&mut rng),
+                )
+            })
+        });
+        let mpk_c = mpk.clone();
+        criterion.bench_function(&format!("RWAC ver encrypt, n = {}", n), move |b| {
+            b.iter(|| RWACVER::encaps(black_box(&mpk_c), black_box(&ap), black_box(&mut rng)))
+        });
+        criterion.bench_function(&format!("RWAC ver decrypt, n = {}", n), move |b| {
+            b.iter(|| RWACVER::decaps(Some(&mpk), black_box(&usk_s), black_box(&ct)))
+        });
     }
 }
 
 /// Estimates cost of CCA by verifiablity by using RWAC CPA.
 fn bench_abe_rwac_cca_ver_est(criterion: &mut Criterion) {
-    use cca_transforms::kem::rwac_cpa::{gen_a, AccessPolicy, RWACCPA};
-    use group::ff::Field;
-    use irmaseal_curve::Scalar;
+    use cca_transforms::kem::rwac_cpa::{gen_a, AccessPolicy, Attribute, RWACCPA};
+    use cca_transforms::kem::ABKEM;
+    use cca_transforms::Derive;
 
     for n in [1, 10, 100] {
         let mut rng = rand::thread_rng();
         let (mpk, msk) = RWACCPA::setup(&mut rng);
 
         // setsize remains the same, see actual set passed to extract_usk
-        let s: Vec<Scalar> = (0..n + 1).map(|_| Scalar::random(&mut rng)).collect();
-        let usk_s = RWACCPA::extract_usk(&msk, &s[..], &mut rng);
+        let s: Vec<Attribute> = (0..n + 1)
+            .map(|i| Attribute::derive_str(&format!("attr:{}", i)))
+            .collect();
+        let usk_s = RWACCPA::extract_usk(None, &msk, &s[..], &mut rng);
 
         // access policy grows by one
         let a = gen_a(n + 1);
         let rho = s[..].to_vec();
         let ap = AccessPolicy { a, rho };
 
-        let (ct, _) = RWACCPA::encaps(&mpk, &ap, &mut rng);
+        let (ct, _) = RWACCPA::encaps(&mpk, &ap, &mut rng).unwrap();
 
-        criterion.bench_function(&format!("RWAC ver est setup, n = {}", n.to_string()), |b| {
+        criterion.bench_function(&format!("RWAC ver est setup, n = {}", n), |b| {
             let mut rng = rand::thread_rng();
             b.iter(|| RWACCPA::setup(&mut rng))
         });
-        criterion.bench_function(
-            &format!("RWAC ver est extract, n = {}", n.to_string()),
-            move |b| {
-                let mut rng = rand::thread_rng();
-                b.iter(|| {
-                    RWACCPA::extract_usk(black_box(&msk), black_box(&s[..n]), black_box(&mut rng))
-                })
-            },
-        );
-        criterion.bench_function(
-            &format!("RWAC ver est encrypt, n = {}", n.to_string()),
-            move |b| {
-                b.iter(|| RWACCPA::encaps(black_box(&mpk), black_box(&ap), black_box(&mut rng)))
-            },
-        );
-        criterion.bench_function(
-            &format!("RWAC ver est decrypt, n = {}", n.to_string()),
-            move |b| {
-                b.iter(|| {
-                    // verifiability requires 2 decryptions essentially
-                    RWACCPA::decaps(black_box(&usk_s), black_box(&ct)).unwrap();
-                    RWACCPA::decaps(black_box(&usk_s), black_box(&ct)).unwrap();
-                })
-            },
-        );
+        criterion.bench_function(&format!("RWAC ver est extract, n = {}", n), move |b| {
+            let mut rng = rand::thread_rng();
+            b.iter(|| {
+                RWACCPA::extract_usk(
+                    None,
+                    black_box(&msk),
+                    black_box(&s[..n]),
+                    black_box(&mut rng),
+                )
+            })
+        });
+        criterion.bench_function(&format!("RWAC ver est encrypt, n = {}", n), move |b| {
+            b.iter(|| RWACCPA::encaps(black_box(&mpk), black_box(&ap), black_box(&mut rng)))
+        });
+        criterion.bench_function(&format!("RWAC ver est decrypt, n = {}", n), move |b| {
+            b.iter(|| {
+                // verifiability requires 2 decryptions essentially
+                RWACCPA::decaps(None, black_box(&usk_s), black_box(&ct)).unwrap();
+                RWACCPA::decaps(None, black_box(&usk_s), black_box(&ct)).unwrap();
+            })
+        });
     }
 }
 
+macro_rules! bench_kem_prepared {
+    ($scheme: ident, $struct: ident) => {
+        paste! {
+            fn [<bench_kem_prepared_ $scheme>](criterion: &mut Criterion) {
+                use cca_transforms::kem::$scheme::*;
+                use cca_transforms::{kem::IBKEM, Derive};
+
+                let mut rng = rand::thread_rng();
+
+                let id = "email:w.geraedts@sarif.nl".as_bytes();
+                let kid = <$struct as IBKEM>::Id::derive(id);
+
+                let (pk, sk) = $struct::setup(&mut rng);
+                let usk = $struct::extract_usk(Some(&pk), &sk, &kid, &mut rng);
+                let prepared = PreparedUserSecretKey::from(&usk);
+                let prepared_pk = PreparedPublicKey::from(&pk);
+
+                let (c, _k) = $struct::encaps(&pk, &kid, &mut rng);
+                let cts: Vec<_> = (0..16).map(|_| $struct::encaps(&pk, &kid, &mut rng).0).collect();
+
+                let kids: Vec<_> = (0..16)
+                    .map(|i| <$struct as IBKEM>::Id::derive_str(&format!("email:{}@sarif.nl", i)))
+                    .collect();
+
+                criterion.bench_function(
+                    &format!("kem_{} extract batch, n = 16", stringify!($scheme)).to_string(),
+                    move |b| {
+                        let mut rng = rand::thread_rng();
+                        b.iter(|| $struct::extract_usk_batch(black_box(&sk), black_box(&kids), &mut rng))
+                    },
+                );
+                criterion.bench_function(
+                    &format!("kem_{} decaps batch, n = 16", stringify!($scheme)).to_string(),
+                    move |b| {
+                        b.iter(|| {
+                            $struct::decaps_batch(
+                                black_box(Some(&pk)),
+                                black_box(&usk),
+                                black_box(&cts),
+                            )
+                        })
+                    },
+                );
+                criterion.bench_function(
+                    &format!("kem_{} prepare", stringify!($scheme)).to_string(),
+                    move |b| b.iter(|| PreparedUserSecretKey::from(black_box(&usk))),
+                );
+                criterion.bench_function(
+                    &format!("kem_{} encaps prepared", stringify!($scheme)).to_string(),
+                    move |b| {
+                        let mut rng = rand::thread_rng();
+                        b.iter(|| {
+                            $struct::encaps_prepared(black_box(&prepared_pk), black_box(&kid), &mut rng)
+                        })
+                    },
+                );
+                criterion.bench_function(
+                    &format!("kem_{} decaps prepared", stringify!($scheme)).to_string(),
+                    move |b| {
+                        b.iter(|| {
+                            $struct::decaps_prepared(
+                                black_box(Some(&pk)),
+                                black_box(&prepared),
+                                black_box(&c),
+                            )
+                        })
+                    },
+                );
+            }
+        }
+    };
+}
+
+/// Compares multi_mul_g1 with separate multiplications, for N random terms.
+fn bench_multi_mul_n<const N: usize>(criterion: &mut Criterion) {
+    use cca_transforms::multi_mul_g1;
+    use group::{ff::Field, Group};
+    use irmaseal_curve::{G1Affine, G1Projective, Scalar};
+
+    let mut rng = rand::thread_rng();
+    let terms: [(G1Affine, Scalar); N] = [(); N].map(|_| {
+        (
+            G1Projective::random(&mut rng).into(),
+            Scalar::random(&mut rng),
+        )
+    });
+
+    criterion.bench_function(&format!("G1 separate mul, n = {}", N), |b| {
+        b.iter(|| {
+            black_box(&terms)
+                .iter()
+                .fold(G1Projective::identity(), |acc, (p, s)| acc + p * s)
+        })
+    });
+    criterion.bench_function(&format!("G1 multi_mul_g1, n = {}", N), |b| {
+        b.iter(|| multi_mul_g1(black_box(&terms)))
+    });
+}
+
+fn bench_multi_mul(criterion: &mut Criterion) {
+    bench_multi_mul_n::<2>(criterion);
+    bench_multi_mul_n::<3>(criterion);
+}
+
 bench_kem!(cgw_kv1, CGWKV1);
 bench_kem!(cgw_fo, CGWFO);
+bench_kem!(cgw_hibe_fo, CGWHIBEFO);
+bench_kem_prepared!(cgw_kv1, CGWKV1);
+bench_kem_prepared!(cgw_fo, CGWFO);
 bench_ibe!(cgw, CGW);
 
 criterion_group!(
@@ -322,7 +585,10 @@ criterion_group!(
     config = Criterion::default().warm_up_time(Duration::new(0, 500));
     targets =
     bench_kem_cgw_fo,
+    bench_kem_cgw_hibe_fo,
     bench_kem_cgw_kv1,
+    bench_kem_prepared_cgw_fo,
+    bench_kem_prepared_cgw_kv1,
 );
 
 criterion_group!(
@@ -338,8 +604,18 @@ criterion_group!(
     targets =
     bench_abe_rwac_cpa,
     bench_abe_rwac_cca_this_paper,
+    bench_abe_rwac_cca_fo,
+    bench_abe_rwac_cca_del,
     bench_abe_rwac_cca_del_est,
+    bench_abe_rwac_cca_ver,
     bench_abe_rwac_cca_ver_est,
 );
 
-criterion_main!(kem_benches, abe_benches);
+criterion_group!(
+    name = util_benches;
+    config = Criterion::default().warm_up_time(Duration::new(0, 500));
+    targets =
+    bench_multi_mul,
+);
+
+criterion_main!(kem_benches, abe_benches, util_benches);
@@ -0,0 +1,236 @@
+//! Hybrid public-key encryption on top of any [`IBKEM`].
+//!
+//! The shared secret of the KEM is expanded into a key and nonce for AES-256-GCM (the DEM)
+//! using SHAKE256. The KDF also absorbs the header, which binds the DEM to the KEM ciphertext.
+//! Sealed messages are self-describing and laid out as follows:
+//!
+//! | identifier length (1 byte) | [`IBKEM::IDENTIFIER`] | KEM ciphertext | DEM ciphertext and tag |
+//!
+//! Every message uses a fresh key, hence a nonce is never reused under the same key.
+//!
+//! For payloads that do not fit in memory, see [`stream`].
+
+pub mod stream;
+
+use crate::kem::{SharedSecret, IBKEM, SS_BYTES};
+use crate::util::shake256;
+use crate::Compress;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use rand::{CryptoRng, Rng};
+
+/// Domain separation tag of the KDF.
+const KDF_DST: &[u8] = b"cca_transforms-hybrid-kdf";
+
+/// Size of the DEM key in bytes.
+const KEY_BYTES: usize = 32;
+
+/// Size of the DEM nonce in bytes.
+const NONCE_BYTES: usize = 12;
+
+/// Size of the DEM authentication tag in bytes.
+pub const TAG_BYTES: usize = 16;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Error indicating that the header is truncated or otherwise malformed.
+    MalformedHeader,
+    /// Error indicating that the header was produced by a different scheme.
+    SchemeMismatch,
+    /// Error indicating that the KEM failed to decapsulate.
+    Kem(crate::kem::Error),
+    /// Error indicating that the plaintext (segment) has an invalid length for the DEM.
+    Encryption,
+    /// Error indicating that the DEM ciphertext or the associated data was modified.
+    Decryption,
+}
+
+/// Writes the header containing the scheme identifier and the KEM ciphertext.
+fn header<K: IBKEM>(ct: &K::Ct) -> Vec<u8> {
+    let id = K::IDENTIFIER.as_bytes();
+    let id_len = u8::try_from(id.len()).expect("scheme identifier too long");
+
+    let mut buf = Vec::with_capacity(1 + id.len() + K::CT_BYTES);
+    buf.push(id_len);
+    buf.extend_from_slice(id);
+    buf.extend_from_slice(ct.to_bytes().as_ref());
+
+    buf
+}
+
+/// Derives key material for the DEM from the shared secret and the header.
+fn kdf<const N: usize>(dst: &[u8], ss: &SharedSecret, header: &[u8]) -> [u8; N] {
+    let mut buf = Vec::with_capacity(dst.len() + SS_BYTES + header.len());
+    buf.extend_from_slice(dst);
+    buf.extend_from_slice(&ss.0);
+    buf.extend_from_slice(header);
+
+    shake256::<N>(&buf)
+}
+
+/// Parses the header and decapsulates the shared secret.
+fn decaps_header<K>(pk: Option<&K::Pk>, usk: &K::Usk, header: &[u8]) -> Result<SharedSecret, Error>
+where
+    K: IBKEM<Ss = SharedSecret>,
+    <K::Ct as Compress>::Output: for<'a> TryFrom<&'a [u8]>,
+{
+    if identifier(header).ok_or(Error::MalformedHeader)? != K::IDENTIFIER {
+        return Err(Error::SchemeMismatch);
+    }
+    if header.len() != header_len::<K>() {
+        return Err(Error::MalformedHeader);
+    }
+
+    let ct_bytes = <K::Ct as Compress>::Output::try_from(&header[1 + K::IDENTIFIER.len()..])
+        .map_err(|_| Error::MalformedHeader)?;
+    let ct = Option::<K::Ct>::from(K::Ct::from_bytes(&ct_bytes)).ok_or(Error::MalformedHeader)?;
+
+    K::decaps(pk, usk, &ct).map_err(Error::Kem)
+}
+
+/// Size of the header of a scheme in bytes.
+pub fn header_len<K: IBKEM>() -> usize {
+    1 + K::IDENTIFIER.len() + K::CT_BYTES
+}
+
+/// Returns the scheme identifier of a sealed message, without further validation.
+pub fn identifier(sealed: &[u8]) -> Option<&str> {
+    let (&id_len, rest) = sealed.split_first()?;
+    let id = rest.get(..id_len as usize)?;
+
+    core::str::from_utf8(id).ok()
+}
+
+/// Encrypt a plaintext for an identity, authenticating the associated data.
+///
+/// # Errors
+///
+/// This function returns an [`Error::Encryption`] when the plaintext is too long for the DEM.
+pub fn seal<K, R>(
+    pk: &K::Pk,
+    id: &K::Id,
+    plaintext: &[u8],
+    aad: &[u8],
+    rng: &mut R,
+) -> Result<Vec<u8>, Error>
+where
+    K: IBKEM<Ss = SharedSecret>,
+    R: Rng + CryptoRng,
+{
+    let (ct, ss) = K::encaps(pk, id, rng);
+
+    let mut sealed = header::<K>(&ct);
+    let okm = kdf::<{ KEY_BYTES + NONCE_BYTES }>(KDF_DST, &ss, &sealed);
+    let (key, nonce) = okm.split_at(KEY_BYTES);
+
+    let body = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key))
+        .encrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| Error::Encryption)?;
+    sealed.extend_from_slice(&body);
+
+    Ok(sealed)
+}
+
+/// Decrypt a sealed message using a user secret key, verifying the associated data.
+///
+/// Optionally requires the public key, see [`IBKEM::decaps`].
+///
+/// # Errors
+///
+/// This function returns an [`Error::MalformedHeader`] or [`Error::SchemeMismatch`]
+/// when the header cannot be parsed for this scheme, an [`Error::Kem`] when the KEM explicitly
+/// rejects, and an [`Error::Decryption`] when the DEM ciphertext or associated data was modified.
+pub fn open<K>(
+    pk: Option<&K::Pk>,
+    usk: &K::Usk,
+    sealed: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, Error>
+where
+    K: IBKEM<Ss = SharedSecret>,
+    <K::Ct as Compress>::Output: for<'a> TryFrom<&'a [u8]>,
+{
+    if sealed.len() < header_len::<K>() + TAG_BYTES {
+        return Err(Error::MalformedHeader);
+    }
+    let (header, body) = sealed.split_at(header_len::<K>());
+
+    let ss = decaps_header::<K>(pk, usk, header)?;
+    let okm = kdf::<{ KEY_BYTES + NONCE_BYTES }>(KDF_DST, &ss, header);
+    let (key, nonce) = okm.split_at(KEY_BYTES);
+
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key))
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: body, aad })
+        .map_err(|_| Error::Decryption)
+}
+
+#[cfg(all(test, feature = "cgwkv1"))]
+mod tests {
+    use super::*;
+    use crate::kem::cgw_kv1::CGWKV1;
+    use crate::Derive;
+
+    const ID: &str = "email:w.geraedts@sarif.nl";
+
+    #[test]
+    fn eq_seal_open() {
+        let mut rng = rand::thread_rng();
+        let id = <CGWKV1 as IBKEM>::Id::derive_str(ID);
+
+        let (pk, sk) = CGWKV1::setup(&mut rng);
+        let usk = CGWKV1::extract_usk(Some(&pk), &sk, &id, &mut rng);
+
+        let sealed = seal::<CGWKV1, _>(&pk, &id, b"hello", b"aad", &mut rng).unwrap();
+        assert_eq!(identifier(&sealed), Some(CGWKV1::IDENTIFIER));
+        assert_eq!(
+            sealed.len(),
+            1 + CGWKV1::IDENTIFIER.len() + CGWKV1::CT_BYTES + 5 + TAG_BYTES
+        );
+        assert_eq!(
+            open::<CGWKV1>(Some(&pk), &usk, &sealed, b"aad").unwrap(),
+            b"hello"
+        );
+    }
+
+    #[test]
+    fn rejects_modifications() {
+        let mut rng = rand::thread_rng();
+        let id = <CGWKV1 as IBKEM>::Id::derive_str(ID);
+
+        let (pk, sk) = CGWKV1::setup(&mut rng);
+        let usk = CGWKV1::extract_usk(Some(&pk), &sk, &id, &mut rng);
+        let sealed = seal::<CGWKV1, _>(&pk, &id, b"hello", b"aad", &mut rng).unwrap();
+
+        assert!(matches!(
+            open::<CGWKV1>(Some(&pk), &usk, &sealed, b"other"),
+            Err(Error::Decryption)
+        ));
+
+        let mut s = sealed.clone();
+        *s.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            open::<CGWKV1>(Some(&pk), &usk, &s, b"aad"),
+            Err(Error::Decryption)
+        ));
+
+        let mut s = sealed.clone();
+        s[1] ^= 1;
+        assert!(matches!(
+            open::<CGWKV1>(Some(&pk), &usk, &s, b"aad"),
+            Err(Error::SchemeMismatch)
+        ));
+
+        assert!(matches!(
+            open::<CGWKV1>(Some(&pk), &usk, &sealed[..sealed.len() - 6], b"aad"),
+            Err(Error::MalformedHeader)
+        ));
+    }
+}
@@ -0,0 +1,448 @@
+//! Streaming hybrid encryption for large payloads, using the STREAM construction.
+//!  * From: "Online Authenticated-Encryption and its Nonce-Reuse Misuse-Resistance"
+//!  * Published in: CRYPTO, 2015
+//!
+//! The plaintext is split into segments of [`SEGMENT_BYTES`], of which only the final segment
+//! may be shorter (or empty). Each segment is encrypted using AES-256-GCM under the nonce
+//! prefix ‖ counter (32 bits) ‖ final flag (1 byte). Hence, segments cannot be reordered,
+//! dropped or truncated without being noticed.
+//!
+//! The stream starts with the same header as a [`seal`](super::seal)ed message.
+//! The key and nonce prefix are derived from the shared secret and this header using SHAKE256.
+//!
+//! With the `std` feature, [`SealWriter`] and [`OpenReader`] adapt this to [`std::io`].
+
+use super::{decaps_header, header, kdf, Error, KEY_BYTES, TAG_BYTES};
+use crate::kem::{SharedSecret, IBKEM};
+use crate::Compress;
+use aes_gcm::aead::stream::{DecryptorBE32, EncryptorBE32};
+use aes_gcm::aead::KeyInit;
+use aes_gcm::{Aes256Gcm, Key};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use rand::{CryptoRng, Rng};
+
+#[cfg(feature = "std")]
+use alloc::boxed::Box;
+#[cfg(feature = "std")]
+use std::io;
+
+/// Domain separation tag of the KDF.
+const KDF_DST: &[u8] = b"cca_transforms-hybrid-stream-kdf";
+
+/// Size of the nonce prefix in bytes, the remaining 5 bytes contain the counter and flag.
+const PREFIX_BYTES: usize = 7;
+
+/// Size of a plaintext segment in bytes.
+pub const SEGMENT_BYTES: usize = 1 << 16;
+
+/// Size of an encrypted (non-final) segment in bytes.
+pub const CT_SEGMENT_BYTES: usize = SEGMENT_BYTES + TAG_BYTES;
+
+/// Derives the DEM key and nonce prefix from the shared secret and the header.
+fn aead(ss: &SharedSecret, header: &[u8]) -> (Aes256Gcm, [u8; PREFIX_BYTES]) {
+    let okm = kdf::<{ KEY_BYTES + PREFIX_BYTES }>(KDF_DST, ss, header);
+    let (key, prefix) = okm.split_at(KEY_BYTES);
+
+    (
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+        <[u8; PREFIX_BYTES]>::try_from(prefix).unwrap(),
+    )
+}
+
+/// Encrypts a stream segment by segment.
+pub struct Encryptor {
+    inner: EncryptorBE32<Aes256Gcm>,
+}
+
+impl Encryptor {
+    /// Starts a stream for an identity.
+    ///
+    /// Returns the header, which must precede the encrypted segments.
+    pub fn new<K, R>(pk: &K::Pk, id: &K::Id, rng: &mut R) -> (Vec<u8>, Self)
+    where
+        K: IBKEM<Ss = SharedSecret>,
+        R: Rng + CryptoRng,
+    {
+        let (ct, ss) = K::encaps(pk, id, rng);
+
+        let header = header::<K>(&ct);
+        let (aead, prefix) = aead(&ss, &header);
+
+        (
+            header,
+            Encryptor {
+                inner: EncryptorBE32::from_aead(aead, (&prefix).into()),
+            },
+        )
+    }
+
+    /// Encrypts a non-final segment.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an [`Error::Encryption`] when the segment is not exactly
+    /// [`SEGMENT_BYTES`] long, or when the stream has too many segments.
+    pub fn encrypt_next(&mut self, segment: &[u8]) -> Result<Vec<u8>, Error> {
+        if segment.len() != SEGMENT_BYTES {
+            return Err(Error::Encryption);
+        }
+
+        self.inner
+            .encrypt_next(segment)
+            .map_err(|_| Error::Encryption)
+    }
+
+    /// Encrypts the final segment, which ends the stream.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an [`Error::Encryption`] when the segment is longer than [`SEGMENT_BYTES`].
+    pub fn encrypt_last(self, segment: &[u8]) -> Result<Vec<u8>, Error> {
+        if segment.len() > SEGMENT_BYTES {
+            return Err(Error::Encryption);
+        }
+
+        self.inner
+            .encrypt_last(segment)
+            .map_err(|_| Error::Encryption)
+    }
+}
+
+/// Decrypts a stream segment by segment.
+pub struct Decryptor {
+    inner: DecryptorBE32<Aes256Gcm>,
+}
+
+impl Decryptor {
+    /// Starts decrypting a stream given its header, see [`header_len`](super::header_len).
+    ///
+    /// Optionally requires the public key, see [`IBKEM::decaps`].
+    ///
+    /// # Errors
+    ///
+    /// This function returns the same errors as [`open`](super::open) does for the header.
+    pub fn new<K>(pk: Option<&K::Pk>, usk: &K::Usk, header: &[u8]) -> Result<Self, Error>
+    where
+        K: IBKEM<Ss = SharedSecret>,
+        <K::Ct as Compress>::Output: for<'a> TryFrom<&'a [u8]>,
+    {
+        let ss = decaps_header::<K>(pk, usk, header)?;
+        let (aead, prefix) = aead(&ss, header);
+
+        Ok(Decryptor {
+            inner: DecryptorBE32::from_aead(aead, (&prefix).into()),
+        })
+    }
+
+    /// Decrypts a non-final segment.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an [`Error::Decryption`] when the segment was modified,
+    /// reordered or is in fact the final segment.
+    pub fn decrypt_next(&mut self, segment: &[u8]) -> Result<Vec<u8>, Error> {
+        if segment.len() != CT_SEGMENT_BYTES {
+            return Err(Error::Decryption);
+        }
+
+        self.inner
+            .decrypt_next(segment)
+            .map_err(|_| Error::Decryption)
+    }
+
+    /// Decrypts the final segment.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an [`Error::Decryption`] when the segment was modified,
+    /// reordered or is not the final segment, e.g., when the stream was truncated.
+    pub fn decrypt_last(self, segment: &[u8]) -> Result<Vec<u8>, Error> {
+        if segment.len() > CT_SEGMENT_BYTES {
+            return Err(Error::Decryption);
+        }
+
+        self.inner
+            .decrypt_last(segment)
+            .map_err(|_| Error::Decryption)
+    }
+}
+
+#[cfg(feature = "std")]
+fn invalid_data(e: Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, std::format!("{:?}", e))
+}
+
+/// Writes an encrypted stream to an underlying writer.
+///
+/// The stream must be ended using [`SealWriter::finish`],
+/// otherwise it is truncated and will fail to decrypt.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub struct SealWriter<W: io::Write> {
+    enc: Encryptor,
+    inner: W,
+    buf: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> SealWriter<W> {
+    /// Starts a stream for an identity and writes its header.
+    pub fn new<K, R>(pk: &K::Pk, id: &K::Id, mut inner: W, rng: &mut R) -> io::Result<Self>
+    where
+        K: IBKEM<Ss = SharedSecret>,
+        R: Rng + CryptoRng,
+    {
+        let (header, enc) = Encryptor::new::<K, R>(pk, id, rng);
+        inner.write_all(&header)?;
+
+        Ok(SealWriter {
+            enc,
+            inner,
+            buf: Vec::with_capacity(SEGMENT_BYTES),
+        })
+    }
+
+    /// Encrypts the final segment and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        let ct = self.enc.encrypt_last(&self.buf).map_err(invalid_data)?;
+        self.inner.write_all(&ct)?;
+        self.inner.flush()?;
+
+        Ok(self.inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> io::Write for SealWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let mut rest = data;
+
+        while !rest.is_empty() {
+            // A full segment is only encrypted once more data follows,
+            // as the final segment must be flagged as such.
+            if self.buf.len() == SEGMENT_BYTES {
+                let ct = self.enc.encrypt_next(&self.buf).map_err(invalid_data)?;
+                self.inner.write_all(&ct)?;
+                self.buf.clear();
+            }
+
+            let n = rest.len().min(SEGMENT_BYTES - self.buf.len());
+            self.buf.extend_from_slice(&rest[..n]);
+            rest = &rest[n..];
+        }
+
+        Ok(data.len())
+    }
+
+    /// Flushes the underlying writer, buffered plaintext of an incomplete segment is kept.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reads and decrypts a stream from an underlying reader.
+///
+/// Returns an error of kind [`io::ErrorKind::InvalidData`]
+/// as soon as a modified or truncated segment is encountered, and on every read after that.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub struct OpenReader<R: io::Read> {
+    state: ReadState,
+    inner: R,
+    /// Encrypted segment with one byte of lookahead.
+    buf: Vec<u8>,
+    /// Decrypted segment.
+    plain: Vec<u8>,
+    pos: usize,
+}
+
+/// Progress of an [`OpenReader`].
+#[cfg(feature = "std")]
+enum ReadState {
+    /// More segments follow.
+    Open(Box<Decryptor>),
+    /// The final segment has been decrypted.
+    Done,
+    /// A segment failed to decrypt, every further read fails as well.
+    Failed,
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> OpenReader<R> {
+    /// Reads the header of a stream and decapsulates it.
+    pub fn new<K>(pk: Option<&K::Pk>, usk: &K::Usk, mut inner: R) -> io::Result<Self>
+    where
+        K: IBKEM<Ss = SharedSecret>,
+        <K::Ct as Compress>::Output: for<'a> TryFrom<&'a [u8]>,
+    {
+        let mut header = alloc::vec![0u8; super::header_len::<K>()];
+        inner.read_exact(&mut header)?;
+
+        let dec = Decryptor::new::<K>(pk, usk, &header).map_err(invalid_data)?;
+
+        Ok(OpenReader {
+            state: ReadState::Open(Box::new(dec)),
+            inner,
+            buf: Vec::with_capacity(CT_SEGMENT_BYTES + 1),
+            plain: Vec::new(),
+            pos: 0,
+        })
+    }
+
+    /// Decrypts the next segment, which is final when no more data follows it.
+    fn fill(&mut self) -> io::Result<()> {
+        let mut len = self.buf.len();
+        self.buf.resize(CT_SEGMENT_BYTES + 1, 0);
+
+        while len < self.buf.len() {
+            match self.inner.read(&mut self.buf[len..]) {
+                Ok(0) => break,
+                Ok(n) => len += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.buf.truncate(len);
+                    return Err(e);
+                }
+            }
+        }
+        self.buf.truncate(len);
+
+        // The reader fails, unless the segment decrypts.
+        let plain = match core::mem::replace(&mut self.state, ReadState::Failed) {
+            ReadState::Open(mut dec) if len > CT_SEGMENT_BYTES => {
+                let rest = self.buf.split_off(CT_SEGMENT_BYTES);
+                let segment = core::mem::replace(&mut self.buf, rest);
+
+                dec.decrypt_next(&segment)
+                    .map(|plain| (plain, ReadState::Open(dec)))
+            }
+            ReadState::Open(dec) => {
+                let segment = core::mem::take(&mut self.buf);
+
+                dec.decrypt_last(&segment)
+                    .map(|plain| (plain, ReadState::Done))
+            }
+            _ => unreachable!("only open readers are filled"),
+        };
+
+        let (plain, state) = plain.map_err(invalid_data)?;
+        self.state = state;
+        self.plain = plain;
+        self.pos = 0;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> io::Read for OpenReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.plain.len() {
+            match self.state {
+                ReadState::Open(_) => self.fill()?,
+                ReadState::Done => return Ok(0),
+                ReadState::Failed => return Err(invalid_data(Error::Decryption)),
+            }
+        }
+
+        let n = out.len().min(self.plain.len() - self.pos);
+        out[..n].copy_from_slice(&self.plain[self.pos..self.pos + n]);
+        self.pos += n;
+
+        Ok(n)
+    }
+}
+
+#[cfg(all(test, feature = "cgwkv1"))]
+mod tests {
+    use super::*;
+    use crate::kem::cgw_kv1::CGWKV1;
+    use crate::Derive;
+
+    const ID: &str = "email:w.geraedts@sarif.nl";
+
+    fn payload(len: usize) -> Vec<u8> {
+        (0..len).map(|i| i as u8).collect()
+    }
+
+    #[test]
+    fn eq_encrypt_decrypt() {
+        let mut rng = rand::thread_rng();
+        let id = <CGWKV1 as IBKEM>::Id::derive_str(ID);
+
+        let (pk, sk) = CGWKV1::setup(&mut rng);
+        let usk = CGWKV1::extract_usk(Some(&pk), &sk, &id, &mut rng);
+        let m = payload(2 * SEGMENT_BYTES + 5);
+
+        let (header, mut enc) = Encryptor::new::<CGWKV1, _>(&pk, &id, &mut rng);
+        let c0 = enc.encrypt_next(&m[..SEGMENT_BYTES]).unwrap();
+        let c1 = enc
+            .encrypt_next(&m[SEGMENT_BYTES..2 * SEGMENT_BYTES])
+            .unwrap();
+        let c2 = enc.encrypt_last(&m[2 * SEGMENT_BYTES..]).unwrap();
+
+        let mut dec = Decryptor::new::<CGWKV1>(Some(&pk), &usk, &header).unwrap();
+        let mut res = dec.decrypt_next(&c0).unwrap();
+        res.extend(dec.decrypt_next(&c1).unwrap());
+        res.extend(dec.decrypt_last(&c2).unwrap());
+        assert_eq!(res, m);
+
+        // Reordering segments.
+        let mut dec = Decryptor::new::<CGWKV1>(Some(&pk), &usk, &header).unwrap();
+        assert!(matches!(dec.decrypt_next(&c1), Err(Error::Decryption)));
+
+        // Truncating the stream.
+        let mut dec = Decryptor::new::<CGWKV1>(Some(&pk), &usk, &header).unwrap();
+        dec.decrypt_next(&c0).unwrap();
+        assert!(matches!(dec.decrypt_last(&c1), Err(Error::Decryption)));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn eq_io_adapters() {
+        use std::io::{Read, Write};
+
+        let mut rng = rand::thread_rng();
+        let id = <CGWKV1 as IBKEM>::Id::derive_str(ID);
+
+        let (pk, sk) = CGWKV1::setup(&mut rng);
+        let usk = CGWKV1::extract_usk(Some(&pk), &sk, &id, &mut rng);
+
+        for len in [0, 1, SEGMENT_BYTES, 3 * SEGMENT_BYTES + 7] {
+            let m = payload(len);
+
+            let mut w = SealWriter::new::<CGWKV1, _>(&pk, &id, Vec::new(), &mut rng).unwrap();
+            for chunk in m.chunks(1000) {
+                w.write_all(chunk).unwrap();
+            }
+            let c = w.finish().unwrap();
+
+            let mut res = Vec::new();
+            OpenReader::new::<CGWKV1>(Some(&pk), &usk, &c[..])
+                .unwrap()
+                .read_to_end(&mut res)
+                .unwrap();
+            assert_eq!(res, m);
+
+            // Dropping the final segment.
+            if len >= SEGMENT_BYTES {
+                let truncated = &c[..c.len() - (len % SEGMENT_BYTES) - TAG_BYTES];
+                let mut r = OpenReader::new::<CGWKV1>(Some(&pk), &usk, truncated).unwrap();
+                let err = r.read_to_end(&mut Vec::new()).unwrap_err();
+                assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+            }
+
+            // Modifying the final segment, the error persists on later reads.
+            let mut modified = c.clone();
+            *modified.last_mut().unwrap() ^= 1;
+            let mut r = OpenReader::new::<CGWKV1>(Some(&pk), &usk, &modified[..]).unwrap();
+            let err = r.read_to_end(&mut Vec::new()).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+            for _ in 0..2 {
+                let err = r.read(&mut [0u8; 16]).unwrap_err();
+                assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+            }
+        }
+    }
+}
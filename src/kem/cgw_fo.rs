@@ -8,25 +8,43 @@
 //! To output a bigger secret SHAKE256 can be used with a bigger output buffer.
 //!
 //! A drawback of a Fujisaki-Okamoto transform is that we now need the public key to decapsulate :(
+//!
+//! Two variants are provided:
+//! * [`CGWFO`] uses explicit rejection, decapsulation of an illegitimate ciphertext fails.
+//! * [`CGWFOImplicit`] uses implicit rejection (U^⊥̸), decapsulation of an illegitimate ciphertext
+//!   results in a pseudorandom key derived from a secret seed in the USK and the ciphertext.
 
-use crate::kem::{Error, SharedSecret, IBKEM};
+use crate::kem::{Error, MultiRecipient, SharedSecret, IBKEM, SS_BYTES};
 use crate::pke::cgw::{CipherText, Msg, CGW, USK_BYTES as CPA_USK_BYTES};
 use crate::pke::IBE;
 use crate::util::*;
 use crate::Compress;
+use alloc::vec::Vec;
 use arrayref::{array_refs, mut_array_refs};
 use group::Group;
+use irmaseal_curve::{G1Affine, G1Projective};
 use rand::{CryptoRng, Rng};
-use subtle::{ConstantTimeEq, CtOption};
+use subtle::{ConditionallySelectable, ConstantTimeEq, CtOption};
+use tiny_keccak::{Hasher, Shake};
 
 /// These struct are identical for the CCA KEM.
-pub use crate::pke::cgw::{PublicKey, SecretKey, CT_BYTES, MSG_BYTES, PK_BYTES, SK_BYTES};
+pub use crate::pke::cgw::{
+    PreparedPublicKey, PublicKey, SecretKey, CT_BYTES, MSG_BYTES, PK_BYTES, SK_BYTES,
+};
 
 /// Size of the compressed user secret key in bytes.
 ///
-/// The USK includes a random message and the identity (needed for re-encryption).
+/// The USK includes the identity (needed for re-encryption).
 pub const USK_BYTES: usize = CPA_USK_BYTES + ID_BYTES;
 
+/// Size of the secret seed used for implicit rejection in bytes.
+pub const SEED_BYTES: usize = 32;
+
+/// Size of the compressed user secret key for implicit rejection in bytes.
+///
+/// The USK additionally includes the secret seed.
+pub const IMPLICIT_USK_BYTES: usize = USK_BYTES + SEED_BYTES;
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct UserSecretKey {
     usk: crate::pke::cgw::UserSecretKey,
@@ -57,11 +75,208 @@ impl Compress for UserSecretKey {
     }
 }
 
-/// The CCA2 secure KEM that results by applying the implicit rejection
+/// User secret key of [`CGWFOImplicit`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImplicitUserSecretKey {
+    usk: UserSecretKey,
+    seed: [u8; SEED_BYTES],
+}
+
+impl Compress for ImplicitUserSecretKey {
+    const OUTPUT_SIZE: usize = IMPLICIT_USK_BYTES;
+    type Output = [u8; Self::OUTPUT_SIZE];
+
+    fn to_bytes(&self) -> [u8; IMPLICIT_USK_BYTES] {
+        let mut buf = [0u8; IMPLICIT_USK_BYTES];
+        let (usk, seed) = mut_array_refs![&mut buf, USK_BYTES, SEED_BYTES];
+
+        *usk = self.usk.to_bytes();
+        *seed = self.seed;
+
+        buf
+    }
+
+    fn from_bytes(bytes: &[u8; IMPLICIT_USK_BYTES]) -> CtOption<Self> {
+        let (usk, rid, seed) = array_refs![&bytes, CPA_USK_BYTES, ID_BYTES, SEED_BYTES];
+
+        let usk = crate::pke::cgw::UserSecretKey::from_bytes(usk);
+        let id = Identity(*rid);
+
+        usk.map(|usk| ImplicitUserSecretKey {
+            usk: UserSecretKey { usk, id },
+            seed: *seed,
+        })
+    }
+}
+
+/// User secret key of [`CGWFO`] with the line coefficients of the Miller loop precomputed.
+///
+/// Speeds up decapsulating many ciphertexts using the same user secret key,
+/// see [`CGWFO::decaps_prepared`].
+#[derive(Clone, Debug)]
+pub struct PreparedUserSecretKey {
+    usk: crate::pke::cgw::PreparedUserSecretKey,
+    id: Identity,
+}
+
+impl From<&UserSecretKey> for PreparedUserSecretKey {
+    fn from(usk: &UserSecretKey) -> Self {
+        PreparedUserSecretKey {
+            usk: (&usk.usk).into(),
+            id: usk.id,
+        }
+    }
+}
+
+/// Derives the encryption coins from a message and the identity it is encrypted for (G).
+fn coins(m: &Msg, id: &Identity) -> [u8; 64] {
+    let mut pre_coins = [0u8; MSG_BYTES + ID_BYTES];
+    pre_coins[..MSG_BYTES].copy_from_slice(&m.to_bytes());
+    pre_coins[MSG_BYTES..].copy_from_slice(&id.0);
+
+    sha3_512(&pre_coins)
+}
+
+/// Derives a shared secret from a secret prefix and the ciphertext (H).
+fn bound_secret(prefix: &[u8], c: &CipherText) -> [u8; SS_BYTES] {
+    let mut digest = Shake::v256();
+    digest.update(prefix);
+    digest.update(&c.to_bytes());
+
+    let mut buf = [0u8; SS_BYTES];
+    digest.finalize(&mut buf);
+
+    buf
+}
+
+/// Re-encrypts a decrypted message and explicitly rejects if it does not result in the ciphertext.
+fn reencrypt(
+    pk: &PublicKey,
+    id: &Identity,
+    m: &Msg,
+    c: &CipherText,
+) -> Result<SharedSecret, Error> {
+    let c2 = CGW::encrypt(pk, id, m, &coins(m, id));
+
+    // Can save some time by not doing a constant-time comparison
+    // since we can leak whether the decapsulation succeeds/fails.
+    if c.ct_eq(&c2).into() {
+        Ok(SharedSecret::from(m))
+    } else {
+        Err(Error::Decapsulation)
+    }
+}
+
+/// The CCA2 secure KEM that results by applying the explicit rejection
 /// variant of the Fujisaki-Okamoto transform to the Chen-Gay-Wee IBE scheme.
 #[derive(Clone)]
 pub struct CGWFO;
 
+impl CGWFO {
+    /// Extract user secret keys for many identities using the MSK, see [`CGW::extract_usk_batch`].
+    pub fn extract_usk_batch<R: Rng + CryptoRng>(
+        sk: &SecretKey,
+        ids: &[Identity],
+        rng: &mut R,
+    ) -> Vec<UserSecretKey> {
+        CGW::extract_usk_batch(sk, ids, rng)
+            .into_iter()
+            .zip(ids.iter())
+            .map(|(usk, id)| UserSecretKey { usk, id: *id })
+            .collect()
+    }
+
+    /// Encapsulate a shared secret using a prepared master public key and an identity.
+    pub fn encaps_prepared<R: Rng + CryptoRng>(
+        pk: &PreparedPublicKey,
+        id: &Identity,
+        rng: &mut R,
+    ) -> (CipherText, SharedSecret) {
+        let m = Msg::random(rng);
+        let ct = CGW::encrypt_prepared(pk, id, &m, &coins(&m, id));
+
+        (ct, SharedSecret::from(&m))
+    }
+
+    /// Decapsulate a shared secret from the ciphertext using a prepared user secret key.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if no master public key is provided, see [`IBKEM::decaps`].
+    ///
+    /// # Errors
+    ///
+    /// This function returns an [`Error::Decapsulation`] when an illegitimate ciphertext is encountered (explicit rejection).
+    pub fn decaps_prepared(
+        opk: Option<&PublicKey>,
+        usk: &PreparedUserSecretKey,
+        c: &CipherText,
+    ) -> Result<SharedSecret, Error> {
+        let pk = opk.unwrap();
+
+        let m = CGW::decrypt_prepared(&usk.usk, c);
+
+        reencrypt(pk, &usk.id, &m, c)
+    }
+
+    /// Decapsulate shared secrets from many ciphertexts for the same user secret key.
+    ///
+    /// Prepares the user secret key once, and normalizes the group elements of all
+    /// re-encryptions at once. The Miller loops and final exponentiations are still
+    /// computed per ciphertext, since every ciphertext results in its own target group element.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if no master public key is provided, see [`IBKEM::decaps`].
+    ///
+    /// # Errors
+    ///
+    /// Every result is an [`Error::Decapsulation`] when its ciphertext is illegitimate (explicit rejection).
+    pub fn decaps_batch(
+        opk: Option<&PublicKey>,
+        usk: &UserSecretKey,
+        cts: &[CipherText],
+    ) -> Vec<Result<SharedSecret, Error>> {
+        let pk = opk.unwrap();
+        let prepared = crate::pke::cgw::PreparedUserSecretKey::from(&usk.usk);
+
+        let ms: Vec<Msg> = cts
+            .iter()
+            .map(|c| CGW::decrypt_prepared(&prepared, c))
+            .collect();
+
+        let mut batch = Vec::with_capacity(4 * cts.len());
+        let mut cprimes = Vec::with_capacity(cts.len());
+        for m in ms.iter() {
+            let (points, cprime) = CGW::encrypt_projective(pk, &usk.id, m, &coins(m, &usk.id));
+            batch.extend_from_slice(&points);
+            cprimes.push(cprime);
+        }
+
+        let mut out = alloc::vec![G1Affine::default(); batch.len()];
+        G1Projective::batch_normalize(&batch, &mut out);
+
+        cts.iter()
+            .zip(ms.iter())
+            .zip(out.chunks_exact(4).zip(cprimes))
+            .map(|((c, m), (points, cprime))| {
+                let c2 = CipherText {
+                    c0: [points[0], points[1]],
+                    c1: [points[2], points[3]],
+                    cprime,
+                };
+
+                // As in decapsulation, the comparison does not need to be constant-time.
+                if c.ct_eq(&c2).into() {
+                    Ok(SharedSecret::from(m))
+                } else {
+                    Err(Error::Decapsulation)
+                }
+            })
+            .collect()
+    }
+}
+
 impl IBKEM for CGWFO {
     const IDENTIFIER: &'static str = "cgwfo";
 
@@ -89,10 +304,7 @@ impl IBKEM for CGWFO {
     ) -> UserSecretKey {
         let usk = CGW::extract_usk(None, sk, id, rng);
 
-        UserSecretKey {
-            usk,
-            id: id.clone(),
-        }
+        UserSecretKey { usk, id: *id }
     }
 
     fn encaps<R: Rng + CryptoRng>(
@@ -101,14 +313,7 @@ impl IBKEM for CGWFO {
         rng: &mut R,
     ) -> (CipherText, SharedSecret) {
         let m = Msg::random(rng);
-
-        let mut pre_coins = [0u8; MSG_BYTES + ID_BYTES];
-        pre_coins[..MSG_BYTES].copy_from_slice(&m.to_bytes());
-        pre_coins[MSG_BYTES..].copy_from_slice(&id.0);
-
-        let coins = sha3_512(&pre_coins);
-
-        let ct = CGW::encrypt(pk, id, &m, &coins);
+        let ct = CGW::encrypt(pk, id, &m, &coins(&m, id));
 
         (ct, SharedSecret::from(&m))
     }
@@ -122,7 +327,7 @@ impl IBKEM for CGWFO {
     ///
     /// # Errors
     ///
-    /// This function returns an [`Error::DecapsulationError`] when an illegitimate ciphertext is encountered (explicit rejection).
+    /// This function returns an [`Error::Decapsulation`] when an illegitimate ciphertext is encountered (explicit rejection).
     fn decaps(
         opk: Option<&PublicKey>,
         usk: &UserSecretKey,
@@ -132,21 +337,118 @@ impl IBKEM for CGWFO {
 
         let m = CGW::decrypt(&usk.usk, c);
 
-        let mut pre_coins = [0u8; MSG_BYTES + ID_BYTES];
-        pre_coins[..MSG_BYTES].copy_from_slice(&m.to_bytes());
-        pre_coins[MSG_BYTES..].copy_from_slice(&usk.id.0);
+        reencrypt(pk, &usk.id, &m, c)
+    }
+}
+
+impl MultiRecipient for CGWFO {
+    /// Encapsulate the same random message for every identity.
+    ///
+    /// Since the coins are derived from both the message and the identity,
+    /// each ciphertext uses independent randomness.
+    fn multi_encaps<R: Rng + CryptoRng>(
+        pk: &PublicKey,
+        ids: &[&Identity],
+        rng: &mut R,
+        cts: &mut [CipherText],
+    ) -> Result<SharedSecret, Error> {
+        if ids.len() != cts.len() {
+            return Err(Error::IncorrectSize);
+        }
+
+        let m = Msg::random(rng);
+
+        for (id, ct) in ids.iter().zip(cts.iter_mut()) {
+            *ct = CGW::encrypt(pk, id, &m, &coins(&m, id));
+        }
+
+        Ok(SharedSecret::from(&m))
+    }
+}
+
+/// The CCA2 secure KEM that results by applying the implicit rejection
+/// variant of the Fujisaki-Okamoto transform to the Chen-Gay-Wee IBE scheme.
+///
+/// The shared secret is bound to the ciphertext, hence this variant does not support
+/// multi-recipient encapsulation.
+#[derive(Clone)]
+pub struct CGWFOImplicit;
+
+impl IBKEM for CGWFOImplicit {
+    const IDENTIFIER: &'static str = "cgwfo-ir";
 
-        let coins = sha3_512(&pre_coins);
+    type Pk = PublicKey;
+    type Sk = SecretKey;
+    type Usk = ImplicitUserSecretKey;
+    type Ct = CipherText;
+    type Ss = SharedSecret;
+    type Id = Identity;
 
-        let c2 = CGW::encrypt(pk, &usk.id, &m, &coins);
+    const PK_BYTES: usize = PK_BYTES;
+    const USK_BYTES: usize = IMPLICIT_USK_BYTES;
+    const SK_BYTES: usize = SK_BYTES;
+    const CT_BYTES: usize = CT_BYTES;
+
+    fn setup<R: Rng + CryptoRng>(rng: &mut R) -> (PublicKey, SecretKey) {
+        CGW::setup(rng)
+    }
+
+    fn extract_usk<R: Rng + CryptoRng>(
+        _pk: Option<&PublicKey>,
+        sk: &SecretKey,
+        id: &Identity,
+        rng: &mut R,
+    ) -> ImplicitUserSecretKey {
+        let usk = CGWFO::extract_usk(None, sk, id, rng);
+
+        let mut seed = [0u8; SEED_BYTES];
+        rng.fill(&mut seed);
+
+        ImplicitUserSecretKey { usk, seed }
+    }
+
+    fn encaps<R: Rng + CryptoRng>(
+        pk: &PublicKey,
+        id: &Identity,
+        rng: &mut R,
+    ) -> (CipherText, SharedSecret) {
+        let m = Msg::random(rng);
+        let ct = CGW::encrypt(pk, id, &m, &coins(&m, id));
+        let ss = bound_secret(&m.to_bytes(), &ct);
 
-        // Can save some time by not doing a constant-time comparison
-        // since we can leak whether the decapsulation succeeds/fails.
-        if c.ct_eq(&c2).into() {
-            Ok(SharedSecret::from(&m))
-        } else {
-            Err(Error::Decapsulation)
+        (ct, SharedSecret(ss))
+    }
+
+    /// Decapsulate a shared secret from the ciphertext.
+    ///
+    /// An illegitimate ciphertext results in a pseudorandom shared secret (implicit rejection),
+    /// which is selected in constant time.
+    ///
+    /// # Panics
+    ///
+    /// This scheme **does** requires the master public key due to usage the Fujisaki-Okamoto transform.
+    /// This function panics if no master public key is provided.
+    fn decaps(
+        opk: Option<&PublicKey>,
+        usk: &ImplicitUserSecretKey,
+        c: &CipherText,
+    ) -> Result<SharedSecret, Error> {
+        let pk = opk.unwrap();
+        let id = &usk.usk.id;
+
+        let m = CGW::decrypt(&usk.usk.usk, c);
+        let c2 = CGW::encrypt(pk, id, &m, &coins(&m, id));
+        let valid = c.ct_eq(&c2);
+
+        let accept = bound_secret(&m.to_bytes(), c);
+        let reject = bound_secret(&usk.seed, c);
+
+        let mut ss = [0u8; SS_BYTES];
+        for ((s, a), r) in ss.iter_mut().zip(accept.iter()).zip(reject.iter()) {
+            *s = u8::conditional_select(r, a, valid);
         }
+
+        Ok(SharedSecret(ss))
     }
 }
 
@@ -156,4 +458,116 @@ mod tests {
     use crate::Derive;
 
     test_kem!(CGWFO);
+    test_multi_kem!(CGWFO);
+
+    #[test]
+    fn eq_timed_encaps_decaps() {
+        use crate::kem::TimedIBKEM;
+        use crate::util::Epoch;
+        use std::vec::Vec;
+
+        let mut rng = rand::thread_rng();
+        let (pk, sk) = CGWFO::setup(&mut rng);
+        let attribute = ID1.as_bytes();
+
+        let now = Epoch::weekly(1_792_238_400);
+        let usks: Vec<(Epoch, UserSecretKey)> =
+            CGWFO::extract_usk_range(Some(&pk), &sk, attribute, now..Epoch(now.0 + 4), &mut rng)
+                .collect();
+        assert_eq!(usks.len(), 4);
+
+        let (c, k) = CGWFO::encaps_at(&pk, attribute, now.next(), &mut rng);
+        assert_eq!(CGWFO::decaps(Some(&pk), &usks[1].1, &c).unwrap(), k);
+        assert!(CGWFO::decaps(Some(&pk), &usks[0].1, &c).is_err());
+    }
+
+    #[test]
+    fn eq_encaps_prepared() {
+        let results = perform_default();
+        let prepared = PreparedPublicKey::from(&results.pk);
+        let (c, k) = CGWFO::encaps_prepared(&prepared, &results.kid, &mut rand::thread_rng());
+
+        assert_eq!(
+            CGWFO::decaps(Some(&results.pk), &results.usk, &c).unwrap(),
+            k
+        );
+    }
+
+    #[test]
+    fn eq_extract_usk_batch() {
+        let mut rng = rand::thread_rng();
+        let (pk, sk) = CGWFO::setup(&mut rng);
+        let ids = [ID1, "email:l.botros@cs.ru.nl"].map(Identity::derive_str);
+        let usks = CGWFO::extract_usk_batch(&sk, &ids, &mut rng);
+        assert_eq!(usks.len(), 2);
+
+        for (id, usk) in ids.iter().zip(usks.iter()) {
+            let (c, k) = CGWFO::encaps(&pk, id, &mut rng);
+            assert_eq!(CGWFO::decaps(Some(&pk), usk, &c).unwrap(), k);
+        }
+    }
+
+    #[test]
+    fn eq_decaps_batch() {
+        let mut rng = rand::thread_rng();
+        let results = perform_default();
+        let other = Identity::derive_str("email:l.botros@cs.ru.nl");
+
+        let (c1, k1) = CGWFO::encaps(&results.pk, &results.kid, &mut rng);
+        let (c2, _) = CGWFO::encaps(&results.pk, &other, &mut rng);
+
+        let ks = CGWFO::decaps_batch(Some(&results.pk), &results.usk, &[c1, c2, results.c]);
+        assert_eq!(ks.len(), 3);
+        assert_eq!(ks[0].as_ref().unwrap(), &k1);
+        assert!(matches!(ks[1], Err(Error::Decapsulation)));
+        assert_eq!(ks[2].as_ref().unwrap(), &results.k);
+    }
+
+    #[test]
+    fn eq_decaps_prepared() {
+        let results = perform_default();
+        let prepared = PreparedUserSecretKey::from(&results.usk);
+        let (c, _) = CGWFO::encaps(
+            &results.pk,
+            &Identity::derive_str("other"),
+            &mut rand::thread_rng(),
+        );
+
+        assert_eq!(
+            CGWFO::decaps_prepared(Some(&results.pk), &prepared, &results.c).unwrap(),
+            results.k
+        );
+        assert!(CGWFO::decaps_prepared(Some(&results.pk), &prepared, &c).is_err());
+    }
+
+    mod implicit {
+        use super::super::*;
+        use crate::Derive;
+
+        type UserSecretKey = ImplicitUserSecretKey;
+
+        test_kem!(CGWFOImplicit);
+
+        #[test]
+        fn implicit_rejection() {
+            let mut rng = rand::thread_rng();
+            let kid = Identity::derive_str(ID1);
+            let (pk, sk) = CGWFOImplicit::setup(&mut rng);
+            let usk = CGWFOImplicit::extract_usk(Some(&pk), &sk, &kid, &mut rng);
+            let (c, k) = CGWFOImplicit::encaps(&pk, &kid, &mut rng);
+            let (c1, _) = CGWFOImplicit::encaps(&pk, &kid, &mut rng);
+
+            // Mix two valid ciphertexts.
+            let mut bytes = c.to_bytes();
+            bytes[..48].copy_from_slice(&c1.to_bytes()[..48]);
+            let c2 = CipherText::from_bytes(&bytes).unwrap();
+
+            let k2 = CGWFOImplicit::decaps(Some(&pk), &usk, &c2).unwrap();
+            let k3 = CGWFOImplicit::decaps(Some(&pk), &usk, &c2).unwrap();
+
+            assert_ne!(k, k2);
+            assert_eq!(k2, k3);
+            assert!(CGWFO::decaps(Some(&pk), &usk.usk, &c2).is_err());
+        }
+    }
 }
@@ -0,0 +1,159 @@
+//! IND-ID-CCA2 secure hierarchical IBKEM based on the HIBE in [`cgw_hibe`](crate::pke::cgw_hibe).
+//!
+//! CCA security due to a general approach by Fujisaki and Okamoto, as in [`cgw_fo`](crate::kem::cgw_fo).
+//! * From: "[A Modular Analysis of the Fujisaki-Okamoto Transformation](https://eprint.iacr.org/2017/604.pdf)"
+//!
+//! The coins are derived from the message and all levels of the hierarchical identity using sha3_512 (G).
+//! The user secret key of the HIBE already includes the identity, which is needed for re-encryption.
+//! Keys can be delegated without the PKG using [`CGWHIBEFO::delegate`].
+
+use crate::kem::{Error, SharedSecret, IBKEM};
+use crate::pke::cgw_hibe::{CipherText, HierarchicalIdentity, Msg, CGWHIBE, HID_BYTES};
+use crate::pke::IBE;
+use crate::util::*;
+use crate::Compress;
+use group::Group;
+use rand::{CryptoRng, Rng};
+use subtle::ConstantTimeEq;
+
+/// These struct are identical for the CCA KEM.
+pub use crate::pke::cgw_hibe::{
+    PublicKey, SecretKey, UserSecretKey, CT_BYTES, MAX_DEPTH, MSG_BYTES, PK_BYTES, SK_BYTES,
+    USK_BYTES,
+};
+
+/// Derives the encryption coins from a message and the identity it is encrypted for (G).
+fn coins(m: &Msg, id: &HierarchicalIdentity) -> [u8; 64] {
+    let mut pre_coins = [0u8; MSG_BYTES + HID_BYTES];
+    pre_coins[..MSG_BYTES].copy_from_slice(&m.to_bytes());
+    pre_coins[MSG_BYTES] = id.depth() as u8;
+    for (chunk, level) in pre_coins[MSG_BYTES + 1..]
+        .chunks_exact_mut(ID_BYTES)
+        .zip(id.levels())
+    {
+        chunk.copy_from_slice(&level.0);
+    }
+
+    sha3_512(&pre_coins)
+}
+
+/// The CCA2 secure KEM that results by applying the explicit rejection
+/// variant of the Fujisaki-Okamoto transform to the hierarchical Chen-Gay-Wee scheme.
+#[derive(Clone)]
+pub struct CGWHIBEFO;
+
+impl CGWHIBEFO {
+    /// Delegate a user secret key to the child identity `id` one level below it.
+    ///
+    /// Does not require the master secret key. Returns `None` if the key is already at [`MAX_DEPTH`].
+    pub fn delegate<R: Rng + CryptoRng>(
+        pk: &PublicKey,
+        usk: &UserSecretKey,
+        id: &Identity,
+        rng: &mut R,
+    ) -> Option<UserSecretKey> {
+        CGWHIBE::delegate(pk, usk, id, rng)
+    }
+}
+
+impl IBKEM for CGWHIBEFO {
+    const IDENTIFIER: &'static str = "cgw_hibe_fo";
+
+    type Pk = PublicKey;
+    type Sk = SecretKey;
+    type Usk = UserSecretKey;
+    type Ct = CipherText;
+    type Ss = SharedSecret;
+    type Id = HierarchicalIdentity;
+
+    const PK_BYTES: usize = PK_BYTES;
+    const USK_BYTES: usize = USK_BYTES;
+    const SK_BYTES: usize = SK_BYTES;
+    const CT_BYTES: usize = CT_BYTES;
+
+    fn setup<R: Rng + CryptoRng>(rng: &mut R) -> (PublicKey, SecretKey) {
+        CGWHIBE::setup(rng)
+    }
+
+    fn extract_usk<R: Rng + CryptoRng>(
+        _pk: Option<&PublicKey>,
+        sk: &SecretKey,
+        id: &HierarchicalIdentity,
+        rng: &mut R,
+    ) -> UserSecretKey {
+        CGWHIBE::extract_usk(None, sk, id, rng)
+    }
+
+    fn encaps<R: Rng + CryptoRng>(
+        pk: &PublicKey,
+        id: &HierarchicalIdentity,
+        rng: &mut R,
+    ) -> (CipherText, SharedSecret) {
+        let m = Msg::random(rng);
+        let ct = CGWHIBE::encrypt(pk, id, &m, &coins(&m, id));
+
+        (ct, SharedSecret::from(&m))
+    }
+
+    /// Decapsulate a shared secret from the ciphertext.
+    ///
+    /// # Panics
+    ///
+    /// This scheme **does** requires the master public key due to usage the Fujisaki-Okamoto transform.
+    /// This function panics if no master public key is provided.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an [`Error::Decapsulation`] when an illegitimate ciphertext is encountered (explicit rejection).
+    fn decaps(
+        opk: Option<&PublicKey>,
+        usk: &UserSecretKey,
+        c: &CipherText,
+    ) -> Result<SharedSecret, Error> {
+        let pk = opk.unwrap();
+
+        let m = CGWHIBE::decrypt(usk, c);
+        let c2 = CGWHIBE::encrypt(pk, usk.id(), &m, &coins(&m, usk.id()));
+
+        if c.ct_eq(&c2).into() {
+            Ok(SharedSecret::from(&m))
+        } else {
+            Err(Error::Decapsulation)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Derive;
+
+    type Identity = HierarchicalIdentity;
+
+    test_kem!(CGWHIBEFO);
+
+    #[test]
+    fn eq_delegate_decaps() {
+        let mut rng = rand::thread_rng();
+        let (pk, sk) = CGWHIBEFO::setup(&mut rng);
+
+        let dept = HierarchicalIdentity::derive_str("org/dept");
+        let user = HierarchicalIdentity::derive_str("org/dept/user");
+
+        let usk_dept = CGWHIBEFO::extract_usk(None, &sk, &dept, &mut rng);
+        let usk_user = CGWHIBEFO::delegate(
+            &pk,
+            &usk_dept,
+            &crate::util::Identity::derive_str("user"),
+            &mut rng,
+        )
+        .unwrap();
+
+        let (c, k) = CGWHIBEFO::encaps(&pk, &user, &mut rng);
+        assert_eq!(CGWHIBEFO::decaps(Some(&pk), &usk_user, &c).unwrap(), k);
+        assert!(matches!(
+            CGWHIBEFO::decaps(Some(&pk), &usk_dept, &c),
+            Err(Error::Decapsulation)
+        ));
+    }
+}
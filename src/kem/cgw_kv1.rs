@@ -4,28 +4,38 @@
 //! CCA security due to a generalized approach from Kiltz & Vahlis.
 //!  * From: "[CCA2 Secure IBE: Standard Model Efficiency through Authenticated Symmetric Encryption](https://link.springer.com/chapter/10.1007/978-3-540-79263-5_14)"
 //!  * Published in: CT-RSA, 2008
+//!
+//! This scheme does not implement [`MultiRecipient`](crate::kem::MultiRecipient).
+//! The shared secret is derived from the encapsulation randomness itself, and reusing that
+//! randomness for three or more identities allows an adversary to recombine the ciphertexts
+//! into a valid ciphertext for an identity of its own choice.
 
 use crate::kem::{Error, SharedSecret, IBKEM};
 use crate::util::*;
 use crate::Compress;
+use alloc::vec::Vec;
+use arrayref::{array_refs, mut_array_refs};
+use core::convert::TryInto;
 use irmaseal_curve::{
     multi_miller_loop, pairing, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt,
     Scalar,
 };
 use rand::{CryptoRng, Rng};
-use subtle::CtOption;
+use subtle::{Choice, CtOption};
 
 /// Size of the compressed master public key in bytes.
 pub const PK_BYTES: usize = 8 * G1_BYTES + GT_BYTES;
 
 /// Size of the compressed master secret key in bytes.
-pub const SK_BYTES: usize = 14 * SCALAR_BYTES;
+pub const SK_BYTES: usize = 16 * SCALAR_BYTES;
 
 /// Size of the compressed user secret key in bytes.
 pub const USK_BYTES: usize = 6 * G2_BYTES;
 
 /// Size of the compressed ciphertext key in bytes.
-pub const CT_BYTES: usize = 4 * G1_BYTES;
+///
+/// The ciphertext includes the 32-byte key of the RPC hash.
+pub const CT_BYTES: usize = 4 * G1_BYTES + 32;
 
 /// Public key parameters generated by the PKG used to encaps messages.
 /// Also known as MPK.
@@ -58,6 +68,62 @@ pub struct UserSecretKey {
     d2: [G2Affine; 2], // K'_i,2
 }
 
+/// Public key with fixed-base tables for all its elements.
+///
+/// Speeds up encapsulating many shared secrets using the same public key,
+/// see [`CGWKV1::encaps_prepared`]. Takes about 1.7 MiB of memory.
+#[derive(Clone, Debug)]
+pub struct PreparedPublicKey {
+    a_1: [FixedBase<G1Projective>; 2],
+    w0ta_1: [FixedBase<G1Projective>; 2],
+    w1ta_1: [FixedBase<G1Projective>; 2],
+    wprime_1: [FixedBase<G1Projective>; 2],
+    kta_t: FixedBase<Gt>,
+}
+
+impl From<&PublicKey> for PreparedPublicKey {
+    fn from(pk: &PublicKey) -> Self {
+        let prepare = |x: &[G1Affine; 2]| {
+            [
+                FixedBase::new(&G1Projective::from(x[0])),
+                FixedBase::new(&G1Projective::from(x[1])),
+            ]
+        };
+
+        PreparedPublicKey {
+            a_1: prepare(&pk.a_1),
+            w0ta_1: prepare(&pk.w0ta_1),
+            w1ta_1: prepare(&pk.w1ta_1),
+            wprime_1: prepare(&pk.wprime_1),
+            kta_t: FixedBase::new(&pk.kta_t),
+        }
+    }
+}
+
+/// User secret key with the line coefficients of the Miller loop precomputed.
+///
+/// Speeds up decapsulating many ciphertexts using the same user secret key,
+/// see [`CGWKV1::decaps_prepared`].
+///
+/// Since decapsulation pairs with K'_i,1 + y' K'_i,2 for a ciphertext-dependent y',
+/// both components are prepared separately and paired with C_0 and y' C_0, respectively.
+#[derive(Clone, Debug)]
+pub struct PreparedUserSecretKey {
+    d0: [G2Prepared; 2],
+    d1: [G2Prepared; 2],
+    d2: [G2Prepared; 2],
+}
+
+impl From<&UserSecretKey> for PreparedUserSecretKey {
+    fn from(usk: &UserSecretKey) -> Self {
+        PreparedUserSecretKey {
+            d0: [G2Prepared::from(usk.d0[0]), G2Prepared::from(usk.d0[1])],
+            d1: [G2Prepared::from(usk.d1[0]), G2Prepared::from(usk.d1[1])],
+            d2: [G2Prepared::from(usk.d2[0]), G2Prepared::from(usk.d2[1])],
+        }
+    }
+}
+
 /// Encrypted message. Can only be decapsed with a corresponding user secret key.
 /// Also known as CT_{id}
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
@@ -71,6 +137,155 @@ pub struct CipherText {
 #[derive(Clone)]
 pub struct CGWKV1;
 
+impl CGWKV1 {
+    /// Extract user secret keys for many identities using the MSK.
+    ///
+    /// Uses a fixed-base table of the generator, and normalizes all keys using one inversion.
+    pub fn extract_usk_batch<R: Rng + CryptoRng>(
+        sk: &SecretKey,
+        ids: &[Identity],
+        rng: &mut R,
+    ) -> Vec<UserSecretKey> {
+        let g2 = FixedBase::new(&G2Projective::generator());
+
+        let batch: Vec<G2Projective> = ids
+            .iter()
+            .flat_map(|v| usk_exponents(sk, v, rng).map(|e| g2.mul(&e)))
+            .collect();
+        let mut out = alloc::vec![G2Affine::default(); batch.len()];
+        G2Projective::batch_normalize(&batch, &mut out);
+
+        out.chunks_exact(6).map(usk_from_affine).collect()
+    }
+
+    /// Encapsulate a shared secret using a prepared master public key and an identity.
+    pub fn encaps_prepared<R: Rng + CryptoRng>(
+        pk: &PreparedPublicKey,
+        id: &Identity,
+        rng: &mut R,
+    ) -> (CipherText, SharedSecret) {
+        let s = rand_scalar(rng);
+        let k = pk.kta_t.mul(&s);
+
+        let x = id.to_scalar();
+        let mut c0 = [G1Affine::default(); 2];
+        G1Projective::batch_normalize(&[pk.a_1[0].mul(&s), pk.a_1[1].mul(&s)], &mut c0);
+
+        let mut smallk = [0u8; 32];
+        rng.fill_bytes(&mut smallk);
+
+        let xprime = rpc(&smallk, &[c0[0], c0[1]]);
+        let (sx, sxprime) = (s * x, s * xprime);
+
+        let mut c1 = [G1Affine::default(); 2];
+        G1Projective::batch_normalize(
+            &[
+                pk.w0ta_1[0].mul(&s) + pk.w1ta_1[0].mul(&sx) + pk.wprime_1[0].mul(&sxprime),
+                pk.w0ta_1[1].mul(&s) + pk.w1ta_1[1].mul(&sx) + pk.wprime_1[1].mul(&sxprime),
+            ],
+            &mut c1,
+        );
+
+        (CipherText { c0, c1, k: smallk }, SharedSecret::from(&k))
+    }
+
+    /// Derive the same SharedSecret from the CipherText using a PreparedUserSecretKey.
+    ///
+    /// # Errors
+    ///
+    /// This operation always implicitly rejects ciphertexts and therefore never errors.
+    pub fn decaps_prepared(
+        _pk: Option<&PublicKey>,
+        usk: &PreparedUserSecretKey,
+        ct: &CipherText,
+    ) -> Result<SharedSecret, Error> {
+        let mut c0y = [G1Affine::default(); 2];
+        G1Projective::batch_normalize(&c0y_projective(ct), &mut c0y);
+
+        Ok(decaps_with(usk, ct, &c0y))
+    }
+
+    /// Derive the SharedSecrets from many CipherTexts using the same UserSecretKey.
+    ///
+    /// Prepares the user secret key once, and normalizes y' C_0 of all ciphertexts at once.
+    /// The Miller loops and final exponentiations are still computed per ciphertext,
+    /// since every ciphertext results in its own target group element.
+    ///
+    /// # Errors
+    ///
+    /// This operation always implicitly rejects ciphertexts and therefore never errors.
+    pub fn decaps_batch(
+        _pk: Option<&PublicKey>,
+        usk: &UserSecretKey,
+        cts: &[CipherText],
+    ) -> Vec<Result<SharedSecret, Error>> {
+        let usk = PreparedUserSecretKey::from(usk);
+
+        let batch: Vec<G1Projective> = cts.iter().flat_map(c0y_projective).collect();
+        let mut c0y = alloc::vec![G1Affine::default(); batch.len()];
+        G1Projective::batch_normalize(&batch, &mut c0y);
+
+        cts.iter()
+            .zip(c0y.chunks_exact(2))
+            .map(|(ct, c0y)| Ok(decaps_with(&usk, ct, c0y)))
+            .collect()
+    }
+}
+
+/// Computes the discrete logarithms of the components of a user secret key.
+fn usk_exponents<R: Rng + CryptoRng>(sk: &SecretKey, v: &Identity, rng: &mut R) -> [Scalar; 6] {
+    let r = rand_scalar(rng);
+    let id = v.to_scalar();
+
+    let br = [sk.b[0] * r, sk.b[1] * r];
+
+    [
+        br[0],
+        br[1],
+        sk.k[0]
+            - (br[0] * sk.w0[0][0]
+                + br[1] * sk.w0[0][1]
+                + id * (br[0] * sk.w1[0][0] + br[1] * sk.w1[0][1])),
+        sk.k[1]
+            - (br[0] * sk.w0[1][0]
+                + br[1] * sk.w0[1][1]
+                + id * (br[0] * sk.w1[1][0] + br[1] * sk.w1[1][1])),
+        -(br[0] * sk.wprime[0][0] + br[1] * sk.wprime[0][1]),
+        -(br[0] * sk.wprime[1][0] + br[1] * sk.wprime[1][1]),
+    ]
+}
+
+/// Assembles a user secret key from its normalized components.
+fn usk_from_affine(out: &[G2Affine]) -> UserSecretKey {
+    UserSecretKey {
+        d0: [out[0], out[1]], // K_i
+        d1: [out[2], out[3]], // K'_i,0
+        d2: [out[4], out[5]], // K'_i,1
+    }
+}
+
+/// Computes y' C_0 of a ciphertext, where y' is the RPC hash of C_0.
+fn c0y_projective(ct: &CipherText) -> [G1Projective; 2] {
+    let yprime = rpc(&ct.k, &[ct.c0[0], ct.c0[1]]);
+
+    [ct.c0[0] * yprime, ct.c0[1] * yprime]
+}
+
+/// Derives the shared secret from a ciphertext using a prepared user secret key and y' C_0.
+fn decaps_with(usk: &PreparedUserSecretKey, ct: &CipherText, c0y: &[G1Affine]) -> SharedSecret {
+    let m = multi_miller_loop(&[
+        (&ct.c0[0], &usk.d1[0]),
+        (&ct.c0[1], &usk.d1[1]),
+        (&c0y[0], &usk.d2[0]),
+        (&c0y[1], &usk.d2[1]),
+        (&ct.c1[0], &usk.d0[0]),
+        (&ct.c1[1], &usk.d0[1]),
+    ])
+    .final_exponentiation();
+
+    SharedSecret::from(&m)
+}
+
 impl IBKEM for CGWKV1 {
     const IDENTIFIER: &'static str = "cgwkv1";
 
@@ -165,33 +380,12 @@ impl IBKEM for CGWKV1 {
         rng: &mut R,
     ) -> UserSecretKey {
         let g2 = G2Affine::generator();
-        let r = rand_scalar(rng);
-        let id = v.to_scalar();
-
-        let br = [sk.b[0] * r, sk.b[1] * r];
 
-        let batch = [
-            g2 * br[0],
-            g2 * br[1],
-            g2 * (sk.k[0]
-                - (br[0] * sk.w0[0][0]
-                    + br[1] * sk.w0[0][1]
-                    + id * (br[0] * sk.w1[0][0] + br[1] * sk.w1[0][1]))),
-            g2 * (sk.k[1]
-                - (br[0] * sk.w0[1][0]
-                    + br[1] * sk.w0[1][1]
-                    + id * (br[0] * sk.w1[1][0] + br[1] * sk.w1[1][1]))),
-            g2 * -(br[0] * sk.wprime[0][0] + br[1] * sk.wprime[0][1]),
-            g2 * -(br[0] * sk.wprime[1][0] + br[1] * sk.wprime[1][1]),
-        ];
+        let batch = usk_exponents(sk, v, rng).map(|e| g2 * e);
         let mut out = [G2Affine::default(); 6];
         G2Projective::batch_normalize(&batch, &mut out);
 
-        UserSecretKey {
-            d0: [out[0], out[1]], // K_i
-            d1: [out[2], out[3]], // K'_i,0
-            d2: [out[4], out[5]], // K'_i,1
-        }
+        usk_from_affine(&out)
     }
 
     fn encaps<R: Rng + CryptoRng>(
@@ -210,13 +404,24 @@ impl IBKEM for CGWKV1 {
 
         let xprime = rpc(&smallk, &[c0[0], c0[1]]);
 
-        // TODO: can optimize with left-to-right square-and-multiply.
-        let c1: [G1Affine; 2] = [
-            ((pk.w0ta_1[0] * s) + (pk.w1ta_1[0] * (s * x)) + (pk.wprime_1[0] * (s * xprime)))
-                .into(),
-            ((pk.w0ta_1[1] * s) + (pk.w1ta_1[1] * (s * x)) + (pk.wprime_1[1] * (s * xprime)))
-                .into(),
-        ];
+        let (sx, sxprime) = (s * x, s * xprime);
+
+        let mut c1 = [G1Affine::default(); 2];
+        G1Projective::batch_normalize(
+            &[
+                multi_mul_g1(&[
+                    (pk.w0ta_1[0], s),
+                    (pk.w1ta_1[0], sx),
+                    (pk.wprime_1[0], sxprime),
+                ]),
+                multi_mul_g1(&[
+                    (pk.w0ta_1[1], s),
+                    (pk.w1ta_1[1], sx),
+                    (pk.wprime_1[1], sxprime),
+                ]),
+            ],
+            &mut c1,
+        );
 
         (CipherText { c0, c1, k: smallk }, SharedSecret::from(&k))
     }
@@ -252,11 +457,59 @@ impl Compress for PublicKey {
     type Output = [u8; Self::OUTPUT_SIZE];
 
     fn to_bytes(&self) -> [u8; PK_BYTES] {
-        unimplemented!();
+        let mut res = [0u8; PK_BYTES];
+
+        for i in 0..2 {
+            let x = i * G1_BYTES;
+            let y = x + G1_BYTES;
+            res[x..y].copy_from_slice(&self.a_1[i].to_compressed());
+            res[96 + x..96 + y].copy_from_slice(&self.w0ta_1[i].to_compressed());
+            res[192 + x..192 + y].copy_from_slice(&self.w1ta_1[i].to_compressed());
+            res[288 + x..288 + y].copy_from_slice(&self.wprime_1[i].to_compressed());
+        }
+        res[384..].copy_from_slice(&self.kta_t.to_compressed());
+
+        res
     }
 
-    fn from_bytes(_bytes: &[u8; PK_BYTES]) -> CtOption<Self> {
-        unimplemented!();
+    fn from_bytes(bytes: &[u8; PK_BYTES]) -> CtOption<Self> {
+        let mut a_1 = [G1Affine::default(); 2];
+        let mut w0ta_1 = [G1Affine::default(); 2];
+        let mut w1ta_1 = [G1Affine::default(); 2];
+        let mut wprime_1 = [G1Affine::default(); 2];
+        let mut kta_t = Gt::default();
+
+        let mut is_some = Choice::from(1u8);
+        for i in 0..2 {
+            let x = i * G1_BYTES;
+            let y = x + G1_BYTES;
+            is_some &= G1Affine::from_compressed(bytes[x..y].try_into().unwrap())
+                .map(|el| a_1[i] = el)
+                .is_some();
+            is_some &= G1Affine::from_compressed(bytes[96 + x..96 + y].try_into().unwrap())
+                .map(|el| w0ta_1[i] = el)
+                .is_some();
+            is_some &= G1Affine::from_compressed(bytes[192 + x..192 + y].try_into().unwrap())
+                .map(|el| w1ta_1[i] = el)
+                .is_some();
+            is_some &= G1Affine::from_compressed(bytes[288 + x..288 + y].try_into().unwrap())
+                .map(|el| wprime_1[i] = el)
+                .is_some();
+        }
+        is_some &= Gt::from_compressed(bytes[384..].try_into().unwrap())
+            .map(|el| kta_t = el)
+            .is_some();
+
+        CtOption::new(
+            PublicKey {
+                a_1,
+                w0ta_1,
+                w1ta_1,
+                wprime_1,
+                kta_t,
+            },
+            is_some,
+        )
     }
 }
 
@@ -265,11 +518,69 @@ impl Compress for SecretKey {
     type Output = [u8; Self::OUTPUT_SIZE];
 
     fn to_bytes(&self) -> [u8; SK_BYTES] {
-        unimplemented!();
+        let mut res = [0u8; SK_BYTES];
+        let (mut x, mut y);
+
+        for i in 0..2 {
+            x = i * SCALAR_BYTES;
+            y = x + SCALAR_BYTES;
+            res[x..y].copy_from_slice(&self.b[i].to_bytes());
+            res[64 + x..64 + y].copy_from_slice(&self.k[i].to_bytes());
+
+            for j in 0..2 {
+                x = (i * 2 + j) * SCALAR_BYTES;
+                y = x + SCALAR_BYTES;
+                res[128 + x..128 + y].copy_from_slice(&self.w0[i][j].to_bytes());
+                res[256 + x..256 + y].copy_from_slice(&self.w1[i][j].to_bytes());
+                res[384 + x..384 + y].copy_from_slice(&self.wprime[i][j].to_bytes());
+            }
+        }
+
+        res
     }
 
-    fn from_bytes(_bytes: &[u8; SK_BYTES]) -> CtOption<Self> {
-        unimplemented!();
+    fn from_bytes(bytes: &[u8; SK_BYTES]) -> CtOption<Self> {
+        let mut b = [Scalar::default(); 2];
+        let mut k = [Scalar::default(); 2];
+        let mut w0 = [[Scalar::default(); 2]; 2];
+        let mut w1 = [[Scalar::default(); 2]; 2];
+        let mut wprime = [[Scalar::default(); 2]; 2];
+
+        let mut is_some = Choice::from(1u8);
+        for i in 0..2 {
+            let x = i * SCALAR_BYTES;
+            let y = x + SCALAR_BYTES;
+            is_some &= Scalar::from_bytes(&bytes[x..y].try_into().unwrap())
+                .map(|s| b[i] = s)
+                .is_some();
+            is_some &= Scalar::from_bytes(&bytes[64 + x..64 + y].try_into().unwrap())
+                .map(|s| k[i] = s)
+                .is_some();
+            for j in 0..2 {
+                let x = (i * 2 + j) * SCALAR_BYTES;
+                let y = x + SCALAR_BYTES;
+                is_some &= Scalar::from_bytes(&bytes[128 + x..128 + y].try_into().unwrap())
+                    .map(|s| w0[i][j] = s)
+                    .is_some();
+                is_some &= Scalar::from_bytes(&bytes[256 + x..256 + y].try_into().unwrap())
+                    .map(|s| w1[i][j] = s)
+                    .is_some();
+                is_some &= Scalar::from_bytes(&bytes[384 + x..384 + y].try_into().unwrap())
+                    .map(|s| wprime[i][j] = s)
+                    .is_some();
+            }
+        }
+
+        CtOption::new(
+            SecretKey {
+                b,
+                k,
+                w0,
+                w1,
+                wprime,
+            },
+            is_some,
+        )
     }
 }
 
@@ -278,11 +589,46 @@ impl Compress for UserSecretKey {
     type Output = [u8; Self::OUTPUT_SIZE];
 
     fn to_bytes(&self) -> [u8; USK_BYTES] {
-        unimplemented!();
+        let mut res = [0u8; USK_BYTES];
+        let (d00, d01, d10, d11, d20, d21) =
+            mut_array_refs![&mut res, G2_BYTES, G2_BYTES, G2_BYTES, G2_BYTES, G2_BYTES, G2_BYTES];
+
+        *d00 = self.d0[0].to_compressed();
+        *d01 = self.d0[1].to_compressed();
+        *d10 = self.d1[0].to_compressed();
+        *d11 = self.d1[1].to_compressed();
+        *d20 = self.d2[0].to_compressed();
+        *d21 = self.d2[1].to_compressed();
+
+        res
     }
 
-    fn from_bytes(_bytes: &[u8; USK_BYTES]) -> CtOption<Self> {
-        unimplemented!();
+    fn from_bytes(bytes: &[u8; USK_BYTES]) -> CtOption<Self> {
+        let (d00, d01, d10, d11, d20, d21) =
+            array_refs![bytes, G2_BYTES, G2_BYTES, G2_BYTES, G2_BYTES, G2_BYTES, G2_BYTES];
+
+        let d00 = G2Affine::from_compressed(d00);
+        let d01 = G2Affine::from_compressed(d01);
+        let d10 = G2Affine::from_compressed(d10);
+        let d11 = G2Affine::from_compressed(d11);
+        let d20 = G2Affine::from_compressed(d20);
+        let d21 = G2Affine::from_compressed(d21);
+
+        d00.and_then(|d00| {
+            d01.and_then(|d01| {
+                d10.and_then(|d10| {
+                    d11.and_then(|d11| {
+                        d20.and_then(|d20| {
+                            d21.map(|d21| UserSecretKey {
+                                d0: [d00, d01],
+                                d1: [d10, d11],
+                                d2: [d20, d21],
+                            })
+                        })
+                    })
+                })
+            })
+        })
     }
 }
 
@@ -291,11 +637,39 @@ impl Compress for CipherText {
     type Output = [u8; Self::OUTPUT_SIZE];
 
     fn to_bytes(&self) -> [u8; CT_BYTES] {
-        unimplemented!();
+        let mut res = [0u8; CT_BYTES];
+        let (c00, c01, c10, c11, k) =
+            mut_array_refs![&mut res, G1_BYTES, G1_BYTES, G1_BYTES, G1_BYTES, 32];
+
+        *c00 = self.c0[0].to_compressed();
+        *c01 = self.c0[1].to_compressed();
+        *c10 = self.c1[0].to_compressed();
+        *c11 = self.c1[1].to_compressed();
+        *k = self.k;
+
+        res
     }
 
-    fn from_bytes(_bytes: &[u8; CT_BYTES]) -> CtOption<Self> {
-        unimplemented!();
+    fn from_bytes(bytes: &[u8; CT_BYTES]) -> CtOption<Self> {
+        let (c00, c01, c10, c11, k) =
+            array_refs![bytes, G1_BYTES, G1_BYTES, G1_BYTES, G1_BYTES, 32];
+
+        let c00 = G1Affine::from_compressed(c00);
+        let c01 = G1Affine::from_compressed(c01);
+        let c10 = G1Affine::from_compressed(c10);
+        let c11 = G1Affine::from_compressed(c11);
+
+        c00.and_then(|c00| {
+            c01.and_then(|c01| {
+                c10.and_then(|c10| {
+                    c11.map(|c11| CipherText {
+                        c0: [c00, c01],
+                        c1: [c10, c11],
+                        k: *k,
+                    })
+                })
+            })
+        })
     }
 }
 
@@ -305,4 +679,57 @@ mod tests {
     use crate::Derive;
 
     test_kem!(CGWKV1);
+
+    #[test]
+    fn eq_encaps_prepared() {
+        let results = perform_default();
+        let prepared = PreparedPublicKey::from(&results.pk);
+        let (c, k) = CGWKV1::encaps_prepared(&prepared, &results.kid, &mut rand::thread_rng());
+
+        assert_eq!(CGWKV1::decaps(None, &results.usk, &c).unwrap(), k);
+    }
+
+    #[test]
+    fn eq_extract_usk_batch() {
+        let mut rng = rand::thread_rng();
+        let (pk, sk) = CGWKV1::setup(&mut rng);
+        let ids = [ID1, "email:l.botros@cs.ru.nl"].map(Identity::derive_str);
+        let usks = CGWKV1::extract_usk_batch(&sk, &ids, &mut rng);
+        assert_eq!(usks.len(), 2);
+
+        for (id, usk) in ids.iter().zip(usks.iter()) {
+            let (c, k) = CGWKV1::encaps(&pk, id, &mut rng);
+            assert_eq!(CGWKV1::decaps(None, usk, &c).unwrap(), k);
+        }
+    }
+
+    #[test]
+    fn eq_decaps_batch() {
+        let mut rng = rand::thread_rng();
+        let results = perform_default();
+        let other = Identity::derive_str("email:l.botros@cs.ru.nl");
+
+        let (c1, k1) = CGWKV1::encaps(&results.pk, &results.kid, &mut rng);
+        let (c2, k2) = CGWKV1::encaps(&results.pk, &other, &mut rng);
+
+        let ks = CGWKV1::decaps_batch(None, &results.usk, &[results.c, c1, c2]);
+        assert_eq!(ks.len(), 3);
+        assert_eq!(ks[0].as_ref().unwrap(), &results.k);
+        assert_eq!(ks[1].as_ref().unwrap(), &k1);
+        assert_ne!(ks[2].as_ref().unwrap(), &k2);
+        assert!(CGWKV1::decaps_batch(None, &results.usk, &[]).is_empty());
+    }
+
+    #[test]
+    fn eq_decaps_prepared() {
+        let results = perform_default();
+        let prepared = PreparedUserSecretKey::from(&results.usk);
+        let (c, k) = CGWKV1::encaps(&results.pk, &results.kid, &mut rand::thread_rng());
+
+        assert_eq!(
+            CGWKV1::decaps_prepared(None, &prepared, &results.c).unwrap(),
+            results.k
+        );
+        assert_eq!(CGWKV1::decaps_prepared(None, &prepared, &c).unwrap(), k);
+    }
 }
@@ -0,0 +1,217 @@
+//! Predicate encodings, used to instantiate the generic scheme in [`pe_kv`](crate::kem::pe_kv).
+//!
+//! A predicate encoding for a predicate P(x, y) consists of linear maps over Z_p:
+//! * sE_x: Z_p^n → Z_p^|s|, encoding the parameters w ∈ Z_p^n for a ciphertext attribute x,
+//! * rE_y: Z_p^n → Z_p^|r| and kE_y: Z_p → Z_p^|r|, encoding the parameters and a secret α for a key attribute y,
+//! * sD_x,y: Z_p^|s| → Z_p and rD_x,y: Z_p^|r| → Z_p, used during decryption.
+//!
+//! Whenever P(x, y) holds, sD_x,y(sE_x(w)) = rD_x,y(rE_y(w)) and rD_x,y(kE_y(α)) = α.
+//! Otherwise, α is hidden given sE_x(w) and kE_y(α) + rE_y(w).
+//! * From: "[Improved Dual System ABE in Prime-Order Groups via Predicate Encodings](https://link.springer.com/chapter/10.1007/978-3-540-79263-5_14)"
+//!
+//! The maps are given by their matrices, every row of a matrix yields one component.
+
+use crate::util::*;
+use crate::CompressVar;
+use alloc::vec::Vec;
+use irmaseal_curve::Scalar;
+
+/// Predicate encoding for a predicate P(x, y).
+pub trait PredicateEncoding {
+    /// Identifier of the scheme that results from the encoding.
+    const IDENTIFIER: &'static str;
+
+    /// Number of parameters n.
+    const PARAMS: usize;
+
+    /// Ciphertext attribute x.
+    type X: Clone + CompressVar;
+
+    /// Key attribute y.
+    type Y: Clone + CompressVar;
+
+    /// Evaluates the predicate P(x, y).
+    fn predicate(x: &Self::X, y: &Self::Y) -> bool;
+
+    /// Matrix of sE_x, of size |s| by n.
+    fn s_e(x: &Self::X) -> Vec<Vec<Scalar>>;
+
+    /// Matrix of rE_y, of size |r| by n.
+    fn r_e(y: &Self::Y) -> Vec<Vec<Scalar>>;
+
+    /// Vector of kE_y, of size |r|.
+    fn k_e(y: &Self::Y) -> Vec<Scalar>;
+
+    /// Vector of sD_x,y, of size |s|.
+    fn s_d(x: &Self::X, y: &Self::Y) -> Vec<Scalar>;
+
+    /// Vector of rD_x,y, of size |r|.
+    fn r_d(x: &Self::X, y: &Self::Y) -> Vec<Scalar>;
+}
+
+/// Encoding for identity-based encryption, P(x, y) holds iff x = y.
+///
+/// Uses w = (w_0, w_1), sE_x(w) = w_0 + x w_1, rE_y(w) = w_0 + y w_1 and kE_y(α) = α.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IbeEncoding;
+
+impl PredicateEncoding for IbeEncoding {
+    const IDENTIFIER: &'static str = "pe_kv_ibe";
+    const PARAMS: usize = 2;
+
+    type X = Identity;
+    type Y = Identity;
+
+    fn predicate(x: &Identity, y: &Identity) -> bool {
+        x == y
+    }
+
+    fn s_e(x: &Identity) -> Vec<Vec<Scalar>> {
+        vec![vec![Scalar::one(), x.to_scalar()]]
+    }
+
+    fn r_e(y: &Identity) -> Vec<Vec<Scalar>> {
+        vec![vec![Scalar::one(), y.to_scalar()]]
+    }
+
+    fn k_e(_y: &Identity) -> Vec<Scalar> {
+        vec![Scalar::one()]
+    }
+
+    fn s_d(_x: &Identity, _y: &Identity) -> Vec<Scalar> {
+        vec![Scalar::one()]
+    }
+
+    fn r_d(_x: &Identity, _y: &Identity) -> Vec<Scalar> {
+        vec![Scalar::one()]
+    }
+}
+
+/// Encoding for zero inner-product encryption of dimension N,
+/// P(x, y) holds iff <x, y> = 0.
+///
+/// Uses w = (w_0, u) with u ∈ Z_p^N, sE_x(w) = w_0 x + u, rE_y(w) = <y, u> and kE_y(α) = α.
+/// Decryption uses sD_x,y = y and rD_x,y = 1.
+///
+/// Broadcast encryption and, e.g., polynomial evaluation can be reduced to this predicate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InnerProductEncoding<const N: usize>;
+
+impl<const N: usize> InnerProductEncoding<N> {
+    /// Identifier of the scheme, which includes the dimension.
+    const ID: &'static IdentifierBuf = &IdentifierBuf::new().push_str("pe_kv_ipe_").push_usize(N);
+}
+
+impl<const N: usize> PredicateEncoding for InnerProductEncoding<N> {
+    const IDENTIFIER: &'static str = Self::ID.as_str();
+    const PARAMS: usize = N + 1;
+
+    type X = [Scalar; N];
+    type Y = [Scalar; N];
+
+    fn predicate(x: &[Scalar; N], y: &[Scalar; N]) -> bool {
+        x.iter()
+            .zip(y.iter())
+            .fold(Scalar::zero(), |acc, (a, b)| acc + a * b)
+            == Scalar::zero()
+    }
+
+    fn s_e(x: &[Scalar; N]) -> Vec<Vec<Scalar>> {
+        (0..N)
+            .map(|t| {
+                let mut row = vec![Scalar::zero(); N + 1];
+                row[0] = x[t];
+                row[t + 1] = Scalar::one();
+                row
+            })
+            .collect()
+    }
+
+    fn r_e(y: &[Scalar; N]) -> Vec<Vec<Scalar>> {
+        let mut row = vec![Scalar::zero()];
+        row.extend_from_slice(y);
+
+        vec![row]
+    }
+
+    fn k_e(_y: &[Scalar; N]) -> Vec<Scalar> {
+        vec![Scalar::one()]
+    }
+
+    fn s_d(_x: &[Scalar; N], y: &[Scalar; N]) -> Vec<Scalar> {
+        y.to_vec()
+    }
+
+    fn r_d(_x: &[Scalar; N], _y: &[Scalar; N]) -> Vec<Scalar> {
+        vec![Scalar::one()]
+    }
+}
+
+impl<const N: usize> CompressVar for [Scalar; N] {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.iter().flat_map(|x| x.to_bytes()).collect()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let mut res = [Scalar::zero(); N];
+        for x in res.iter_mut() {
+            *x = r.read_scalar()?;
+        }
+
+        r.finish(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Derive;
+
+    /// Checks sD_x,y(sE_x(w)) = rD_x,y(rE_y(w)) on the matrices, as w is arbitrary.
+    fn consistent<E: PredicateEncoding>(x: &E::X, y: &E::Y) -> bool {
+        let lhs = E::s_d(x, y)
+            .iter()
+            .zip(E::s_e(x).iter())
+            .fold(vec![Scalar::zero(); E::PARAMS], |acc, (d, row)| {
+                acc.iter().zip(row.iter()).map(|(a, e)| a + d * e).collect()
+            });
+        let rhs = E::r_d(x, y)
+            .iter()
+            .zip(E::r_e(y).iter())
+            .fold(vec![Scalar::zero(); E::PARAMS], |acc, (d, row)| {
+                acc.iter().zip(row.iter()).map(|(a, e)| a + d * e).collect()
+            });
+
+        lhs == rhs
+    }
+
+    #[test]
+    fn ibe_encoding() {
+        let x = Identity::derive_str("email:w.geraedts@sarif.nl");
+        let y = Identity::derive_str("email:l.botros@cs.ru.nl");
+
+        assert!(IbeEncoding::predicate(&x, &x));
+        assert!(consistent::<IbeEncoding>(&x, &x));
+        assert!(!IbeEncoding::predicate(&x, &y));
+        assert!(!consistent::<IbeEncoding>(&x, &y));
+    }
+
+    #[test]
+    fn inner_product_encoding() {
+        let x = [Scalar::from(1), Scalar::from(2), Scalar::from(3)];
+        let y = [Scalar::from(1), Scalar::from(1), -Scalar::from(1)];
+        let z = [Scalar::from(1), Scalar::zero(), Scalar::zero()];
+
+        assert!(InnerProductEncoding::<3>::predicate(&x, &y));
+        assert!(consistent::<InnerProductEncoding<3>>(&x, &y));
+        assert!(!InnerProductEncoding::<3>::predicate(&x, &z));
+        assert!(!consistent::<InnerProductEncoding<3>>(&x, &z));
+    }
+
+    #[test]
+    fn inner_product_identifier() {
+        assert_eq!(InnerProductEncoding::<3>::IDENTIFIER, "pe_kv_ipe_3");
+        assert_eq!(InnerProductEncoding::<128>::IDENTIFIER, "pe_kv_ipe_128");
+    }
+}
@@ -0,0 +1,209 @@
+//! Generic IND-ID-CCA2 secure IBKEM from any IND-ID-CPA secure IBE.
+//!
+//! CCA security due to a general approach by Fujisaki and Okamoto.
+//! * From: "[A Modular Analysis of the Fujisaki-Okamoto Transformation](https://eprint.iacr.org/2017/604.pdf)"
+//!
+//! The random message is hashed together with the identity to the coins of the IBE (G),
+//! using [`Coins`]. The shared secret is derived from the message using SHAKE256 (H).
+//!
+//! The Chen-Gay-Wee scheme also has a dedicated instantiation in the `cgw_fo` module.
+//! The generic transform applied to it is available as [`FOCGW`].
+
+use crate::kem::{Error, SharedSecret, IBKEM, SS_BYTES};
+use crate::pke::{Coins, IBE};
+pub use crate::util::ID_BYTES;
+use crate::util::*;
+use crate::Compress;
+use core::convert::TryFrom;
+use core::marker::PhantomData;
+use group::Group;
+use rand::{CryptoRng, Rng};
+use subtle::{ConstantTimeEq, CtOption};
+
+/// User secret key of the IBE, together with the identity (needed for re-encryption).
+///
+/// The size of the compressed key `N` must equal the size of the IBE user secret key plus [`ID_BYTES`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UserSecretKey<U, const N: usize> {
+    usk: U,
+    id: Identity,
+}
+
+impl<U, const N: usize> Compress for UserSecretKey<U, N>
+where
+    U: Compress + Default,
+    U::Output: for<'a> TryFrom<&'a [u8]>,
+{
+    const OUTPUT_SIZE: usize = if N == U::OUTPUT_SIZE + ID_BYTES {
+        N
+    } else {
+        panic!("size of the user secret key does not match the IBE")
+    };
+    type Output = [u8; N];
+
+    fn to_bytes(&self) -> [u8; N] {
+        let mut buf = [0u8; N];
+        let (usk, id) = buf.split_at_mut(Self::OUTPUT_SIZE - ID_BYTES);
+
+        usk.copy_from_slice(self.usk.to_bytes().as_ref());
+        id.copy_from_slice(&self.id.0);
+
+        buf
+    }
+
+    fn from_bytes(bytes: &[u8; N]) -> CtOption<Self> {
+        let (usk, id) = bytes.split_at(Self::OUTPUT_SIZE - ID_BYTES);
+
+        // Cannot fail, the sizes are checked above.
+        let usk = U::Output::try_from(usk).ok().unwrap();
+        let id = Identity(*arrayref::array_ref![id, 0, ID_BYTES]);
+
+        let usk = U::from_bytes(&usk);
+        let is_some = usk.is_some();
+        let usk = Option::from(usk).unwrap_or_default();
+
+        CtOption::new(UserSecretKey { usk, id }, is_some)
+    }
+}
+
+/// The CCA2 secure KEM that results by applying the Fujisaki-Okamoto transform to an IBE.
+///
+/// The size of the compressed user secret key is given by `USK_BYTES`,
+/// which must equal the size of the IBE user secret key plus [`ID_BYTES`].
+pub struct FO<P, const USK_BYTES: usize>(PhantomData<P>);
+
+/// The Fujisaki-Okamoto transform applied to the Chen-Gay-Wee IBE.
+#[cfg(feature = "cgw")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cgw")))]
+pub type FOCGW = FO<crate::pke::cgw::CGW, { crate::pke::cgw::USK_BYTES + ID_BYTES }>;
+
+impl<P, const USK_BYTES: usize> Clone for FO<P, USK_BYTES> {
+    fn clone(&self) -> Self {
+        FO(PhantomData)
+    }
+}
+
+impl<P, const USK_BYTES: usize> FO<P, USK_BYTES>
+where
+    P: IBE<Id = Identity>,
+    P::RngBytes: Coins,
+{
+    /// Identifier of the KEM, the identifier of the underlying IBE prefixed by `fo-`.
+    const ID: &'static IdentifierBuf =
+        &IdentifierBuf::new().push_str("fo-").push_str(P::IDENTIFIER);
+
+    /// Derives the encryption coins from a message and the identity it is encrypted for (G).
+    fn coins(m: &P::Msg, id: &Identity) -> P::RngBytes {
+        P::RngBytes::derive_coins(&[m.to_bytes().as_ref(), &id.0])
+    }
+}
+
+impl<P, const USK_BYTES: usize> IBKEM for FO<P, USK_BYTES>
+where
+    P: IBE<Id = Identity>,
+    P::Ct: Default + ConstantTimeEq,
+    P::Usk: Default,
+    P::RngBytes: Coins,
+    <P::Usk as Compress>::Output: for<'a> TryFrom<&'a [u8]>,
+{
+    /// Identifies the KEM by the identifier of the underlying IBE, prefixed by `fo-`.
+    const IDENTIFIER: &'static str = Self::ID.as_str();
+
+    type Pk = P::Pk;
+    type Sk = P::Sk;
+    type Usk = UserSecretKey<P::Usk, USK_BYTES>;
+    type Ct = P::Ct;
+    type Ss = SharedSecret;
+    type Id = Identity;
+
+    const PK_BYTES: usize = P::PK_BYTES;
+    const SK_BYTES: usize = P::SK_BYTES;
+    const USK_BYTES: usize = <Self::Usk as Compress>::OUTPUT_SIZE;
+    const CT_BYTES: usize = P::CT_BYTES;
+
+    fn setup<R: Rng + CryptoRng>(rng: &mut R) -> (P::Pk, P::Sk) {
+        P::setup(rng)
+    }
+
+    fn extract_usk<R: Rng + CryptoRng>(
+        pk: Option<&P::Pk>,
+        sk: &P::Sk,
+        id: &Identity,
+        rng: &mut R,
+    ) -> Self::Usk {
+        let usk = P::extract_usk(pk, sk, id, rng);
+
+        UserSecretKey { usk, id: *id }
+    }
+
+    fn encaps<R: Rng + CryptoRng>(pk: &P::Pk, id: &Identity, rng: &mut R) -> (P::Ct, SharedSecret) {
+        let m = P::Msg::random(rng);
+        let ct = P::encrypt(pk, id, &m, &Self::coins(&m, id));
+
+        (
+            ct,
+            SharedSecret(shake256::<SS_BYTES>(m.to_bytes().as_ref())),
+        )
+    }
+
+    /// Decapsulate a shared secret from the ciphertext.
+    ///
+    /// # Panics
+    ///
+    /// This scheme **does** requires the master public key due to usage the Fujisaki-Okamoto transform.
+    /// This function panics if no master public key is provided.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an [`Error::Decapsulation`] when an illegitimate ciphertext is encountered (explicit rejection).
+    fn decaps(opk: Option<&P::Pk>, usk: &Self::Usk, c: &P::Ct) -> Result<SharedSecret, Error> {
+        let pk = opk.unwrap();
+
+        let m = P::decrypt(&usk.usk, c);
+        let c2 = P::encrypt(pk, &usk.id, &m, &Self::coins(&m, &usk.id));
+
+        if c.ct_eq(&c2).into() {
+            Ok(SharedSecret(shake256::<SS_BYTES>(m.to_bytes().as_ref())))
+        } else {
+            Err(Error::Decapsulation)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "cgw"))]
+mod tests {
+    use super::*;
+    use crate::pke::cgw::{CipherText, PublicKey, SecretKey, USK_BYTES as CPA_USK_BYTES};
+    use crate::Derive;
+
+    type UserSecretKey =
+        super::UserSecretKey<crate::pke::cgw::UserSecretKey, { CPA_USK_BYTES + ID_BYTES }>;
+
+    test_kem!(FOCGW);
+
+    #[test]
+    fn identifier() {
+        assert_eq!(FOCGW::IDENTIFIER, "fo-cgw");
+    }
+
+    #[test]
+    fn rejects_modified_ciphertext() {
+        let mut rng = rand::thread_rng();
+        let kid = Identity::derive_str(ID1);
+
+        let (pk, sk) = FOCGW::setup(&mut rng);
+        let usk = FOCGW::extract_usk(Some(&pk), &sk, &kid, &mut rng);
+        let (c, _) = FOCGW::encaps(&pk, &kid, &mut rng);
+        let (c2, _) = FOCGW::encaps(&pk, &kid, &mut rng);
+
+        // Mix two valid ciphertexts.
+        let mut bytes = c.to_bytes();
+        bytes[..48].copy_from_slice(&c2.to_bytes()[..48]);
+        let c = CipherText::from_bytes(&bytes).unwrap();
+
+        assert!(matches!(
+            FOCGW::decaps(Some(&pk), &usk, &c),
+            Err(Error::Decapsulation)
+        ));
+    }
+}
@@ -4,10 +4,30 @@
 #[cfg_attr(docsrs, doc(cfg(feature = "cgwfo")))]
 pub mod cgw_fo;
 
+#[cfg(feature = "cgw_hibe_fo")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cgw_hibe_fo")))]
+pub mod cgw_hibe_fo;
+
+#[cfg(feature = "fo")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fo")))]
+pub mod fo;
+
 #[cfg(feature = "cgwkv1")]
 #[cfg_attr(docsrs, doc(cfg(feature = "cgwkv1")))]
 pub mod cgw_kv1;
 
+#[cfg(feature = "pe_kv")]
+#[cfg_attr(docsrs, doc(cfg(feature = "pe_kv")))]
+pub mod encoding;
+
+#[cfg(feature = "pe_kv")]
+#[cfg_attr(docsrs, doc(cfg(feature = "pe_kv")))]
+pub mod pe_kv;
+
+#[cfg(any(feature = "rwac", feature = "rwac_cpa"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "rwac", feature = "rwac_cpa"))))]
+pub mod policy;
+
 #[cfg(feature = "rwac")]
 #[cfg_attr(docsrs, doc(cfg(feature = "rwac")))]
 pub mod rwac;
@@ -16,8 +36,21 @@ pub mod rwac;
 #[cfg_attr(docsrs, doc(cfg(feature = "rwac_cpa")))]
 pub mod rwac_cpa;
 
+#[cfg(feature = "rwac_del")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rwac_del")))]
+pub mod rwac_del;
+
+#[cfg(feature = "rwac_fo")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rwac_fo")))]
+pub mod rwac_fo;
+
+#[cfg(feature = "rwac_ver")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rwac_ver")))]
+pub mod rwac_ver;
+
 use crate::util::*;
 use crate::{Compress, Derive};
+use core::ops::Range;
 use irmaseal_curve::Gt;
 use rand::{CryptoRng, Rng};
 
@@ -46,6 +79,10 @@ pub enum Error {
     Decapsulation,
     /// Error that the given buffer of ciphertexts is not the correct size.
     IncorrectSize,
+    /// Error indicating that the attributes of a user secret key do not satisfy the access policy.
+    PolicyNotSatisfied,
+    /// Error indicating that an access policy is malformed, e.g., empty or with rows of different lengths.
+    MalformedPolicy,
 }
 
 /// Identity-based key encapsulation mechanism (IBKEM).
@@ -111,3 +148,135 @@ pub trait IBKEM: Clone {
     /// a bogus ciphertext is used as input.
     fn decaps(mpk: Option<&Self::Pk>, usk: &Self::Usk, ct: &Self::Ct) -> Result<Self::Ss, Error>;
 }
+
+/// Multi-recipient extension of an IBKEM.
+///
+/// Encapsulates a single shared secret for several identities at once,
+/// resulting in one ciphertext per identity.
+///
+/// Only schemes whose ciphertext carries an encrypted message can implement this securely.
+pub trait MultiRecipient: IBKEM {
+    /// Encapsulate one shared secret for multiple identities.
+    ///
+    /// The ciphertext for `ids[i]` is written to `cts[i]`.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an [`Error::IncorrectSize`] when `ids` and `cts` differ in length.
+    fn multi_encaps<R: Rng + CryptoRng>(
+        pk: &Self::Pk,
+        ids: &[&Self::Id],
+        rng: &mut R,
+        cts: &mut [Self::Ct],
+    ) -> Result<Self::Ss, Error>;
+}
+
+/// Extension of an IBKEM to identities with a validity epoch, see [`IdentityBuilder`].
+///
+/// Rotating user secret keys then does not require rotating the master key pair:
+/// a sender encapsulates for an attribute at a point in time, and a user obtains
+/// keys for the attribute in every epoch it is entitled to.
+///
+/// Implemented for all schemes that use the default [`Identity`].
+pub trait TimedIBKEM: IBKEM<Id = Identity> {
+    /// Encapsulate a shared secret for an attribute in an epoch.
+    fn encaps_at<R: Rng + CryptoRng>(
+        pk: &Self::Pk,
+        attribute: &[u8],
+        epoch: Epoch,
+        rng: &mut R,
+    ) -> (Self::Ct, Self::Ss) {
+        let id = Identity::builder(attribute).epoch(epoch).build();
+        Self::encaps(pk, &id, rng)
+    }
+
+    /// Extract a user secret key for an attribute in an epoch.
+    fn extract_usk_at<R: Rng + CryptoRng>(
+        pk: Option<&Self::Pk>,
+        sk: &Self::Sk,
+        attribute: &[u8],
+        epoch: Epoch,
+        rng: &mut R,
+    ) -> Self::Usk {
+        let id = Identity::builder(attribute).epoch(epoch).build();
+        Self::extract_usk(pk, sk, &id, rng)
+    }
+
+    /// Extract user secret keys for an attribute in each epoch in the range.
+    ///
+    /// Lazily yields the keys along with their epoch.
+    fn extract_usk_range<'a, R: Rng + CryptoRng>(
+        pk: Option<&'a Self::Pk>,
+        sk: &'a Self::Sk,
+        attribute: &'a [u8],
+        epochs: Range<Epoch>,
+        rng: &'a mut R,
+    ) -> impl Iterator<Item = (Epoch, Self::Usk)> + 'a {
+        (epochs.start.0..epochs.end.0).map(move |e| {
+            let epoch = Epoch(e);
+            (epoch, Self::extract_usk_at(pk, sk, attribute, epoch, rng))
+        })
+    }
+}
+
+impl<K: IBKEM<Id = Identity>> TimedIBKEM for K {}
+
+/// Attribute-based key encapsulation mechanism (ABKEM).
+///
+/// Ciphertexts are associated with a policy, user secret keys with a set of attributes.
+/// Decapsulation is only possible when the attribute set satisfies the policy.
+pub trait ABKEM: Clone {
+    /// Scheme identifier.
+    const IDENTIFIER: &'static str;
+
+    /// Master public key (Mpk).
+    type Pk;
+
+    /// Master secret key (Msk).
+    type Sk;
+
+    /// User secret key (Usk).
+    type Usk;
+
+    /// Ciphertext (Ct).
+    type Ct;
+
+    /// Access policy under which shared secrets are encapsulated.
+    type Policy;
+
+    /// Set of attributes associated with a user secret key.
+    type AttributeSet: ?Sized;
+
+    /// Shared secret.
+    type Ss: Copy;
+
+    /// Creates a MSK, MPK pair.
+    fn setup<R: Rng + CryptoRng>(rng: &mut R) -> (Self::Pk, Self::Sk);
+
+    /// Extract a user secret key for a set of attributes using the MSK.
+    ///
+    /// Optionally requires the system's public key.
+    fn extract_usk<R: Rng + CryptoRng>(
+        pk: Option<&Self::Pk>,
+        sk: &Self::Sk,
+        attrs: &Self::AttributeSet,
+        rng: &mut R,
+    ) -> Self::Usk;
+
+    /// Encapsulate a shared secret using the master public key and an access policy.
+    ///
+    /// Fails when the access policy is malformed.
+    fn encaps<R: Rng + CryptoRng>(
+        pk: &Self::Pk,
+        policy: &Self::Policy,
+        rng: &mut R,
+    ) -> Result<(Self::Ct, Self::Ss), Error>;
+
+    /// Decrypt a ciphertext using a user secret key to retrieve the shared secret.
+    ///
+    /// Optionally requires a public key to perform this operation.
+    ///
+    /// Fails when the attributes of the user secret key do not satisfy the access policy,
+    /// or when the access policy of the ciphertext is malformed.
+    fn decaps(mpk: Option<&Self::Pk>, usk: &Self::Usk, ct: &Self::Ct) -> Result<Self::Ss, Error>;
+}
@@ -0,0 +1,516 @@
+//! IND-CCA2 secure KEM for any predicate encoding, using the dual system framework of Chen, Gay and Wee.
+//!  * From: "[Improved Dual System ABE in Prime-Order Groups via Predicate Encodings](https://link.springer.com/chapter/10.1007/978-3-540-79263-5_14)"
+//!
+//! CCA security due to a generalized approach from Kiltz & Vahlis, as in [`cgw_kv1`](crate::kem::cgw_kv1).
+//!  * From: "[CCA2 Secure IBE: Standard Model Efficiency through Authenticated Symmetric Encryption](https://link.springer.com/chapter/10.1007/978-3-540-79263-5_14)"
+//!  * Published in: CT-RSA, 2008
+//!
+//! The scheme is parameterized by a [`PredicateEncoding`], which determines all key and ciphertext components.
+//! The transform adds one extra parameter W' to the encoding. Every user secret key contains
+//! an extra component [-W'Br]_2, every ciphertext an extra component [x' W'^T A s]_1,
+//! where x' is the RPC hash of [As]_1 with a fresh key. Decapsulation combines the extra key component
+//! with the hash and always implicitly rejects.
+//!
+//! Unlike [`cgw_kv1`](crate::kem::cgw_kv1), the extra ciphertext component is not folded into
+//! the other components, as sD may depend on the key attribute.
+//! For the IBE encoding this costs two additional G1 elements.
+
+pub use crate::kem::encoding::{IbeEncoding, InnerProductEncoding, PredicateEncoding};
+use crate::kem::{Error, SharedSecret, ABKEM};
+use crate::util::*;
+use crate::CompressVar;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use irmaseal_curve::{
+    multi_miller_loop, pairing, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt,
+    Scalar,
+};
+use rand::{CryptoRng, Rng};
+
+/// Public key parameters generated by the PKG used to encaps messages.
+/// Also known as MPK.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PublicKey<E: PredicateEncoding> {
+    /// [A]_1
+    a_1: [G1Affine; 2],
+    /// [W_i^T A]_1, one per parameter of the encoding
+    wta_1: Vec<[G1Affine; 2]>,
+    /// [W'^T A]_1
+    wprimeta_1: [G1Affine; 2],
+    /// [k^T A]_T
+    kta_t: Gt,
+    encoding: PhantomData<E>,
+}
+
+/// Secret key parameter generated by the PKG used to extract user secret keys.
+/// Also known as MSK.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SecretKey<E: PredicateEncoding> {
+    b: [Scalar; 2],
+    k: [Scalar; 2],
+    w: Vec<[[Scalar; 2]; 2]>,
+    wprime: [[Scalar; 2]; 2],
+    encoding: PhantomData<E>,
+}
+
+/// User secret key for a key attribute y. Can be used to decaps the corresponding ciphertext.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UserSecretKey<E: PredicateEncoding> {
+    /// [Br]_2
+    d0: [G2Affine; 2],
+    /// [kE_y(k) - rE_y(W)Br]_2, one per row of rE_y
+    d1: Vec<[G2Affine; 2]>,
+    /// [-W'Br]_2
+    d2: [G2Affine; 2],
+    /// y
+    y: E::Y,
+}
+
+/// Encrypted message for a ciphertext attribute x. Can only be decapsed with a corresponding user secret key.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CipherText<E: PredicateEncoding> {
+    /// [As]_1
+    c0: [G1Affine; 2],
+    /// [sE_x(W^T A)s]_1, one per row of sE_x
+    c1: Vec<[G1Affine; 2]>,
+    /// [x' W'^T A s]_1
+    c2: [G1Affine; 2],
+    /// Key of the RPC hash
+    k: [u8; 32],
+    /// x
+    x: E::X,
+}
+
+/// The CCA2 secure KEM that results by applying our transform to the CGW scheme
+/// instantiated with the predicate encoding `E`.
+pub struct PEKV<E>(PhantomData<E>);
+
+impl<E> Clone for PEKV<E> {
+    fn clone(&self) -> Self {
+        PEKV(PhantomData)
+    }
+}
+
+/// Computes W v for a 2x2 matrix W.
+fn mul(w: &[[Scalar; 2]; 2], v: &[Scalar; 2]) -> [Scalar; 2] {
+    [
+        w[0][0] * v[0] + w[0][1] * v[1],
+        w[1][0] * v[0] + w[1][1] * v[1],
+    ]
+}
+
+/// Computes W^T v for a 2x2 matrix W.
+fn mul_t(w: &[[Scalar; 2]; 2], v: &[Scalar; 2]) -> [Scalar; 2] {
+    [
+        w[0][0] * v[0] + w[1][0] * v[1],
+        w[0][1] * v[0] + w[1][1] * v[1],
+    ]
+}
+
+/// Computes Σ_i c_i v_i for vectors v_i of group elements.
+fn combine<A, G>(coeffs: &[Scalar], vs: &[[A; 2]]) -> [G; 2]
+where
+    G: Copy + Default + core::ops::Add<Output = G>,
+    for<'a> &'a A: core::ops::Mul<&'a Scalar, Output = G>,
+{
+    coeffs
+        .iter()
+        .zip(vs.iter())
+        .fold([G::default(); 2], |acc, (c, v)| {
+            [acc[0] + &v[0] * c, acc[1] + &v[1] * c]
+        })
+}
+
+impl<E: PredicateEncoding> ABKEM for PEKV<E> {
+    const IDENTIFIER: &'static str = E::IDENTIFIER;
+
+    type Pk = PublicKey<E>;
+    type Sk = SecretKey<E>;
+    type Usk = UserSecretKey<E>;
+    type Ct = CipherText<E>;
+    type Policy = E::X;
+    type AttributeSet = E::Y;
+    type Ss = SharedSecret;
+
+    /// Generate a keypair used by the Private Key Generator (PKG).
+    fn setup<R: Rng + CryptoRng>(rng: &mut R) -> (PublicKey<E>, SecretKey<E>) {
+        let g1 = G1Affine::generator();
+        let g2 = G2Affine::generator();
+
+        let rand_mat = |rng: &mut R| {
+            [
+                [rand_scalar(rng), rand_scalar(rng)],
+                [rand_scalar(rng), rand_scalar(rng)],
+            ]
+        };
+
+        let a = [rand_scalar(rng), rand_scalar(rng)];
+        let b = [rand_scalar(rng), rand_scalar(rng)];
+        let k = [rand_scalar(rng), rand_scalar(rng)];
+        let w: Vec<[[Scalar; 2]; 2]> = (0..E::PARAMS).map(|_| rand_mat(rng)).collect();
+        let wprime = rand_mat(rng);
+
+        let to_g1 = |v: [Scalar; 2]| -> [G1Affine; 2] { [(g1 * v[0]).into(), (g1 * v[1]).into()] };
+
+        (
+            PublicKey {
+                a_1: to_g1(a),
+                wta_1: w.iter().map(|w_i| to_g1(mul_t(w_i, &a))).collect(),
+                wprimeta_1: to_g1(mul_t(&wprime, &a)),
+                kta_t: pairing(&g1, &g2) * (k[0] * a[0] + k[1] * a[1]),
+                encoding: PhantomData,
+            },
+            SecretKey {
+                b,
+                k,
+                w,
+                wprime,
+                encoding: PhantomData,
+            },
+        )
+    }
+
+    /// Extract a user secret key for a key attribute.
+    fn extract_usk<R: Rng + CryptoRng>(
+        _pk: Option<&PublicKey<E>>,
+        sk: &SecretKey<E>,
+        y: &E::Y,
+        rng: &mut R,
+    ) -> UserSecretKey<E> {
+        let g2 = G2Affine::generator();
+        let r = rand_scalar(rng);
+
+        let br = [sk.b[0] * r, sk.b[1] * r];
+        let wbr: Vec<[Scalar; 2]> = sk.w.iter().map(|w_i| mul(w_i, &br)).collect();
+        let wprimebr = mul(&sk.wprime, &br);
+
+        let d1 = E::r_e(y)
+            .iter()
+            .zip(E::k_e(y).iter())
+            .map(|(row, ke)| {
+                let mut d = [sk.k[0] * ke, sk.k[1] * ke];
+                for (e, wbr_i) in row.iter().zip(wbr.iter()) {
+                    d[0] -= e * wbr_i[0];
+                    d[1] -= e * wbr_i[1];
+                }
+
+                [(g2 * d[0]).into(), (g2 * d[1]).into()]
+            })
+            .collect();
+
+        UserSecretKey {
+            d0: [(g2 * br[0]).into(), (g2 * br[1]).into()],
+            d1,
+            d2: [(g2 * -wprimebr[0]).into(), (g2 * -wprimebr[1]).into()],
+            y: y.clone(),
+        }
+    }
+
+    fn encaps<R: Rng + CryptoRng>(
+        pk: &PublicKey<E>,
+        x: &E::X,
+        rng: &mut R,
+    ) -> Result<(CipherText<E>, SharedSecret), Error> {
+        let s = rand_scalar(rng);
+        let k = pk.kta_t * s;
+
+        let c0 = [(pk.a_1[0] * s).into(), (pk.a_1[1] * s).into()];
+
+        let c1 = E::s_e(x)
+            .iter()
+            .map(|row| {
+                let c: [G1Projective; 2] = combine(row, &pk.wta_1);
+                [(c[0] * s).into(), (c[1] * s).into()]
+            })
+            .collect();
+
+        let mut smallk = [0u8; 32];
+        rng.fill_bytes(&mut smallk);
+
+        let xprime = rpc(&smallk, &c0);
+        let c2 = [
+            (pk.wprimeta_1[0] * (s * xprime)).into(),
+            (pk.wprimeta_1[1] * (s * xprime)).into(),
+        ];
+
+        Ok((
+            CipherText {
+                c0,
+                c1,
+                c2,
+                k: smallk,
+                x: x.clone(),
+            },
+            SharedSecret::from(&k),
+        ))
+    }
+
+    /// Derive the same SharedSecret from the CipherText using a UserSecretKey.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an [`Error::PolicyNotSatisfied`] when the predicate does not hold
+    /// for the attributes of the ciphertext and user secret key.
+    /// Otherwise, ciphertexts are always implicitly rejected.
+    fn decaps(
+        _pk: Option<&PublicKey<E>>,
+        usk: &UserSecretKey<E>,
+        ct: &CipherText<E>,
+    ) -> Result<SharedSecret, Error> {
+        if !E::predicate(&ct.x, &usk.y) {
+            return Err(Error::PolicyNotSatisfied);
+        }
+
+        let yprime = rpc(&ct.k, &ct.c0);
+
+        let c: [G1Projective; 2] = combine(&E::s_d(&ct.x, &usk.y), &ct.c1);
+        let d: [G2Projective; 2] = combine(&E::r_d(&ct.x, &usk.y), &usk.d1);
+
+        let c = [
+            G1Affine::from(c[0] + ct.c2[0]),
+            G1Affine::from(c[1] + ct.c2[1]),
+        ];
+        let d = [
+            G2Affine::from(d[0] + usk.d2[0] * yprime),
+            G2Affine::from(d[1] + usk.d2[1] * yprime),
+        ];
+
+        let m = multi_miller_loop(&[
+            (&ct.c0[0], &G2Prepared::from(d[0])),
+            (&ct.c0[1], &G2Prepared::from(d[1])),
+            (&c[0], &G2Prepared::from(usk.d0[0])),
+            (&c[1], &G2Prepared::from(usk.d0[1])),
+        ])
+        .final_exponentiation();
+
+        Ok(SharedSecret::from(&m))
+    }
+}
+
+impl<E: PredicateEncoding> CompressVar for PublicKey<E> {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+
+        for el in self.a_1.iter().chain(self.wta_1.iter().flatten()) {
+            buf.extend_from_slice(&el.to_compressed());
+        }
+        for el in self.wprimeta_1.iter() {
+            buf.extend_from_slice(&el.to_compressed());
+        }
+        buf.extend_from_slice(&self.kta_t.to_compressed());
+
+        buf
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+
+        let a_1 = [r.read_g1()?, r.read_g1()?];
+        let wta_1 = (0..E::PARAMS)
+            .map(|_| Some([r.read_g1()?, r.read_g1()?]))
+            .collect::<Option<Vec<_>>>()?;
+        let wprimeta_1 = [r.read_g1()?, r.read_g1()?];
+        let kta_t = r.read_gt()?;
+
+        r.finish(PublicKey {
+            a_1,
+            wta_1,
+            wprimeta_1,
+            kta_t,
+            encoding: PhantomData,
+        })
+    }
+}
+
+impl<E: PredicateEncoding> CompressVar for SecretKey<E> {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+
+        for x in self.b.iter().chain(self.k.iter()) {
+            buf.extend_from_slice(&x.to_bytes());
+        }
+        for x in self.w.iter().chain([&self.wprime]).flatten().flatten() {
+            buf.extend_from_slice(&x.to_bytes());
+        }
+
+        buf
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+
+        let read_mat = |r: &mut Reader| {
+            Some([
+                [r.read_scalar()?, r.read_scalar()?],
+                [r.read_scalar()?, r.read_scalar()?],
+            ])
+        };
+
+        let b = [r.read_scalar()?, r.read_scalar()?];
+        let k = [r.read_scalar()?, r.read_scalar()?];
+        let w = (0..E::PARAMS)
+            .map(|_| read_mat(&mut r))
+            .collect::<Option<Vec<_>>>()?;
+        let wprime = read_mat(&mut r)?;
+
+        r.finish(SecretKey {
+            b,
+            k,
+            w,
+            wprime,
+            encoding: PhantomData,
+        })
+    }
+}
+
+impl<E: PredicateEncoding> CompressVar for UserSecretKey<E> {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+
+        for el in self.d0.iter() {
+            buf.extend_from_slice(&el.to_compressed());
+        }
+        put_len(&mut buf, self.d1.len());
+        for el in self.d1.iter().flatten().chain(self.d2.iter()) {
+            buf.extend_from_slice(&el.to_compressed());
+        }
+        put_var(&mut buf, &self.y);
+
+        buf
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+
+        let d0 = [r.read_g2()?, r.read_g2()?];
+        let d1 = r.read_vec(|r| Some([r.read_g2()?, r.read_g2()?]))?;
+        let d2 = [r.read_g2()?, r.read_g2()?];
+        let y = r.read_var()?;
+
+        if d1.len() != E::r_e(&y).len() {
+            return None;
+        }
+
+        r.finish(UserSecretKey { d0, d1, d2, y })
+    }
+}
+
+impl<E: PredicateEncoding> CompressVar for CipherText<E> {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+
+        for el in self.c0.iter() {
+            buf.extend_from_slice(&el.to_compressed());
+        }
+        put_len(&mut buf, self.c1.len());
+        for el in self.c1.iter().flatten().chain(self.c2.iter()) {
+            buf.extend_from_slice(&el.to_compressed());
+        }
+        buf.extend_from_slice(&self.k);
+        put_var(&mut buf, &self.x);
+
+        buf
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+
+        let c0 = [r.read_g1()?, r.read_g1()?];
+        let c1 = r.read_vec(|r| Some([r.read_g1()?, r.read_g1()?]))?;
+        let c2 = [r.read_g1()?, r.read_g1()?];
+        let k = *r.read_bytes::<32>()?;
+        let x = r.read_var()?;
+
+        if c1.len() != E::s_e(&x).len() {
+            return None;
+        }
+
+        r.finish(CipherText { c0, c1, c2, k, x })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Derive;
+
+    type IbeKem = PEKV<IbeEncoding>;
+    type IpeKem = PEKV<InnerProductEncoding<3>>;
+
+    #[test]
+    fn eq_encaps_decaps_ibe() {
+        let mut rng = rand::thread_rng();
+        let id = Identity::derive_str("email:w.geraedts@sarif.nl");
+        let other = Identity::derive_str("email:l.botros@cs.ru.nl");
+
+        let (pk, sk) = IbeKem::setup(&mut rng);
+        let usk = IbeKem::extract_usk(Some(&pk), &sk, &id, &mut rng);
+        let (c, k) = IbeKem::encaps(&pk, &id, &mut rng).unwrap();
+
+        assert_eq!(IbeKem::decaps(None, &usk, &c).unwrap(), k);
+
+        let usk = IbeKem::extract_usk(Some(&pk), &sk, &other, &mut rng);
+        assert!(matches!(
+            IbeKem::decaps(None, &usk, &c),
+            Err(Error::PolicyNotSatisfied)
+        ));
+    }
+
+    #[test]
+    fn eq_encaps_decaps_ipe() {
+        let mut rng = rand::thread_rng();
+        let x = [Scalar::from(1), Scalar::from(2), Scalar::from(3)];
+        let y = [Scalar::from(1), Scalar::from(1), -Scalar::from(1)];
+        let z = [Scalar::from(1), Scalar::zero(), Scalar::zero()];
+
+        let (pk, sk) = IpeKem::setup(&mut rng);
+        let usk = IpeKem::extract_usk(Some(&pk), &sk, &y, &mut rng);
+        let (c, k) = IpeKem::encaps(&pk, &x, &mut rng).unwrap();
+
+        assert_eq!(IpeKem::decaps(None, &usk, &c).unwrap(), k);
+
+        let usk = IpeKem::extract_usk(Some(&pk), &sk, &z, &mut rng);
+        assert!(matches!(
+            IpeKem::decaps(None, &usk, &c),
+            Err(Error::PolicyNotSatisfied)
+        ));
+    }
+
+    #[test]
+    fn implicit_rejection() {
+        let mut rng = rand::thread_rng();
+        let id = Identity::derive_str("email:w.geraedts@sarif.nl");
+
+        let (pk, sk) = IbeKem::setup(&mut rng);
+        let usk = IbeKem::extract_usk(Some(&pk), &sk, &id, &mut rng);
+        let (mut c, k) = IbeKem::encaps(&pk, &id, &mut rng).unwrap();
+
+        c.k[0] ^= 1;
+        assert_ne!(IbeKem::decaps(None, &usk, &c).unwrap(), k);
+    }
+
+    #[test]
+    fn eq_serialize_deserialize() {
+        let mut rng = rand::thread_rng();
+        let x = [Scalar::from(1), Scalar::from(2), Scalar::from(3)];
+        let y = [Scalar::from(1), Scalar::from(1), -Scalar::from(1)];
+
+        let (pk, sk) = IpeKem::setup(&mut rng);
+        let usk = IpeKem::extract_usk(Some(&pk), &sk, &y, &mut rng);
+        let (c, _) = IpeKem::encaps(&pk, &x, &mut rng).unwrap();
+
+        assert_eq!(pk, PublicKey::from_bytes(&pk.to_bytes()).unwrap());
+        assert_eq!(sk, SecretKey::from_bytes(&sk.to_bytes()).unwrap());
+        assert_eq!(usk, UserSecretKey::from_bytes(&usk.to_bytes()).unwrap());
+        assert_eq!(c, CipherText::from_bytes(&c.to_bytes()).unwrap());
+
+        let bytes = c.to_bytes();
+        assert!(
+            CipherText::<InnerProductEncoding<3>>::from_bytes(&bytes[..bytes.len() - 1]).is_none()
+        );
+
+        // Keys of an encoding with a different number of parameters are rejected.
+        assert!(PublicKey::<InnerProductEncoding<2>>::from_bytes(&pk.to_bytes()).is_none());
+        assert!(SecretKey::<IbeEncoding>::from_bytes(&sk.to_bytes()).is_none());
+    }
+}
@@ -0,0 +1,721 @@
+//! Access policies for the attribute-based KEMs.
+//!
+//! Policies are boolean formulas over attributes, e.g.,
+//! `(dept:eng AND role:admin) OR clearance:top`, compiled into a linear secret sharing scheme
+//! (LSSS) using the conversion by Lewko and Waters.
+//! * From: "[Decentralizing Attribute-Based Encryption](https://eprint.iacr.org/2010/351.pdf)", Appendix G
+//!
+//! Besides `AND` and `OR`, the formulas support `k of (x, y, ..)` threshold gates.
+//! These are compiled by extending the parent vector with a Vandermonde row per child.
+//!
+//! Attributes, both in policies and user secret keys, are derived from strings using [`Attribute`].
+
+use crate::kem::Error;
+use crate::util::*;
+use crate::{CompressVar, Derive};
+use alloc::vec::Vec;
+use irmaseal_curve::Scalar;
+use tiny_keccak::Hasher;
+
+/// Domain separation tag for hashing attributes.
+const ATTRIBUTE_DST: &[u8] = b"cca_transforms-attribute";
+
+/// Maximum nesting depth of parentheses and threshold gates in a policy formula.
+pub const MAX_NESTING: usize = 64;
+
+/// Attribute of the attribute-based schemes, e.g., `role:admin`.
+///
+/// This attribute is obtained by hashing using sha3_512 and reducing the digest to a scalar.
+/// The input is prefixed with a domain separation tag, such that attributes
+/// never collide with identities derived from the same string.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Attribute(pub(crate) Scalar);
+
+impl Derive for Attribute {
+    /// Hash a byte slice to an attribute.
+    fn derive(b: &[u8]) -> Attribute {
+        let mut digest = tiny_keccak::Sha3::v512();
+        digest.update(ATTRIBUTE_DST);
+        digest.update(b);
+
+        let mut buf = [0u8; 64];
+        digest.finalize(&mut buf);
+
+        Attribute(Scalar::from_bytes_wide(&buf))
+    }
+
+    /// Hash a string slice to an attribute.
+    /// Directly converts characters to UTF-8 byte representation.
+    fn derive_str(s: &str) -> Attribute {
+        Self::derive(s.as_bytes())
+    }
+}
+
+pub type LSSSMatrix = Vec<Vec<Scalar>>;
+
+/// Access policy (A, rho), where row j of A is labeled by attribute rho(j).
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccessPolicy {
+    pub a: LSSSMatrix,
+    pub rho: Vec<Attribute>,
+}
+
+/// Generates an LSSS matrix of size n for AND-policies.
+pub fn gen_a(n: usize) -> LSSSMatrix {
+    if n == 1 {
+        vec![vec![Scalar::one()]]
+    } else {
+        let mut a_mat = Vec::new();
+
+        // 1, 1, 0, 0, ..
+        let mut v = vec![Scalar::default(); n];
+        v[0] = Scalar::one();
+        v[1] = Scalar::one();
+        a_mat.push(v);
+
+        for i in 1..n - 1 {
+            // 0, 0, -1, 1, 0, 0, etc.
+            v = vec![Scalar::default(); n];
+            v[i] = Scalar::one().neg();
+            v[i + 1] = Scalar::one();
+            a_mat.push(v);
+        }
+
+        // 0, 0, ..,  -1
+        v = vec![Scalar::default(); n];
+        v[n - 1] = Scalar::one().neg();
+        a_mat.push(v);
+
+        a_mat
+    }
+}
+
+/// Error encountered while compiling a policy formula.
+#[derive(Debug, PartialEq)]
+pub enum PolicyError {
+    /// The formula ended while more input was expected.
+    UnexpectedEnd,
+    /// Unexpected token at the given byte offset.
+    UnexpectedToken(usize),
+    /// Threshold gate at the given byte offset has a threshold of zero or one larger than its number of children.
+    InvalidThreshold(usize),
+    /// Gate at the given byte offset is nested deeper than [`MAX_NESTING`].
+    NestingTooDeep(usize),
+}
+
+/// Compiles a boolean formula into an access policy.
+///
+/// Attributes are derived from their string representation using [`Attribute::derive_str`].
+///
+/// # Grammar
+///
+/// ```text
+/// formula   := and ("OR" and)*
+/// and       := gate ("AND" gate)*
+/// gate      := "(" formula ")" | k "of" "(" formula ("," formula)* ")" | attribute
+/// ```
+///
+/// `AND` binds stronger than `OR`.
+/// Parentheses and threshold gates can be nested at most [`MAX_NESTING`] levels deep.
+/// Attributes consist of any characters except whitespace, parentheses and commas.
+pub fn compile(formula: &str) -> Result<AccessPolicy, PolicyError> {
+    let mut parser = Parser {
+        tokens: tokenize(formula),
+        pos: 0,
+        depth: 0,
+    };
+
+    let node = parser.parse_or()?;
+    if let Some((offset, _)) = parser.peek() {
+        return Err(PolicyError::UnexpectedToken(offset));
+    }
+
+    let mut rows = Vec::new();
+    let mut cols = 1;
+    assign(&node, vec![Scalar::one()], &mut cols, &mut rows);
+
+    let (a, rho) = rows
+        .into_iter()
+        .map(|(mut row, attr)| {
+            row.resize(cols, Scalar::zero());
+            (row, Attribute::derive_str(attr))
+        })
+        .unzip();
+
+    Ok(AccessPolicy { a, rho })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token<'a> {
+    Open,
+    Close,
+    Comma,
+    Word(&'a str),
+}
+
+/// Splits the formula into tokens, each paired with its byte offset.
+fn tokenize(formula: &str) -> Vec<(usize, Token<'_>)> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+
+    for (i, c) in formula.char_indices() {
+        let delim = match c {
+            '(' => Some(Token::Open),
+            ')' => Some(Token::Close),
+            ',' => Some(Token::Comma),
+            _ => None,
+        };
+
+        if delim.is_some() || c.is_whitespace() {
+            if let Some(s) = start.take() {
+                tokens.push((s, Token::Word(&formula[s..i])));
+            }
+            if let Some(t) = delim {
+                tokens.push((i, t));
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+
+    if let Some(s) = start {
+        tokens.push((s, Token::Word(&formula[s..])));
+    }
+
+    tokens
+}
+
+/// Node of a parsed formula. Both `AND` and `OR` are expressed as threshold gates.
+enum Node<'a> {
+    Attr(&'a str),
+    Gate(usize, Vec<Node<'a>>),
+}
+
+struct Parser<'a> {
+    tokens: Vec<(usize, Token<'a>)>,
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<(usize, Token<'a>)> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Result<(usize, Token<'a>), PolicyError> {
+        let t = self.peek().ok_or(PolicyError::UnexpectedEnd)?;
+        self.pos += 1;
+
+        Ok(t)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), PolicyError> {
+        match self.next()? {
+            (_, t) if t == expected => Ok(()),
+            (offset, _) => Err(PolicyError::UnexpectedToken(offset)),
+        }
+    }
+
+    /// Parses a list of operands separated by a keyword into a gate.
+    fn parse_op(
+        &mut self,
+        keyword: &str,
+        operand: fn(&mut Self) -> Result<Node<'a>, PolicyError>,
+        threshold: fn(usize) -> usize,
+    ) -> Result<Node<'a>, PolicyError> {
+        let mut children = vec![operand(self)?];
+        while let Some((_, Token::Word(w))) = self.peek() {
+            if w != keyword {
+                break;
+            }
+            self.pos += 1;
+            children.push(operand(self)?);
+        }
+
+        if children.len() == 1 {
+            Ok(children.pop().unwrap())
+        } else {
+            Ok(Node::Gate(threshold(children.len()), children))
+        }
+    }
+
+    /// Parses a formula nested inside the gate at the given offset.
+    fn parse_nested(&mut self, offset: usize) -> Result<Node<'a>, PolicyError> {
+        if self.depth == MAX_NESTING {
+            return Err(PolicyError::NestingTooDeep(offset));
+        }
+
+        self.depth += 1;
+        let node = self.parse_or();
+        self.depth -= 1;
+
+        node
+    }
+
+    fn parse_or(&mut self) -> Result<Node<'a>, PolicyError> {
+        self.parse_op("OR", Self::parse_and, |_| 1)
+    }
+
+    fn parse_and(&mut self) -> Result<Node<'a>, PolicyError> {
+        self.parse_op("AND", Self::parse_gate, |n| n)
+    }
+
+    fn parse_gate(&mut self) -> Result<Node<'a>, PolicyError> {
+        match self.next()? {
+            (offset, Token::Open) => {
+                let node = self.parse_nested(offset)?;
+                self.expect(Token::Close)?;
+
+                Ok(node)
+            }
+            (offset, Token::Word(w)) if w == "AND" || w == "OR" || w == "of" => {
+                Err(PolicyError::UnexpectedToken(offset))
+            }
+            (offset, Token::Word(w)) => match (w.parse::<usize>(), self.peek()) {
+                (Ok(k), Some((_, Token::Word("of")))) => {
+                    self.pos += 1;
+                    self.expect(Token::Open)?;
+
+                    let mut children = vec![self.parse_nested(offset)?];
+                    while let Some((_, Token::Comma)) = self.peek() {
+                        self.pos += 1;
+                        children.push(self.parse_nested(offset)?);
+                    }
+                    self.expect(Token::Close)?;
+
+                    if k == 0 || k > children.len() {
+                        return Err(PolicyError::InvalidThreshold(offset));
+                    }
+
+                    Ok(Node::Gate(k, children))
+                }
+                _ => Ok(Node::Attr(w)),
+            },
+            (offset, _) => Err(PolicyError::UnexpectedToken(offset)),
+        }
+    }
+}
+
+/// Assigns vector `v` to `node`, appending a row per attribute.
+///
+/// New columns are allocated from `cols`, rows are padded with zeroes afterwards.
+fn assign<'a>(
+    node: &Node<'a>,
+    v: Vec<Scalar>,
+    cols: &mut usize,
+    rows: &mut Vec<(Vec<Scalar>, &'a str)>,
+) {
+    match node {
+        Node::Attr(attr) => rows.push((v, attr)),
+        // OR-gate: every child receives the parent vector.
+        Node::Gate(1, children) => {
+            for child in children {
+                assign(child, v.clone(), cols, rows);
+            }
+        }
+        // AND-gate: child i receives (0, .., 0, -1, 1), the first child (v, 1)
+        // and the last child (0, .., 0, -1), such that all rows sum to v.
+        Node::Gate(k, children) if *k == children.len() => {
+            let mut v = v;
+            for child in &children[..k - 1] {
+                let c = *cols;
+                *cols += 1;
+
+                let mut left = v;
+                left.resize(c + 1, Scalar::zero());
+                left[c] = Scalar::one();
+
+                let mut right = vec![Scalar::zero(); c + 1];
+                right[c] = Scalar::one().neg();
+
+                assign(child, left, cols, rows);
+                v = right;
+            }
+            assign(&children[k - 1], v, cols, rows);
+        }
+        // Threshold gate: child i receives (v, i, i^2, .., i^(k - 1)).
+        // Any k of these rows reconstruct v using Lagrange coefficients.
+        Node::Gate(k, children) => {
+            let c = *cols;
+            *cols += k - 1;
+
+            for (i, child) in children.iter().enumerate() {
+                let x = Scalar::from(i as u64 + 1);
+                let mut row = v.clone();
+                row.resize(c + k - 1, Scalar::zero());
+
+                let mut pow = x;
+                for el in row[c..].iter_mut() {
+                    *el = pow;
+                    pow *= x;
+                }
+
+                assign(child, row, cols, rows);
+            }
+        }
+    }
+}
+
+impl AccessPolicy {
+    /// Checks that the policy has at least one row and column,
+    /// that all rows are of the same length and that every row is labeled by an attribute.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an [`Error::MalformedPolicy`] if any of these checks fail.
+    pub fn validate(&self) -> Result<(), Error> {
+        let n2 = self.a.first().map_or(0, |row| row.len());
+
+        if n2 == 0 || self.a.iter().any(|row| row.len() != n2) || self.rho.len() != self.a.len() {
+            Err(Error::MalformedPolicy)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Computes reconstruction coefficients for a set of attributes.
+    ///
+    /// Finds ω_j for the rows j labeled by the attributes, such that Σ ω_j A_j = (1, 0, .., 0),
+    /// using Gaussian elimination over the scalar field.
+    /// Returns the pairs (j, ω_j) with non-zero ω_j, or `None` when the attributes do not satisfy the policy.
+    ///
+    /// This operation is not constant-time, it only depends on the policy and the attributes.
+    /// Assumes the policy is well-formed, see [`AccessPolicy::validate`].
+    pub fn reconstruction_coefficients(&self, attrs: &[Attribute]) -> Option<Vec<(usize, Scalar)>> {
+        let rows: Vec<usize> = (0..self.a.len())
+            .filter(|j| attrs.contains(&self.rho[*j]))
+            .collect();
+        let n = self.a.first()?.len();
+        let m = rows.len();
+
+        // Augmented system A_I^T ω = (1, 0, .., 0), one equation per column of A.
+        let mut sys: Vec<Vec<Scalar>> = (0..n)
+            .map(|c| {
+                let mut eq: Vec<Scalar> = rows.iter().map(|&j| self.a[j][c]).collect();
+                eq.push(if c == 0 {
+                    Scalar::one()
+                } else {
+                    Scalar::zero()
+                });
+                eq
+            })
+            .collect();
+
+        // Reduce to row echelon form, remembering the pivot column of each equation.
+        let mut pivots = Vec::new();
+        for col in 0..m {
+            let r = pivots.len();
+            let p = match (r..n).find(|&k| sys[k][col] != Scalar::zero()) {
+                Some(p) => p,
+                None => continue,
+            };
+            sys.swap(r, p);
+
+            let inv = sys[r][col].invert().unwrap();
+            for x in sys[r][col..].iter_mut() {
+                *x *= inv;
+            }
+
+            let pivot = sys[r].clone();
+            for (k, eq) in sys.iter_mut().enumerate() {
+                let f = eq[col];
+                if k != r && f != Scalar::zero() {
+                    for (x, p) in eq[col..].iter_mut().zip(pivot[col..].iter()) {
+                        *x -= f * p;
+                    }
+                }
+            }
+            pivots.push(col);
+        }
+
+        // The system is inconsistent if an equation without pivot remains with a non-zero target.
+        if sys[pivots.len()..].iter().any(|eq| eq[m] != Scalar::zero()) {
+            return None;
+        }
+
+        // Free variables are set to zero.
+        Some(
+            pivots
+                .iter()
+                .enumerate()
+                .filter(|(r, _)| sys[*r][m] != Scalar::zero())
+                .map(|(r, &col)| (rows[col], sys[r][m]))
+                .collect(),
+        )
+    }
+
+    pub(crate) fn write(&self, buf: &mut Vec<u8>) {
+        put_len(buf, self.a.len());
+        for row in self.a.iter() {
+            put_len(buf, row.len());
+            for x in row.iter() {
+                buf.extend_from_slice(&x.to_bytes());
+            }
+        }
+
+        put_len(buf, self.rho.len());
+        for x in self.rho.iter() {
+            buf.extend_from_slice(&x.0.to_bytes());
+        }
+    }
+
+    pub(crate) fn read(r: &mut Reader) -> Option<Self> {
+        let a = r.read_vec(|r| r.read_vec(|r| r.read_scalar()))?;
+        let rho = r.read_vec(|r| r.read_scalar().map(Attribute))?;
+
+        let ap = AccessPolicy { a, rho };
+
+        ap.validate().ok().map(|_| ap)
+    }
+}
+
+impl CompressVar for AccessPolicy {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.write(&mut buf);
+
+        buf
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let ap = AccessPolicy::read(&mut r)?;
+
+        r.finish(ap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attr(s: &str) -> Attribute {
+        Attribute::derive_str(s)
+    }
+
+    /// Checks whether the weighted rows of the given attributes sum to (1, 0, .., 0).
+    fn reconstructs(ap: &AccessPolicy, coeffs: &[(&str, Scalar)]) -> bool {
+        let mut sum = vec![Scalar::zero(); ap.a[0].len()];
+        for (name, w) in coeffs {
+            let j = ap.rho.iter().position(|x| *x == attr(name)).unwrap();
+            for (s, a) in sum.iter_mut().zip(ap.a[j].iter()) {
+                *s += w * a;
+            }
+        }
+
+        sum[0] == Scalar::one() && sum[1..].iter().all(|x| *x == Scalar::zero())
+    }
+
+    /// Lagrange coefficients for interpolation at zero.
+    fn lagrange(xs: &[u64]) -> Vec<Scalar> {
+        xs.iter()
+            .map(|&i| {
+                xs.iter()
+                    .filter(|&&j| j != i)
+                    .fold(Scalar::one(), |acc, &j| {
+                        let (i, j) = (Scalar::from(i), Scalar::from(j));
+                        acc * j * (j - i).invert().unwrap()
+                    })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn attribute_domain_separation() {
+        assert_eq!(attr("role:admin"), attr("role:admin"));
+        assert_ne!(attr("role:admin"), attr("role:user"));
+        assert_ne!(
+            attr("role:admin").0,
+            Identity::derive_str("role:admin").to_scalar()
+        );
+    }
+
+    #[test]
+    fn and_equals_gen_a() {
+        let ap = compile("a AND b AND c AND d").unwrap();
+
+        assert_eq!(ap.a, gen_a(4));
+        assert_eq!(ap.rho, vec![attr("a"), attr("b"), attr("c"), attr("d")]);
+        assert_eq!(compile("a").unwrap().a, gen_a(1));
+    }
+
+    #[test]
+    fn or_of_and() {
+        let ap = compile("(dept:eng AND role:admin) OR clearance:top").unwrap();
+        let one = Scalar::one();
+
+        assert_eq!(ap.a.len(), 3);
+        assert!(reconstructs(&ap, &[("dept:eng", one), ("role:admin", one)]));
+        assert!(reconstructs(&ap, &[("clearance:top", one)]));
+        assert!(!reconstructs(&ap, &[("dept:eng", one)]));
+        assert!(!reconstructs(&ap, &[("role:admin", one)]));
+    }
+
+    #[test]
+    fn threshold() {
+        let ap = compile("2 of (a, b, c)").unwrap();
+        let names = ["a", "b", "c"];
+
+        for i in 0..3 {
+            for j in i + 1..3 {
+                let w = lagrange(&[i as u64 + 1, j as u64 + 1]);
+                assert!(reconstructs(&ap, &[(names[i], w[0]), (names[j], w[1])]));
+            }
+        }
+
+        // A single row never suffices.
+        for name in names.iter() {
+            assert!(!reconstructs(&ap, &[(name, Scalar::one())]));
+        }
+    }
+
+    #[test]
+    fn nested_threshold() {
+        let ap = compile("2 of (a AND b, c, d OR e)").unwrap();
+        let w = lagrange(&[1, 3]);
+
+        assert_eq!(ap.a.len(), 5);
+        assert!(reconstructs(&ap, &[("a", w[0]), ("b", w[0]), ("e", w[1])]));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(compile(""), Err(PolicyError::UnexpectedEnd));
+        assert_eq!(compile("a AND"), Err(PolicyError::UnexpectedEnd));
+        assert_eq!(compile("(a OR b"), Err(PolicyError::UnexpectedEnd));
+        assert_eq!(compile("a b"), Err(PolicyError::UnexpectedToken(2)));
+        assert_eq!(compile("a OR OR b"), Err(PolicyError::UnexpectedToken(5)));
+        assert_eq!(compile("a)"), Err(PolicyError::UnexpectedToken(1)));
+        assert_eq!(
+            compile("3 of (a, b)"),
+            Err(PolicyError::InvalidThreshold(0))
+        );
+        assert_eq!(
+            compile("0 of (a, b)"),
+            Err(PolicyError::InvalidThreshold(0))
+        );
+    }
+
+    #[test]
+    fn nesting_limit() {
+        let nested = |n: usize| alloc::format!("{}a{}", "(".repeat(n), ")".repeat(n));
+
+        assert!(compile(&nested(MAX_NESTING)).is_ok());
+        assert_eq!(
+            compile(&nested(MAX_NESTING + 1)),
+            Err(PolicyError::NestingTooDeep(MAX_NESTING))
+        );
+        assert_eq!(
+            compile(&nested(1_000_000)),
+            Err(PolicyError::NestingTooDeep(MAX_NESTING))
+        );
+        assert_eq!(
+            compile(&"1 of (".repeat(MAX_NESTING + 1)),
+            Err(PolicyError::NestingTooDeep(6 * MAX_NESTING))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "rwac")]
+    fn decaps_authorized() {
+        use crate::kem::{rwac::RWAC, ABKEM};
+
+        let mut rng = rand::thread_rng();
+        let (pk, sk) = RWAC::setup(&mut rng);
+        let ap = compile("(dept:eng AND role:admin) OR 2 of (a, b, c)").unwrap();
+        let (ct, k) = RWAC::encaps(&pk, &ap, &mut rng).unwrap();
+
+        let authorized: [&[&str]; 3] = [&["role:admin", "dept:eng"], &["c", "a"], &["x", "b", "c"]];
+        for names in authorized {
+            let attrs: Vec<Attribute> = names.iter().map(|n| attr(n)).collect();
+            let usk = RWAC::extract_usk(None, &sk, &attrs, &mut rng);
+            assert_eq!(RWAC::decaps(None, &usk, &ct).unwrap(), k);
+        }
+
+        let usk = RWAC::extract_usk(None, &sk, &[attr("dept:eng"), attr("a")], &mut rng);
+        assert!(matches!(
+            RWAC::decaps(None, &usk, &ct),
+            Err(Error::PolicyNotSatisfied)
+        ));
+    }
+
+    #[test]
+    fn validate() {
+        let attrs = [attr("a"), attr("b")];
+
+        assert!(compile("a OR b").unwrap().validate().is_ok());
+
+        let malformed = [
+            AccessPolicy {
+                a: vec![],
+                rho: vec![],
+            },
+            AccessPolicy {
+                a: vec![vec![]],
+                rho: attrs[..1].to_vec(),
+            },
+            AccessPolicy {
+                a: vec![vec![Scalar::one(), Scalar::one()], vec![Scalar::one()]],
+                rho: attrs.to_vec(),
+            },
+            AccessPolicy {
+                a: gen_a(2),
+                rho: attrs[..1].to_vec(),
+            },
+        ];
+        for ap in malformed {
+            assert!(matches!(ap.validate(), Err(Error::MalformedPolicy)));
+        }
+    }
+
+    #[test]
+    fn solve_coefficients() {
+        let ap = compile("(a AND b) OR 2 of (c, d AND e, f)").unwrap();
+
+        let authorized: [&[&str]; 5] = [
+            &["a", "b"],
+            &["c", "f"],
+            &["d", "e", "f"],
+            &["a", "b", "c", "d", "e", "f"],
+            &["b", "c", "e", "f"],
+        ];
+        for names in authorized {
+            let attrs: Vec<Attribute> = names.iter().map(|n| attr(n)).collect();
+            let omega = ap.reconstruction_coefficients(&attrs).unwrap();
+            let coeffs: Vec<(&str, Scalar)> = omega
+                .iter()
+                .map(|(j, w)| (*names.iter().find(|n| attr(n) == ap.rho[*j]).unwrap(), *w))
+                .collect();
+
+            assert!(reconstructs(&ap, &coeffs));
+        }
+
+        let unauthorized: [&[&str]; 5] = [&[], &["a"], &["c", "d"], &["b", "c", "e"], &["x", "y"]];
+        for names in unauthorized {
+            let attrs: Vec<Attribute> = names.iter().map(|n| attr(n)).collect();
+            assert!(ap.reconstruction_coefficients(&attrs).is_none());
+        }
+    }
+
+    #[test]
+    fn solve_coefficients_and() {
+        let names = ["a", "b", "c", "d"];
+        let attrs: Vec<Attribute> = names.iter().map(|n| attr(n)).collect();
+        let ap = AccessPolicy {
+            a: gen_a(4),
+            rho: attrs.clone(),
+        };
+
+        let omega = ap.reconstruction_coefficients(&attrs).unwrap();
+        assert_eq!(
+            omega,
+            vec![
+                (0, Scalar::one()),
+                (1, Scalar::one()),
+                (2, Scalar::one()),
+                (3, Scalar::one())
+            ]
+        );
+        assert!(ap.reconstruction_coefficients(&attrs[1..]).is_none());
+    }
+}
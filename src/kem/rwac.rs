@@ -1,7 +1,9 @@
 //! IND-ID-CCA2 secure KEM from ABE from Rouselakis and Waters (RW13).
 
-use crate::kem::{Error, SharedSecret};
+pub use crate::kem::policy::{gen_a, AccessPolicy, Attribute, LSSSMatrix};
+use crate::kem::{Error, SharedSecret, ABKEM};
 use crate::util::*;
+use crate::CompressVar;
 use alloc::vec::Vec;
 use irmaseal_curve::{
     multi_miller_loop, pairing, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt,
@@ -9,44 +11,6 @@ use irmaseal_curve::{
 };
 use rand::{CryptoRng, Rng};
 
-pub type LSSSMatrix = Vec<Vec<Scalar>>;
-
-#[derive(Clone)]
-pub struct AccessPolicy {
-    pub a: LSSSMatrix,
-    pub rho: Vec<Scalar>,
-}
-
-/// Generates an LSSS matrix of size n for AND-policies.
-pub fn gen_a(n: usize) -> LSSSMatrix {
-    if n == 1 {
-        vec![vec![Scalar::one()]]
-    } else {
-        let mut a_mat = Vec::new();
-
-        // 1, 1, 0, 0, ..
-        let mut v = vec![Scalar::default(); n];
-        v[0] = Scalar::one();
-        v[1] = Scalar::one();
-        a_mat.push(v);
-
-        for i in 1..n - 1 {
-            // 0, 0, -1, 1, 0, 0, etc.
-            v = vec![Scalar::default(); n];
-            v[i] = Scalar::one().neg();
-            v[i + 1] = Scalar::one();
-            a_mat.push(v);
-        }
-
-        // 0, 0, ..,  -1
-        v = vec![Scalar::default(); n];
-        v[n - 1] = Scalar::one().neg();
-        a_mat.push(v);
-
-        a_mat
-    }
-}
-
 /// Public key parameters generated by the PKG used to encaps messages.
 /// Also known as MPK.
 #[derive(Clone, PartialEq)]
@@ -85,7 +49,7 @@ pub struct SecretKey {
 
 /// User secret key. Can be used to decaps the corresponding ciphertext.
 /// Also known as USK_{S}.
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct UserSecretKey {
     /// K_i
     k0: [G2Affine; 2],
@@ -100,12 +64,12 @@ pub struct UserSecretKey {
     /// K_2,att,1
     k2_attrs: Vec<[G2Affine; 2]>,
     /// S, set of attributes
-    attrs: Vec<Scalar>,
+    attrs: Vec<Attribute>,
 }
 
 /// Encrypted message. Can only be decapsed with a corresponding user secret key.
 /// Also known as CT_{A}
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct CipherText {
     /// C'_i
     c0: [G1Affine; 2],
@@ -123,12 +87,514 @@ pub struct CipherText {
     ap: AccessPolicy,
 }
 
+/// Public key with fixed-base tables for all its elements.
+///
+/// Speeds up encapsulating many shared secrets using the same public key,
+/// see [`RWAC::encaps_prepared`]. Takes about 2.5 MiB of memory.
+#[derive(Clone, Debug)]
+pub struct PreparedPublicKey {
+    /// A
+    a: FixedBase<Gt>,
+    /// g_i
+    g: [FixedBase<G1Projective>; 2],
+    /// Bi
+    b: [FixedBase<G1Projective>; 2],
+    /// B'i
+    bprime: [FixedBase<G1Projective>; 2],
+    /// Bl,i
+    b_mat: [[FixedBase<G1Projective>; 2]; 2],
+    /// B'l,i
+    bprime_mat: [[FixedBase<G1Projective>; 2]; 2],
+}
+
+impl From<&PublicKey> for PreparedPublicKey {
+    fn from(pk: &PublicKey) -> Self {
+        let prepare = |x: &[G1Affine; 2]| {
+            [
+                FixedBase::new(&G1Projective::from(x[0])),
+                FixedBase::new(&G1Projective::from(x[1])),
+            ]
+        };
+
+        PreparedPublicKey {
+            a: FixedBase::new(&pk.a),
+            g: prepare(&pk.g),
+            b: prepare(&pk.b),
+            bprime: prepare(&pk.bprime),
+            b_mat: [prepare(&pk.b_mat[0]), prepare(&pk.b_mat[1])],
+            bprime_mat: [prepare(&pk.bprime_mat[0]), prepare(&pk.bprime_mat[1])],
+        }
+    }
+}
+
+/// User secret key with the line coefficients of the Miller loop precomputed.
+///
+/// Speeds up decapsulating many ciphertexts using the same user secret key,
+/// see [`RWAC::decaps_prepared`].
+///
+/// Since decapsulation pairs with K_i + K_i(2) + y' K_i(3) for a ciphertext-dependent y',
+/// K_i + K_i(2) and K_i(3) are prepared separately and paired with C'_i and y' C'_i, respectively.
+#[derive(Clone, Debug)]
+pub struct PreparedUserSecretKey {
+    /// K_i + K_i(2)
+    k02: [G2Prepared; 2],
+    /// K'_i
+    k1: [G2Prepared; 2],
+    /// K_i(3)
+    k3: [G2Prepared; 2],
+    /// K_1,att,1
+    k1_attrs: Vec<[G2Prepared; 2]>,
+    /// K_2,att,1
+    k2_attrs: Vec<[G2Prepared; 2]>,
+    /// S, set of attributes
+    attrs: Vec<Attribute>,
+}
+
+impl From<&UserSecretKey> for PreparedUserSecretKey {
+    fn from(usk: &UserSecretKey) -> Self {
+        let prepare = |k: &[G2Affine; 2]| [G2Prepared::from(k[0]), G2Prepared::from(k[1])];
+
+        let mut k02 = [G2Affine::default(); 2];
+        G2Projective::batch_normalize(
+            &[
+                G2Projective::from(usk.k0[0]) + usk.k2[0],
+                G2Projective::from(usk.k0[1]) + usk.k2[1],
+            ],
+            &mut k02,
+        );
+
+        PreparedUserSecretKey {
+            k02: prepare(&k02),
+            k1: prepare(&usk.k1),
+            k3: prepare(&usk.k3),
+            k1_attrs: usk.k1_attrs.iter().map(prepare).collect(),
+            k2_attrs: usk.k2_attrs.iter().map(prepare).collect(),
+            attrs: usk.attrs.clone(),
+        }
+    }
+}
+
+/// Raises a ciphertext component to its coefficient, which is one for AND-policies.
+fn scale(c: &G1Affine, w: &Scalar) -> G1Affine {
+    if *w == Scalar::one() {
+        *c
+    } else {
+        (c * w).into()
+    }
+}
+
+/// Computes the RPC hash y' of the ciphertext and the reconstruction coefficients
+/// of its access policy for a set of attributes.
+fn prepare_ct(
+    ct: &CipherText,
+    attrs: &[Attribute],
+) -> Result<(Scalar, Vec<(usize, Scalar)>), Error> {
+    ct.ap.validate()?;
+
+    let n1 = ct.ap.a.len();
+
+    let mut rpc_input = Vec::<G1Affine>::new();
+    for i in 0..2 {
+        for j in 0..n1 {
+            rpc_input.push(ct.c0[i]);
+            rpc_input.push(ct.c1[i][j]);
+            rpc_input.push(ct.c2[i][j]);
+            rpc_input.push(ct.c3[i][j]);
+        }
+    }
+
+    let yprime = rpc(&ct.k, &rpc_input);
+
+    let omega = ct
+        .ap
+        .reconstruction_coefficients(attrs)
+        .ok_or(Error::PolicyNotSatisfied)?;
+
+    Ok((yprime, omega))
+}
+
+/// Element of a public key that is multiplied during encapsulation.
+#[derive(Clone, Copy)]
+enum Base {
+    G(usize),
+    B(usize),
+    BPrime(usize),
+    BMat(usize, usize),
+    BPrimeMat(usize, usize),
+}
+
+/// Scalar multiplications with the elements of a public key.
+///
+/// Allows sharing encapsulation between a [`PublicKey`] and a [`PreparedPublicKey`].
+trait Bases {
+    fn a(&self, s: &Scalar) -> Gt;
+
+    /// Computes the sum of the multiples of elements of the public key.
+    fn mul<const N: usize>(&self, terms: [(Base, Scalar); N]) -> G1Projective;
+}
+
+impl Bases for PublicKey {
+    fn a(&self, s: &Scalar) -> Gt {
+        self.a * s
+    }
+
+    fn mul<const N: usize>(&self, terms: [(Base, Scalar); N]) -> G1Projective {
+        multi_mul_g1(&terms.map(|(base, s)| {
+            let p = match base {
+                Base::G(i) => self.g[i],
+                Base::B(i) => self.b[i],
+                Base::BPrime(i) => self.bprime[i],
+                Base::BMat(l, i) => self.b_mat[l][i],
+                Base::BPrimeMat(l, i) => self.bprime_mat[l][i],
+            };
+
+            (p, s)
+        }))
+    }
+}
+
+impl Bases for PreparedPublicKey {
+    fn a(&self, s: &Scalar) -> Gt {
+        self.a.mul(s)
+    }
+
+    fn mul<const N: usize>(&self, terms: [(Base, Scalar); N]) -> G1Projective {
+        terms
+            .iter()
+            .map(|(base, s)| {
+                let table = match *base {
+                    Base::G(i) => &self.g[i],
+                    Base::B(i) => &self.b[i],
+                    Base::BPrime(i) => &self.bprime[i],
+                    Base::BMat(l, i) => &self.b_mat[l][i],
+                    Base::BPrimeMat(l, i) => &self.bprime_mat[l][i],
+                };
+
+                table.mul(s)
+            })
+            .sum()
+    }
+}
+
+/// Encapsulates a shared secret for an access policy.
+fn encaps_with<P: Bases, R: Rng + CryptoRng>(
+    pk: &P,
+    ap: &AccessPolicy,
+    rng: &mut R,
+) -> Result<(CipherText, SharedSecret), Error> {
+    ap.validate()?;
+
+    let n1 = ap.a.len();
+    let n2 = ap.a[0].len();
+
+    let s = rand_scalar(rng);
+    let k = pk.a(&s);
+
+    // s_j in paper
+    let s_vec: Vec<Scalar> = (0..n1).map(|_| rand_scalar(rng)).collect();
+
+    // v_j' = [v_0, v_1, ..., v_n2-1] with v_0 = 0
+    let v: Vec<Scalar> = (0..n2)
+        .map(|j| {
+            if j == 0 {
+                Scalar::default()
+            } else {
+                rand_scalar(rng)
+            }
+        })
+        .collect();
+
+    let λ: Vec<Scalar> =
+        ap.a.iter()
+            .map(|r| {
+                r.iter()
+                    .zip(v.iter())
+                    .fold(Scalar::default(), |a, (x, y)| a + x * y)
+            })
+            .collect();
+
+    let c0 = [0, 1].map(|i| pk.mul([(Base::G(i), s)]).into());
+
+    let c1: [Vec<G1Affine>; 2] = [0, 1].map(|i| {
+        (0..n1)
+            .map(|j| {
+                pk.mul([
+                    (Base::B(i), ap.a[j][0] * s),
+                    (Base::G(i), λ[j]),
+                    (Base::BPrime(i), s_vec[j]),
+                ])
+                .into()
+            })
+            .collect()
+    });
+
+    let c2: [Vec<G1Affine>; 2] = [0, 1].map(|i| {
+        (0..n1)
+            .map(|j| {
+                pk.mul([
+                    (Base::BMat(1, i), s_vec[j] * ap.rho[j].0),
+                    (Base::BMat(0, i), s_vec[j]),
+                ])
+                .into()
+            })
+            .collect()
+    });
+
+    let c3: [Vec<G1Affine>; 2] = [0, 1].map(|i| {
+        s_vec
+            .iter()
+            .map(|s_j| pk.mul([(Base::G(i), *s_j)]).into())
+            .collect()
+    });
+
+    let mut smallk = [0u8; 32];
+    rng.fill_bytes(&mut smallk);
+
+    let mut rpc_input = Vec::<G1Affine>::new();
+    for i in 0..2 {
+        for j in 0..n1 {
+            rpc_input.push(c0[i]);
+            rpc_input.push(c1[i][j]);
+            rpc_input.push(c2[i][j]);
+            rpc_input.push(c3[i][j]);
+        }
+    }
+    let xprime = rpc(&smallk, &rpc_input);
+
+    let c4 = [0, 1].map(|i| {
+        pk.mul([
+            (Base::BPrimeMat(1, i), xprime * s),
+            (Base::BPrimeMat(0, i), s),
+        ])
+        .into()
+    });
+
+    Ok((
+        CipherText {
+            c0,
+            c1,
+            c2,
+            c3,
+            c4,
+            k: smallk,
+            ap: ap.clone(),
+        },
+        SharedSecret::from(&k),
+    ))
+}
+
+/// Precalculations on the MSK that are shared by all user secret keys.
+struct KeyParams {
+    /// d_6 (d_3, -d_2)
+    d6: [Scalar; 2],
+    /// bbar_i
+    bbar: [Scalar; 2],
+    /// b'bar_i
+    bprimebar: [Scalar; 2],
+    /// bbar_l,i
+    bbar_mat: [[Scalar; 2]; 2],
+    /// b'bar_l,i
+    bprimebar_mat: [[Scalar; 2]; 2],
+}
+
+impl From<&SecretKey> for KeyParams {
+    fn from(sk: &SecretKey) -> Self {
+        let d6 = sk.d[4] * (sk.d[0] * sk.d[3] - sk.d[1] * sk.d[2]).invert().unwrap(); // cannot panic, see sampling in setup and from_bytes
+
+        let bar = |x: &[Scalar; 3]| {
+            [
+                d6 * (x[0] * sk.d[3] - x[1] * sk.d[1]),
+                d6 * (-x[0] * sk.d[2] + x[1] * sk.d[0]),
+            ]
+        };
+
+        KeyParams {
+            d6: [sk.d[3] * d6, -sk.d[2] * d6],
+            bbar: bar(&sk.b),
+            bprimebar: bar(&sk.bprime),
+            bbar_mat: [bar(&sk.b_mat[0]), bar(&sk.b_mat[1])],
+            bprimebar_mat: [bar(&sk.bprime_mat[0]), bar(&sk.bprime_mat[1])],
+        }
+    }
+}
+
+impl KeyParams {
+    /// Computes the discrete logarithms of the components of a user secret key,
+    /// in the order k0, k1, k2, k3 followed by k1_att and k2_att for each attribute.
+    fn usk_exponents<R: Rng + CryptoRng>(
+        &self,
+        sk: &SecretKey,
+        attrs: &[Attribute],
+        rng: &mut R,
+    ) -> Vec<Scalar> {
+        let r = rand_scalar(rng);
+
+        let mut alpha = [[Scalar::default(); 2]; 2];
+        alpha[0][0] = rand_scalar(rng);
+        alpha[1][0] = rand_scalar(rng);
+        alpha[0][1] = sk.alpha[0] - alpha[0][0];
+        alpha[1][1] = sk.alpha[1] - alpha[1][0];
+
+        let mut out = Vec::with_capacity(8 + 4 * attrs.len());
+
+        // Compute all static key components, k0 through k3
+        out.extend_from_slice(&[
+            // K_i
+            alpha[0][0] - r * self.bbar[0],
+            alpha[1][0] - r * self.bbar[1],
+            // K'_i
+            r * self.d6[0],
+            r * self.d6[1],
+            // K_i(2)
+            alpha[0][1] - r * self.bprimebar_mat[0][0],
+            alpha[1][1] - r * self.bprimebar_mat[0][1],
+            // K_i(3)
+            -r * self.bprimebar_mat[1][0],
+            -r * self.bprimebar_mat[1][1],
+        ]);
+
+        // Compute all dynamic key components, based on the number of attributes
+        for attr in attrs {
+            let r_att = rand_scalar(rng);
+
+            out.extend_from_slice(&[
+                // K_1,att,i
+                -r_att * (self.bbar_mat[1][0] * attr.0 + self.bbar_mat[0][0])
+                    - r * self.bprimebar[0],
+                -r_att * (self.bbar_mat[1][1] * attr.0 + self.bbar_mat[0][1])
+                    - r * self.bprimebar[1],
+                // K_2,att,i
+                r_att * self.d6[0],
+                r_att * self.d6[1],
+            ]);
+        }
+
+        out
+    }
+}
+
+/// Assembles a user secret key from its normalized components,
+/// in the order produced by [`KeyParams::usk_exponents`].
+fn usk_from_affine(out: &[G2Affine], attrs: &[Attribute]) -> UserSecretKey {
+    let (k1_attrs, k2_attrs) = out[8..]
+        .chunks_exact(4)
+        .map(|c| ([c[0], c[1]], [c[2], c[3]]))
+        .unzip();
+
+    UserSecretKey {
+        k0: [out[0], out[1]],
+        k1: [out[2], out[3]],
+        k2: [out[4], out[5]],
+        k3: [out[6], out[7]],
+        k1_attrs,
+        k2_attrs,
+        attrs: attrs.to_vec(),
+    }
+}
+
+/// The CCA2 secure ABE KEM that results by applying our transform to RW13.
 #[derive(Clone)]
 pub struct RWAC;
 
 impl RWAC {
+    /// Extract user secret keys for many sets of attributes using the MSK.
+    ///
+    /// Computes the precalculations on the MSK only once, uses a fixed-base table of the
+    /// generator, and normalizes all keys using one inversion.
+    pub fn extract_usk_batch<R: Rng + CryptoRng>(
+        sk: &SecretKey,
+        attr_sets: &[&[Attribute]],
+        rng: &mut R,
+    ) -> Vec<UserSecretKey> {
+        let h = FixedBase::new(&G2Projective::generator());
+        let params = KeyParams::from(sk);
+
+        let batch: Vec<G2Projective> = attr_sets
+            .iter()
+            .flat_map(|attrs| params.usk_exponents(sk, attrs, rng))
+            .map(|e| h.mul(&e))
+            .collect();
+        let mut out = vec![G2Affine::default(); batch.len()];
+        G2Projective::batch_normalize(&batch, &mut out);
+
+        let mut offset = 0;
+        attr_sets
+            .iter()
+            .map(|attrs| {
+                let len = 8 + 4 * attrs.len();
+                let usk = usk_from_affine(&out[offset..offset + len], attrs);
+                offset += len;
+                usk
+            })
+            .collect()
+    }
+
+    /// Encapsulate a shared secret using a prepared master public key and an access policy.
+    ///
+    /// # Errors
+    ///
+    /// See [`ABKEM::encaps`].
+    pub fn encaps_prepared<R: Rng + CryptoRng>(
+        pk: &PreparedPublicKey,
+        ap: &AccessPolicy,
+        rng: &mut R,
+    ) -> Result<(CipherText, SharedSecret), Error> {
+        encaps_with(pk, ap, rng)
+    }
+
+    /// Derive the same SharedSecret from the CipherText using a PreparedUserSecretKey.
+    ///
+    /// # Errors
+    ///
+    /// See [`ABKEM::decaps`].
+    pub fn decaps_prepared(
+        _pk: Option<&PublicKey>,
+        usk: &PreparedUserSecretKey,
+        ct: &CipherText,
+    ) -> Result<SharedSecret, Error> {
+        let (yprime, omega) = prepare_ct(ct, &usk.attrs)?;
+
+        let mut pairs = Vec::<(G1Affine, &G2Prepared)>::new();
+
+        for i in 0..2 {
+            pairs.push((ct.c0[i], &usk.k02[i]));
+            pairs.push(((ct.c0[i] * yprime).into(), &usk.k3[i]));
+            pairs.push((
+                G1Affine::from(
+                    omega.iter().fold(G1Projective::default(), |acc, (j, w)| {
+                        acc + scale(&ct.c1[i][*j], w)
+                    }) + ct.c4[i],
+                ),
+                &usk.k1[i],
+            ));
+
+            for (j, w) in omega.iter() {
+                let idx = usk.attrs.iter().position(|&x| x == ct.ap.rho[*j]).unwrap();
+                pairs.push((scale(&ct.c2[i][*j], w), &usk.k2_attrs[idx][i]));
+                pairs.push((scale(&ct.c3[i][*j], w), &usk.k1_attrs[idx][i]));
+            }
+        }
+
+        let pairs_ref: Vec<(&G1Affine, &G2Prepared)> = pairs.iter().map(|(i, j)| (i, *j)).collect();
+        let k = multi_miller_loop(&pairs_ref[..]).final_exponentiation();
+
+        Ok(SharedSecret::from(&k))
+    }
+}
+
+impl ABKEM for RWAC {
+    const IDENTIFIER: &'static str = "rwac";
+
+    type Pk = PublicKey;
+    type Sk = SecretKey;
+    type Usk = UserSecretKey;
+    type Ct = CipherText;
+    type Policy = AccessPolicy;
+    type AttributeSet = [Attribute];
+    type Ss = SharedSecret;
+
     /// Generate a keypair used by the Private Key Generator (PKG).
-    pub fn setup<R: Rng + CryptoRng>(rng: &mut R) -> (PublicKey, SecretKey) {
+    fn setup<R: Rng + CryptoRng>(rng: &mut R) -> (PublicKey, SecretKey) {
         let g = G1Affine::generator();
         let h = G2Affine::generator();
 
@@ -200,301 +666,364 @@ impl RWAC {
     }
 
     /// Extract a user secret key for a set of attributes.
-    pub fn extract_usk<R: Rng + CryptoRng>(
+    fn extract_usk<R: Rng + CryptoRng>(
+        _pk: Option<&PublicKey>,
         sk: &SecretKey,
-        attrs: &[Scalar],
+        attrs: &[Attribute],
         rng: &mut R,
     ) -> UserSecretKey {
         let h = G2Affine::generator();
-        let r = rand_scalar(rng);
 
-        let mut alpha = [[Scalar::default(); 2]; 2];
-        alpha[0][0] = rand_scalar(rng);
-        alpha[1][0] = rand_scalar(rng);
-        alpha[0][1] = sk.alpha[0] - alpha[0][0];
-        alpha[1][1] = sk.alpha[1] - alpha[1][0];
+        let batch: Vec<G2Projective> = KeyParams::from(sk)
+            .usk_exponents(sk, attrs, rng)
+            .iter()
+            .map(|e| h * e)
+            .collect();
+        let mut out = vec![G2Affine::default(); batch.len()];
+        G2Projective::batch_normalize(&batch, &mut out);
 
-        // some precalculations
-        let d6 = sk.d[4] * (sk.d[0] * sk.d[3] - sk.d[1] * sk.d[2]).invert().unwrap(); // cannot panic, see sampling in setup
+        usk_from_affine(&out, attrs)
+    }
 
-        let bbar = [
-            d6 * (sk.b[0] * sk.d[3] - sk.b[1] * sk.d[1]),
-            d6 * (-sk.b[0] * sk.d[2] + sk.b[1] * sk.d[0]),
-        ];
+    fn encaps<R: Rng + CryptoRng>(
+        pk: &PublicKey,
+        ap: &AccessPolicy,
+        rng: &mut R,
+    ) -> Result<(CipherText, SharedSecret), Error> {
+        encaps_with(pk, ap, rng)
+    }
 
-        let bprimebar = [
-            d6 * (sk.bprime[0] * sk.d[3] - sk.bprime[1] * sk.d[1]),
-            d6 * (-sk.bprime[0] * sk.d[2] + sk.bprime[1] * sk.d[0]),
-        ];
+    /// Derive the same SharedSecret from the CipherText using a UserSecretKey.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an [`Error::PolicyNotSatisfied`] when the attributes of the
+    /// user secret key do not satisfy the access policy,
+    /// and an [`Error::MalformedPolicy`] when the access policy itself is malformed.
+    /// Otherwise, ciphertexts are always implicitly rejected.
+    fn decaps(
+        _pk: Option<&PublicKey>,
+        usk: &UserSecretKey,
+        ct: &CipherText,
+    ) -> Result<SharedSecret, Error> {
+        let (yprime, omega) = prepare_ct(ct, &usk.attrs)?;
 
-        let bbar_mat = [
-            [
-                // l = 0
-                d6 * (sk.b_mat[0][0] * sk.d[3] - sk.b_mat[0][1] * sk.d[1]),
-                d6 * (-sk.b_mat[0][0] * sk.d[2] + sk.b_mat[0][1] * sk.d[0]),
-            ],
-            [
-                // l = 1
-                d6 * (sk.b_mat[1][0] * sk.d[3] - sk.b_mat[1][1] * sk.d[1]),
-                d6 * (-sk.b_mat[1][0] * sk.d[2] + sk.b_mat[1][1] * sk.d[0]),
-            ],
-        ];
+        let mut pairs = Vec::<(G1Affine, G2Prepared)>::new();
 
-        let bprimebar_mat = [
-            [
-                // l = 0
-                d6 * (sk.bprime_mat[0][0] * sk.d[3] - sk.bprime_mat[0][1] * sk.d[1]),
-                d6 * (-sk.bprime_mat[0][0] * sk.d[2] + sk.bprime_mat[0][1] * sk.d[0]),
-            ],
-            [
-                // l = 1
-                d6 * (sk.bprime_mat[1][0] * sk.d[3] - sk.bprime_mat[1][1] * sk.d[1]),
-                d6 * (-sk.bprime_mat[1][0] * sk.d[2] + sk.bprime_mat[1][1] * sk.d[0]),
-            ],
-        ];
+        for i in 0..2 {
+            pairs.push((
+                ct.c0[i],
+                G2Prepared::from(G2Affine::from(
+                    G2Projective::from(usk.k0[i])
+                        + G2Projective::from(usk.k2[i])
+                        + (usk.k3[i] * yprime),
+                )),
+            ));
 
-        // Compute all static key components, k0 through k2
-        // K_i
-        let k0 = [
-            (h * (alpha[0][0] - r * bbar[0])).into(),
-            (h * (alpha[1][0] - r * bbar[1])).into(),
-        ];
+            pairs.push((
+                G1Affine::from(
+                    omega.iter().fold(G1Projective::default(), |acc, (j, w)| {
+                        acc + scale(&ct.c1[i][*j], w)
+                    }) + ct.c4[i],
+                ),
+                G2Prepared::from(usk.k1[i]),
+            ));
 
-        // K'_i
-        let k1 = [
-            (h * (r * sk.d[3] * d6)).into(),
-            (h * (-r * sk.d[2] * d6)).into(),
-        ];
+            for (j, w) in omega.iter() {
+                let idx = usk.attrs.iter().position(|&x| x == ct.ap.rho[*j]).unwrap();
+                pairs.push((
+                    scale(&ct.c2[i][*j], w),
+                    G2Prepared::from(usk.k2_attrs[idx][i]),
+                ));
+                pairs.push((
+                    scale(&ct.c3[i][*j], w),
+                    G2Prepared::from(usk.k1_attrs[idx][i]),
+                ));
+            }
+        }
 
-        // K_i(2)
-        let k2 = [
-            (h * (alpha[0][1] - r * bprimebar_mat[0][0])).into(),
-            (h * (alpha[1][1] - r * bprimebar_mat[0][1])).into(),
-        ];
+        let pairs_ref: Vec<(&G1Affine, &G2Prepared)> = pairs.iter().map(|(i, j)| (i, j)).collect();
+        let k = multi_miller_loop(&pairs_ref[..]).final_exponentiation();
 
-        // K_i(3)
-        let k3 = [
-            (h * (-r * bprimebar_mat[1][0])).into(),
-            (h * (-r * bprimebar_mat[1][1])).into(),
-        ];
+        Ok(SharedSecret::from(&k))
+    }
+}
 
-        // Compute all dynamic key components, based on the number of attributes
-        // K_1,att,i
-        let mut k1_attrs = Vec::<[G2Affine; 2]>::new();
-        // K_2,att,i
-        let mut k2_attrs = Vec::<[G2Affine; 2]>::new();
+impl CompressVar for PublicKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
 
-        for attr in attrs {
-            let r_att = rand_scalar(rng);
+        buf.extend_from_slice(&self.a.to_compressed());
+        for i in 0..2 {
+            buf.extend_from_slice(&self.g[i].to_compressed());
+            buf.extend_from_slice(&self.b[i].to_compressed());
+            buf.extend_from_slice(&self.bprime[i].to_compressed());
+        }
+        for el in self.b_mat.iter().chain(self.bprime_mat.iter()).flatten() {
+            buf.extend_from_slice(&el.to_compressed());
+        }
 
-            k1_attrs.push([
-                (h * (-r_att * (bbar_mat[1][0] * attr + bbar_mat[0][0]) - r * bprimebar[0])).into(), // i = 0
-                (h * (-r_att * (bbar_mat[1][1] * attr + bbar_mat[0][1]) - r * bprimebar[1])).into(), // i = 1
-            ]);
+        buf
+    }
 
-            // minimal speedup possible:
-            // can save 2 scalar multiplicatons here
-            k2_attrs.push([
-                (h * (r_att * sk.d[3] * d6)).into(),  // K_2,att,1
-                (h * (-r_att * sk.d[2] * d6)).into(), // K_2,att,2
-            ]);
-        }
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
 
-        UserSecretKey {
-            k0,
-            k1,
-            k2,
-            k3,
-            k1_attrs,
-            k2_attrs,
-            attrs: attrs.to_vec(),
+        let a = r.read_gt()?;
+        let mut g = [G1Affine::default(); 2];
+        let mut b = [G1Affine::default(); 2];
+        let mut bprime = [G1Affine::default(); 2];
+        let mut b_mat = [[G1Affine::default(); 2]; 2];
+        let mut bprime_mat = [[G1Affine::default(); 2]; 2];
+
+        for i in 0..2 {
+            g[i] = r.read_g1()?;
+            b[i] = r.read_g1()?;
+            bprime[i] = r.read_g1()?;
         }
+        for el in b_mat.iter_mut().chain(bprime_mat.iter_mut()).flatten() {
+            *el = r.read_g1()?;
+        }
+
+        r.finish(PublicKey {
+            a,
+            g,
+            b,
+            bprime,
+            b_mat,
+            bprime_mat,
+        })
     }
+}
 
-    pub fn encaps<R: Rng + CryptoRng>(
-        pk: &PublicKey,
-        ap: &AccessPolicy,
-        rng: &mut R,
-    ) -> (CipherText, SharedSecret) {
-        // assumes all rows of A are the same length..
-        // also assumes a has at least 1 row
-        let n1 = ap.a.len();
-        let n2 = ap.a[0].len();
+impl CompressVar for SecretKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
 
-        let s = rand_scalar(rng);
-        let k = pk.a * s;
+        for x in self.alpha.iter() {
+            buf.extend_from_slice(&x.to_bytes());
+        }
+        for x in self.d.iter().chain(self.b.iter()).chain(self.bprime.iter()) {
+            buf.extend_from_slice(&x.to_bytes());
+        }
+        for x in self.b_mat.iter().chain(self.bprime_mat.iter()).flatten() {
+            buf.extend_from_slice(&x.to_bytes());
+        }
 
-        // s_j in paper
-        let s_vec: Vec<Scalar> = (0..n1).map(|_| rand_scalar(rng)).collect();
+        buf
+    }
 
-        // v_j' = [v_0, v_1, ..., v_n2-1] with v_0 = 0
-        let v: Vec<Scalar> = (0..n2)
-            .map(|j| {
-                if j == 0 {
-                    Scalar::default()
-                } else {
-                    rand_scalar(rng)
-                }
-            })
-            .collect();
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
 
-        let λ: Vec<Scalar> =
-            ap.a.iter()
-                .map(|r| {
-                    r.iter()
-                        .zip(v.iter())
-                        .fold(Scalar::default(), |a, (x, y)| a + x * y)
-                })
-                .collect();
-
-        let c0 = [(pk.g[0] * s).into(), (pk.g[1] * s).into()];
-
-        let c1: [Vec<G1Affine>; 2] = [
-            (0..n1)
-                .map(|j| {
-                    (pk.b[0] * (ap.a[j][0] * s) + pk.g[0] * λ[j] + pk.bprime[0] * s_vec[j]).into()
-                })
-                .collect(),
-            (0..n1)
-                .map(|j| {
-                    (pk.b[1] * (ap.a[j][0] * s) + pk.g[1] * λ[j] + pk.bprime[1] * s_vec[j]).into()
-                })
-                .collect(),
-        ];
+        let mut alpha = [Scalar::default(); 2];
+        let mut d = [Scalar::default(); 5];
+        let mut b = [Scalar::default(); 3];
+        let mut bprime = [Scalar::default(); 3];
+        let mut b_mat = [[Scalar::default(); 3]; 2];
+        let mut bprime_mat = [[Scalar::default(); 3]; 2];
 
-        let c2: [Vec<G1Affine>; 2] = [
-            (0..n1)
-                .map(|j| {
-                    (pk.b_mat[1][0] * (s_vec[j] * ap.rho[j]) + pk.b_mat[0][0] * s_vec[j]).into()
-                })
-                .collect(),
-            (0..n1)
-                .map(|j| {
-                    (pk.b_mat[1][1] * (s_vec[j] * ap.rho[j]) + pk.b_mat[0][1] * s_vec[j]).into()
-                })
-                .collect(),
-        ];
+        for x in alpha.iter_mut().chain(d.iter_mut()) {
+            *x = r.read_scalar()?;
+        }
 
-        let c3: [Vec<G1Affine>; 2] = [
-            (0..n1).map(|j| (pk.g[0] * s_vec[j]).into()).collect(),
-            (0..n1).map(|j| (pk.g[1] * s_vec[j]).into()).collect(),
-        ];
+        // Extraction inverts d_1 d_4 - d_2 d_3, see sampling in setup.
+        if d[0] * d[3] == d[1] * d[2] {
+            return None;
+        }
+        for x in b.iter_mut().chain(bprime.iter_mut()) {
+            *x = r.read_scalar()?;
+        }
+        for x in b_mat.iter_mut().chain(bprime_mat.iter_mut()).flatten() {
+            *x = r.read_scalar()?;
+        }
 
-        let mut smallk = [0u8; 32];
-        rng.fill_bytes(&mut smallk);
+        r.finish(SecretKey {
+            alpha,
+            d,
+            b,
+            bprime,
+            b_mat,
+            bprime_mat,
+        })
+    }
+}
+
+impl CompressVar for UserSecretKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
 
-        let mut rpc_input = Vec::<G1Affine>::new();
         for i in 0..2 {
-            for j in 0..n1 {
-                rpc_input.push(c0[i]);
-                rpc_input.push(c1[i][j]);
-                rpc_input.push(c2[i][j]);
-                rpc_input.push(c3[i][j]);
+            buf.extend_from_slice(&self.k0[i].to_compressed());
+            buf.extend_from_slice(&self.k1[i].to_compressed());
+            buf.extend_from_slice(&self.k2[i].to_compressed());
+            buf.extend_from_slice(&self.k3[i].to_compressed());
+        }
+        for attrs in [&self.k1_attrs, &self.k2_attrs] {
+            put_len(&mut buf, attrs.len());
+            for k in attrs.iter() {
+                buf.extend_from_slice(&k[0].to_compressed());
+                buf.extend_from_slice(&k[1].to_compressed());
             }
         }
-        let xprime = rpc(&smallk, &rpc_input);
-
-        let c4 = [
-            (pk.bprime_mat[1][0] * (xprime * s) + pk.bprime_mat[0][0] * s).into(),
-            (pk.bprime_mat[1][1] * (xprime * s) + pk.bprime_mat[0][1] * s).into(),
-        ];
+        put_len(&mut buf, self.attrs.len());
+        for x in self.attrs.iter() {
+            buf.extend_from_slice(&x.0.to_bytes());
+        }
 
-        (
-            CipherText {
-                c0,
-                c1,
-                c2,
-                c3,
-                c4,
-                k: smallk,
-                ap: ap.clone(),
-            },
-            SharedSecret::from(&k),
-        )
+        buf
     }
 
-    /// Derive the same SharedSecret from the CipherText using a UserSecretKey.
-    ///
-    /// This operation always implicitly rejects ciphertexts and therefore never errors.
-    pub fn decaps(usk: &UserSecretKey, ct: &CipherText) -> Result<SharedSecret, Error> {
-        let n1 = ct.ap.a.len();
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+
+        let mut k0 = [G2Affine::default(); 2];
+        let mut k1 = [G2Affine::default(); 2];
+        let mut k2 = [G2Affine::default(); 2];
+        let mut k3 = [G2Affine::default(); 2];
 
-        let mut rpc_input = Vec::<G1Affine>::new();
         for i in 0..2 {
-            for j in 0..n1 {
-                rpc_input.push(ct.c0[i]);
-                rpc_input.push(ct.c1[i][j]);
-                rpc_input.push(ct.c2[i][j]);
-                rpc_input.push(ct.c3[i][j]);
-            }
+            k0[i] = r.read_g2()?;
+            k1[i] = r.read_g2()?;
+            k2[i] = r.read_g2()?;
+            k3[i] = r.read_g2()?;
         }
+        let k1_attrs = r.read_vec(|r| Some([r.read_g2()?, r.read_g2()?]))?;
+        let k2_attrs = r.read_vec(|r| Some([r.read_g2()?, r.read_g2()?]))?;
+        let attrs = r.read_vec(|r| r.read_scalar().map(Attribute))?;
 
-        let yprime = rpc(&ct.k, &rpc_input);
+        if k1_attrs.len() != attrs.len() || k2_attrs.len() != attrs.len() {
+            return None;
+        }
 
-        let upsilon: Vec<usize> = (0..n1)
-            .filter(|j| usk.attrs.contains(&ct.ap.rho[*j]))
-            .collect();
+        r.finish(UserSecretKey {
+            k0,
+            k1,
+            k2,
+            k3,
+            k1_attrs,
+            k2_attrs,
+            attrs,
+        })
+    }
+}
 
-        let mut pairs = Vec::<(G1Affine, G2Prepared)>::new();
+impl CompressVar for CipherText {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
 
+        self.ap.write(&mut buf);
         for i in 0..2 {
-            pairs.push((
-                ct.c0[i],
-                G2Prepared::from(G2Affine::from(
-                    G2Projective::from(usk.k0[i])
-                        + G2Projective::from(usk.k2[i])
-                        + (usk.k3[i] * yprime),
-                )),
-            ));
+            buf.extend_from_slice(&self.c0[i].to_compressed());
+            buf.extend_from_slice(&self.c4[i].to_compressed());
+        }
+        for c in [&self.c1, &self.c2, &self.c3] {
+            for row in c.iter() {
+                put_len(&mut buf, row.len());
+                for el in row.iter() {
+                    buf.extend_from_slice(&el.to_compressed());
+                }
+            }
+        }
+        buf.extend_from_slice(&self.k);
 
-            pairs.push((
-                G1Affine::from(
-                    upsilon
-                        .iter()
-                        .fold(G1Projective::default(), |acc, j| acc + ct.c1[i][*j])
-                        + ct.c4[i],
-                ),
-                G2Prepared::from(usk.k1[i]),
-            ));
+        buf
+    }
 
-            for j in upsilon.iter() {
-                let idx = usk.attrs.iter().position(|&x| x == ct.ap.rho[*j]).unwrap();
-                pairs.push((ct.c2[i][*j], G2Prepared::from(usk.k2_attrs[idx][i])));
-                pairs.push((ct.c3[i][*j], G2Prepared::from(usk.k1_attrs[idx][i])));
-            }
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+
+        let ap = AccessPolicy::read(&mut r)?;
+        let n1 = ap.a.len();
+
+        let mut c0 = [G1Affine::default(); 2];
+        let mut c4 = [G1Affine::default(); 2];
+        for i in 0..2 {
+            c0[i] = r.read_g1()?;
+            c4[i] = r.read_g1()?;
         }
 
-        let pairs_ref: Vec<(&G1Affine, &G2Prepared)> = pairs.iter().map(|(i, j)| (i, j)).collect();
-        let k = multi_miller_loop(&pairs_ref[..]).final_exponentiation();
+        let mut read_row = || {
+            let row = r.read_vec(|r| r.read_g1())?;
+            if row.len() == n1 {
+                Some(row)
+            } else {
+                None
+            }
+        };
 
-        Ok(SharedSecret::from(&k))
+        let c1 = [read_row()?, read_row()?];
+        let c2 = [read_row()?, read_row()?];
+        let c3 = [read_row()?, read_row()?];
+        let k = *r.read_bytes::<32>()?;
+
+        r.finish(CipherText {
+            c0,
+            c1,
+            c2,
+            c3,
+            c4,
+            k,
+            ap,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Derive;
 
-    #[test]
-    fn test_enc_dec() {
-        let n = 10;
-
-        let mut rng = rand::thread_rng();
-        let (mpk, msk) = RWAC::setup(&mut rng);
-
-        let s: Vec<Scalar> = (0..n).map(|_| rand_scalar(&mut rng)).collect();
-        let usk_s = RWAC::extract_usk(&msk, &s[..], &mut rng);
+    test_abkem!(RWAC);
 
-        let a = gen_a(n);
-        let mut rho = s.clone();
-
-        rho.reverse(); // should still work
-        let ap = AccessPolicy { a, rho };
+    #[test]
+    fn eq_encaps_prepared() {
+        let results = perform_default();
+        let prepared = PreparedPublicKey::from(&results.pk);
 
-        let (ct, ss) = RWAC::encaps(&mpk, &ap, &mut rng);
+        let ap = AccessPolicy {
+            a: gen_a(N),
+            rho: results.attrs.clone(),
+        };
+        let (c, k) = RWAC::encaps_prepared(&prepared, &ap, &mut rand::thread_rng()).unwrap();
+        assert_eq!(RWAC::decaps(None, &results.usk, &c).unwrap(), k);
+    }
 
-        let ss2 = RWAC::decaps(&usk_s, &ct).unwrap();
+    #[test]
+    fn eq_extract_usk_batch() {
+        let mut rng = rand::thread_rng();
+        let results = perform_default();
+        let (_, sk) = RWAC::setup(&mut rng);
+        let other = [Attribute::derive_str("other")];
+
+        let usks = RWAC::extract_usk_batch(&results.sk, &[&results.attrs, &other, &[]], &mut rng);
+        assert_eq!(usks.len(), 3);
+        assert_eq!(usks[1].attrs, other);
+        assert!(usks[2].k1_attrs.is_empty());
+        assert_eq!(RWAC::decaps(None, &usks[0], &results.c).unwrap(), results.k);
+
+        let usks = RWAC::extract_usk_batch(&sk, &[&results.attrs], &mut rng);
+        assert_ne!(RWAC::decaps(None, &usks[0], &results.c).unwrap(), results.k);
+    }
 
-        assert_eq!(ss, ss2);
+    #[test]
+    fn eq_decaps_prepared() {
+        let results = perform_default();
+        let prepared = PreparedUserSecretKey::from(&results.usk);
+
+        assert_eq!(
+            RWAC::decaps_prepared(None, &prepared, &results.c).unwrap(),
+            results.k
+        );
+
+        let ap = AccessPolicy {
+            a: gen_a(2),
+            rho: vec![results.attrs[3], Attribute::derive_str("other")],
+        };
+        let (c, _) = RWAC::encaps(&results.pk, &ap, &mut rand::thread_rng()).unwrap();
+        assert!(matches!(
+            RWAC::decaps_prepared(None, &prepared, &c),
+            Err(Error::PolicyNotSatisfied)
+        ));
     }
 }
@@ -1,59 +1,23 @@
 //! IND-ID-CPA secure KEM from ABE from Rouselakis and Waters (RW13).
 
-use crate::kem::{Error, SharedSecret};
+pub use crate::kem::policy::{gen_a, AccessPolicy, Attribute, LSSSMatrix};
+use crate::kem::{Error, SharedSecret, ABKEM};
 use crate::util::*;
+use crate::CompressVar;
 use alloc::vec::Vec;
 use irmaseal_curve::{
     multi_miller_loop, pairing, G1Affine, G1Projective, G2Affine, G2Prepared, Gt, Scalar,
 };
 use rand::{CryptoRng, Rng};
 
-pub type LSSSMatrix = Vec<Vec<Scalar>>;
-
-#[derive(Clone)]
-pub struct AccessPolicy {
-    pub a: LSSSMatrix,
-    pub rho: Vec<Scalar>,
-}
-
-/// Generates an LSSS matrix of size n for AND-policies.
-pub fn gen_a(n: usize) -> LSSSMatrix {
-    if n == 1 {
-        vec![vec![Scalar::one()]]
-    } else {
-        let mut a_mat = Vec::new();
-
-        // 1, 1, 0, 0, ..
-        let mut v = vec![Scalar::default(); n];
-        v[0] = Scalar::one();
-        v[1] = Scalar::one();
-        a_mat.push(v);
-
-        for i in 1..n - 1 {
-            // 0, 0, -1, 1, 0, 0, etc.
-            v = vec![Scalar::default(); n];
-            v[i] = Scalar::one().neg();
-            v[i + 1] = Scalar::one();
-            a_mat.push(v);
-        }
-
-        // 0, 0, ..,  -1
-        v = vec![Scalar::default(); n];
-        v[n - 1] = Scalar::one().neg();
-        a_mat.push(v);
-
-        a_mat
-    }
-}
-
 /// Public key parameters generated by the PKG used to encaps messages.
 /// Also known as MPK.
 #[derive(Clone, PartialEq)]
 pub struct PublicKey {
     /// A
-    a: Gt,
+    pub(crate) a: Gt,
     /// g_i
-    g: [G1Affine; 2],
+    pub(crate) g: [G1Affine; 2],
     /// Bi
     b: [G1Affine; 2],
     /// B'i
@@ -80,23 +44,23 @@ pub struct SecretKey {
 
 /// User secret key. Can be used to decaps the corresponding ciphertext.
 /// Also known as USK_{S}.
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct UserSecretKey {
     /// K_i
     k0: [G2Affine; 2],
     /// K'_i
     k1: [G2Affine; 2],
     /// K_1,att,1
-    k1_attrs: Vec<[G2Affine; 2]>,
+    pub(crate) k1_attrs: Vec<[G2Affine; 2]>,
     /// K_2,att,1
-    k2_attrs: Vec<[G2Affine; 2]>,
+    pub(crate) k2_attrs: Vec<[G2Affine; 2]>,
     /// S, set of attributes
-    attrs: Vec<Scalar>,
+    pub(crate) attrs: Vec<Attribute>,
 }
 
 /// Encrypted message. Can only be decapsed with a corresponding user secret key.
 /// Also known as CT_{A}
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct CipherText {
     /// C'_i
     c0: [G1Affine; 2],
@@ -110,12 +74,23 @@ pub struct CipherText {
     ap: AccessPolicy,
 }
 
+/// The CPA secure ABE KEM by Rouselakis and Waters.
 #[derive(Clone)]
 pub struct RWACCPA;
 
-impl RWACCPA {
+impl ABKEM for RWACCPA {
+    const IDENTIFIER: &'static str = "rwac_cpa";
+
+    type Pk = PublicKey;
+    type Sk = SecretKey;
+    type Usk = UserSecretKey;
+    type Ct = CipherText;
+    type Policy = AccessPolicy;
+    type AttributeSet = [Attribute];
+    type Ss = SharedSecret;
+
     /// Generate a keypair used by the Private Key Generator (PKG).
-    pub fn setup<R: Rng + CryptoRng>(rng: &mut R) -> (PublicKey, SecretKey) {
+    fn setup<R: Rng + CryptoRng>(rng: &mut R) -> (PublicKey, SecretKey) {
         let g = G1Affine::generator();
         let h = G2Affine::generator();
 
@@ -177,16 +152,17 @@ impl RWACCPA {
     }
 
     /// Extract a user secret key for a set of attributes.
-    pub fn extract_usk<R: Rng + CryptoRng>(
+    fn extract_usk<R: Rng + CryptoRng>(
+        _pk: Option<&PublicKey>,
         sk: &SecretKey,
-        attrs: &[Scalar],
+        attrs: &[Attribute],
         rng: &mut R,
     ) -> UserSecretKey {
         let h = G2Affine::generator();
         let r = rand_scalar(rng);
 
         // some precalculations
-        let d6 = sk.d[4] * (sk.d[0] * sk.d[3] - sk.d[1] * sk.d[2]).invert().unwrap(); // cannot panic, see sampling in setup
+        let d6 = sk.d[4] * (sk.d[0] * sk.d[3] - sk.d[1] * sk.d[2]).invert().unwrap(); // cannot panic, see sampling in setup and from_bytes
 
         let bbar = [
             d6 * (sk.b[0] * sk.d[3] - sk.b[1] * sk.d[1]),
@@ -234,8 +210,10 @@ impl RWACCPA {
             let r_att = rand_scalar(rng);
 
             k1_attrs.push([
-                (h * (-r_att * (bbar_mat[1][0] * attr + bbar_mat[0][0]) - r * bprimebar[0])).into(), // i = 0
-                (h * (-r_att * (bbar_mat[1][1] * attr + bbar_mat[0][1]) - r * bprimebar[1])).into(), // i = 1
+                (h * (-r_att * (bbar_mat[1][0] * attr.0 + bbar_mat[0][0]) - r * bprimebar[0]))
+                    .into(), // i = 0
+                (h * (-r_att * (bbar_mat[1][1] * attr.0 + bbar_mat[0][1]) - r * bprimebar[1]))
+                    .into(), // i = 1
             ]);
 
             // minimal speedup possible:
@@ -255,76 +233,19 @@ impl RWACCPA {
         }
     }
 
-    pub fn encaps<R: Rng + CryptoRng>(
+    fn encaps<R: Rng + CryptoRng>(
         pk: &PublicKey,
         ap: &AccessPolicy,
         rng: &mut R,
-    ) -> (CipherText, SharedSecret) {
-        // assumes all rows of A are the same length..
-        // also assumes a has at least 1 row
-        let n1 = ap.a.len();
-        let n2 = ap.a[0].len();
+    ) -> Result<(CipherText, SharedSecret), Error> {
+        ap.validate()?;
 
         let s = rand_scalar(rng);
         let k = pk.a * s;
 
-        // s_j in paper
-        let s_vec: Vec<Scalar> = (0..n1).map(|_| rand_scalar(rng)).collect();
-
-        // v_j' = [v_0, v_1, ..., v_n2-1] with v_0 = 0
-        let v: Vec<Scalar> = (0..n2)
-            .map(|j| {
-                if j == 0 {
-                    Scalar::default()
-                } else {
-                    rand_scalar(rng)
-                }
-            })
-            .collect();
-
-        let λ: Vec<Scalar> =
-            ap.a.iter()
-                .map(|r| {
-                    r.iter()
-                        .zip(v.iter())
-                        .fold(Scalar::default(), |a, (x, y)| a + x * y)
-                })
-                .collect();
-
-        let c0 = [(pk.g[0] * s).into(), (pk.g[1] * s).into()];
-
-        let c1 = [
-            (0..n1)
-                .map(|j| {
-                    (pk.b[0] * (ap.a[j][0] * s) + pk.g[0] * λ[j] + pk.bprime[0] * s_vec[j]).into()
-                })
-                .collect(),
-            (0..n1)
-                .map(|j| {
-                    (pk.b[1] * (ap.a[j][0] * s) + pk.g[1] * λ[j] + pk.bprime[1] * s_vec[j]).into()
-                })
-                .collect(),
-        ];
-
-        let c2 = [
-            (0..n1)
-                .map(|j| {
-                    (pk.b_mat[1][0] * (s_vec[j] * ap.rho[j]) + pk.b_mat[0][0] * s_vec[j]).into()
-                })
-                .collect(),
-            (0..n1)
-                .map(|j| {
-                    (pk.b_mat[1][1] * (s_vec[j] * ap.rho[j]) + pk.b_mat[0][1] * s_vec[j]).into()
-                })
-                .collect(),
-        ];
+        let (c0, [c1, c2, c3]) = encrypt_rows(pk, &s, &policy_rows(ap, rng), rng);
 
-        let c3 = [
-            (0..n1).map(|j| (pk.g[0] * s_vec[j]).into()).collect(),
-            (0..n1).map(|j| (pk.g[1] * s_vec[j]).into()).collect(),
-        ];
-
-        (
+        Ok((
             CipherText {
                 c0,
                 c1,
@@ -333,71 +254,397 @@ impl RWACCPA {
                 ap: ap.clone(),
             },
             SharedSecret::from(&k),
-        )
+        ))
     }
 
     /// Derive the same SharedSecret from the CipherText using a UserSecretKey.
     ///
-    /// This operation always implicitly rejects ciphertexts and therefore never errors.
-    pub fn decaps(usk: &UserSecretKey, ct: &CipherText) -> Result<SharedSecret, Error> {
-        let n1 = ct.ap.a.len();
+    /// # Errors
+    ///
+    /// This function returns an [`Error::PolicyNotSatisfied`] when the attributes of the
+    /// user secret key do not satisfy the access policy,
+    /// and an [`Error::MalformedPolicy`] when the access policy itself is malformed.
+    /// Otherwise, ciphertexts are always implicitly rejected.
+    fn decaps(
+        _pk: Option<&PublicKey>,
+        usk: &UserSecretKey,
+        ct: &CipherText,
+    ) -> Result<SharedSecret, Error> {
+        ct.ap.validate()?;
 
-        let upsilon: Vec<usize> = (0..n1)
-            .filter(|j| usk.attrs.contains(&ct.ap.rho[*j]))
-            .collect();
+        let omega = omega_indices(&usk.attrs, &ct.ap).ok_or(Error::PolicyNotSatisfied)?;
 
-        let mut pairs = Vec::<(G1Affine, G2Prepared)>::new();
+        let k = decrypt_rows(usk, &ct.c0, [&ct.c1, &ct.c2, &ct.c3], &omega);
 
-        for i in 0..2 {
-            pairs.push((ct.c0[i], G2Prepared::from(usk.k0[i])));
+        Ok(SharedSecret::from(&k))
+    }
+}
+
+/// Samples shares λ_j = A_j · v of zero for every row of the matrix, where v = (0, v_2, .., v_n2).
+fn shares<R: Rng + CryptoRng>(a: &LSSSMatrix, rng: &mut R) -> Vec<Scalar> {
+    let n2 = a[0].len();
+
+    // v_j' = [v_0, v_1, ..., v_n2-1] with v_0 = 0
+    let v: Vec<Scalar> = (0..n2)
+        .map(|j| {
+            if j == 0 {
+                Scalar::default()
+            } else {
+                rand_scalar(rng)
+            }
+        })
+        .collect();
+
+    a.iter()
+        .map(|r| {
+            r.iter()
+                .zip(v.iter())
+                .fold(Scalar::default(), |a, (x, y)| a + x * y)
+        })
+        .collect()
+}
 
+/// Labels every row j of the policy with its first entry A_j,1, a fresh share λ_j of zero
+/// and its label ρ(j), as used by [`encrypt_rows`].
+pub(crate) fn policy_rows<R: Rng + CryptoRng>(
+    ap: &AccessPolicy,
+    rng: &mut R,
+) -> Vec<(Scalar, Scalar, Attribute)> {
+    ap.a.iter()
+        .zip(shares(&ap.a, rng))
+        .zip(ap.rho.iter())
+        .map(|((row, λ), attr)| (row[0], λ, *attr))
+        .collect()
+}
+
+/// Computes the reconstruction coefficients of the attributes for the policy, see [`decrypt_rows`].
+///
+/// Returns `None` if the attributes do not satisfy the policy.
+pub(crate) fn omega_indices(
+    attrs: &[Attribute],
+    ap: &AccessPolicy,
+) -> Option<Vec<(usize, Scalar, usize)>> {
+    let omega = ap.reconstruction_coefficients(attrs)?;
+
+    Some(
+        omega
+            .into_iter()
+            .map(|(j, w)| {
+                let idx = attrs.iter().position(|&x| x == ap.rho[j]).unwrap();
+                (j, w, idx)
+            })
+            .collect(),
+    )
+}
+
+/// Ciphertext components C_1,i,j, C_2,i,j and C_3,i,j, with one entry per row j of the policy.
+pub(crate) type Rows = [[Vec<G1Affine>; 2]; 3];
+
+/// Encrypts the key A^s to the rows of a policy.
+///
+/// Every row j is given by its first entry A_j,1, its share λ_j and its label ρ(j).
+/// Returns C'_i and the ciphertext components for every row.
+pub(crate) fn encrypt_rows<R: Rng + CryptoRng>(
+    pk: &PublicKey,
+    s: &Scalar,
+    rows: &[(Scalar, Scalar, Attribute)],
+    rng: &mut R,
+) -> ([G1Affine; 2], Rows) {
+    // s_j in paper
+    let s_vec: Vec<Scalar> = rows.iter().map(|_| rand_scalar(rng)).collect();
+
+    let c0 = [(pk.g[0] * s).into(), (pk.g[1] * s).into()];
+
+    let c1 = [0, 1].map(|i| {
+        rows.iter()
+            .zip(s_vec.iter())
+            .map(|((a, λ, _), s_j)| {
+                multi_mul_g1(&[(pk.b[i], a * s), (pk.g[i], *λ), (pk.bprime[i], *s_j)]).into()
+            })
+            .collect()
+    });
+
+    let c2 = [0, 1].map(|i| {
+        rows.iter()
+            .zip(s_vec.iter())
+            .map(|((_, _, attr), s_j)| {
+                multi_mul_g1(&[(pk.b_mat[1][i], s_j * attr.0), (pk.b_mat[0][i], *s_j)]).into()
+            })
+            .collect()
+    });
+
+    let c3 = [0, 1].map(|i| s_vec.iter().map(|s_j| (pk.g[i] * s_j).into()).collect());
+
+    (c0, [c1, c2, c3])
+}
+
+/// Recovers the key A^s from the ciphertext components.
+///
+/// Every element (j, ω_j, k) of omega is a reconstruction coefficient for row j,
+/// where k is the index of the label ρ(j) in the attributes of the user secret key.
+pub(crate) fn decrypt_rows(
+    usk: &UserSecretKey,
+    c0: &[G1Affine; 2],
+    [c1, c2, c3]: [&[Vec<G1Affine>; 2]; 3],
+    omega: &[(usize, Scalar, usize)],
+) -> Gt {
+    // Raises a ciphertext component to its coefficient, which is one for AND-policies.
+    let scale = |c: &G1Affine, w: &Scalar| -> G1Affine {
+        if *w == Scalar::one() {
+            *c
+        } else {
+            (c * w).into()
+        }
+    };
+
+    let mut pairs = Vec::<(G1Affine, G2Prepared)>::new();
+
+    for i in 0..2 {
+        pairs.push((c0[i], G2Prepared::from(usk.k0[i])));
+
+        pairs.push((
+            G1Affine::from(
+                omega
+                    .iter()
+                    .fold(G1Projective::default(), |acc, (j, w, _)| {
+                        acc + scale(&c1[i][*j], w)
+                    }),
+            ),
+            G2Prepared::from(usk.k1[i]),
+        ));
+
+        for (j, w, idx) in omega.iter() {
             pairs.push((
-                G1Affine::from(
-                    upsilon
-                        .iter()
-                        .fold(G1Projective::default(), |acc, j| acc + ct.c1[i][*j]),
-                ),
-                G2Prepared::from(usk.k1[i]),
+                scale(&c2[i][*j], w),
+                G2Prepared::from(usk.k2_attrs[*idx][i]),
             ));
+            pairs.push((
+                scale(&c3[i][*j], w),
+                G2Prepared::from(usk.k1_attrs[*idx][i]),
+            ));
+        }
+    }
 
-            for j in upsilon.iter() {
-                let idx = usk.attrs.iter().position(|&x| x == ct.ap.rho[*j]).unwrap();
-                pairs.push((ct.c2[i][*j], G2Prepared::from(usk.k2_attrs[idx][i])));
-                pairs.push((ct.c3[i][*j], G2Prepared::from(usk.k1_attrs[idx][i])));
+    let pairs_ref: Vec<(&G1Affine, &G2Prepared)> = pairs.iter().map(|(i, j)| (i, j)).collect();
+
+    multi_miller_loop(&pairs_ref[..]).final_exponentiation()
+}
+
+/// Serializes the ciphertext components of the rows.
+pub(crate) fn write_rows(buf: &mut Vec<u8>, rows: [&[Vec<G1Affine>; 2]; 3]) {
+    for c in rows {
+        for row in c.iter() {
+            put_len(buf, row.len());
+            for el in row.iter() {
+                buf.extend_from_slice(&el.to_compressed());
             }
         }
+    }
+}
+
+/// Reads the ciphertext components of the rows, each of which must contain n1 elements.
+pub(crate) fn read_rows(r: &mut Reader, n1: usize) -> Option<Rows> {
+    let mut read_row = || {
+        let row = r.read_vec(|r| r.read_g1())?;
+        if row.len() == n1 {
+            Some(row)
+        } else {
+            None
+        }
+    };
+
+    Some([
+        [read_row()?, read_row()?],
+        [read_row()?, read_row()?],
+        [read_row()?, read_row()?],
+    ])
+}
 
-        let pairs_ref: Vec<(&G1Affine, &G2Prepared)> = pairs.iter().map(|(i, j)| (i, j)).collect();
-        let k = multi_miller_loop(&pairs_ref[..]).final_exponentiation();
+impl CompressVar for PublicKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
 
-        Ok(SharedSecret::from(&k))
+        buf.extend_from_slice(&self.a.to_compressed());
+        for i in 0..2 {
+            buf.extend_from_slice(&self.g[i].to_compressed());
+            buf.extend_from_slice(&self.b[i].to_compressed());
+            buf.extend_from_slice(&self.bprime[i].to_compressed());
+        }
+        for el in self.b_mat.iter().flatten() {
+            buf.extend_from_slice(&el.to_compressed());
+        }
+
+        buf
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let pk = PublicKey::read(&mut r)?;
+
+        r.finish(pk)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl PublicKey {
+    pub(crate) fn read(r: &mut Reader) -> Option<Self> {
+        let a = r.read_gt()?;
+        let mut g = [G1Affine::default(); 2];
+        let mut b = [G1Affine::default(); 2];
+        let mut bprime = [G1Affine::default(); 2];
+        let mut b_mat = [[G1Affine::default(); 2]; 2];
 
-    #[test]
-    fn test_enc_dec() {
-        let n = 10;
+        for i in 0..2 {
+            g[i] = r.read_g1()?;
+            b[i] = r.read_g1()?;
+            bprime[i] = r.read_g1()?;
+        }
+        for el in b_mat.iter_mut().flatten() {
+            *el = r.read_g1()?;
+        }
 
-        let mut rng = rand::thread_rng();
-        let (mpk, msk) = RWACCPA::setup(&mut rng);
+        Some(PublicKey {
+            a,
+            g,
+            b,
+            bprime,
+            b_mat,
+        })
+    }
+}
 
-        let s: Vec<Scalar> = (0..n).map(|_| rand_scalar(&mut rng)).collect();
-        let usk_s = RWACCPA::extract_usk(&msk, &s[..], &mut rng);
+impl CompressVar for SecretKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
 
-        let a = gen_a(n);
-        let mut rho = s.clone();
+        for x in self.alpha.iter() {
+            buf.extend_from_slice(&x.to_bytes());
+        }
+        for x in self.d.iter().chain(self.b.iter()).chain(self.bprime.iter()) {
+            buf.extend_from_slice(&x.to_bytes());
+        }
+        for x in self.b_mat.iter().flatten() {
+            buf.extend_from_slice(&x.to_bytes());
+        }
 
-        rho.reverse(); // should still work
-        let ap = AccessPolicy { a, rho };
+        buf
+    }
 
-        let (ct, ss) = RWACCPA::encaps(&mpk, &ap, &mut rng);
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
 
-        let ss2 = RWACCPA::decaps(&usk_s, &ct).unwrap();
+        let mut alpha = [Scalar::default(); 2];
+        let mut d = [Scalar::default(); 5];
+        let mut b = [Scalar::default(); 3];
+        let mut bprime = [Scalar::default(); 3];
+        let mut b_mat = [[Scalar::default(); 3]; 2];
+
+        for x in alpha.iter_mut().chain(d.iter_mut()) {
+            *x = r.read_scalar()?;
+        }
+
+        // Extraction inverts d_1 d_4 - d_2 d_3, see sampling in setup.
+        if d[0] * d[3] == d[1] * d[2] {
+            return None;
+        }
+        for x in b.iter_mut().chain(bprime.iter_mut()) {
+            *x = r.read_scalar()?;
+        }
+        for x in b_mat.iter_mut().flatten() {
+            *x = r.read_scalar()?;
+        }
+
+        r.finish(SecretKey {
+            alpha,
+            d,
+            b,
+            bprime,
+            b_mat,
+        })
+    }
+}
+
+impl CompressVar for UserSecretKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+
+        for i in 0..2 {
+            buf.extend_from_slice(&self.k0[i].to_compressed());
+            buf.extend_from_slice(&self.k1[i].to_compressed());
+        }
+        for attrs in [&self.k1_attrs, &self.k2_attrs] {
+            put_len(&mut buf, attrs.len());
+            for k in attrs.iter() {
+                buf.extend_from_slice(&k[0].to_compressed());
+                buf.extend_from_slice(&k[1].to_compressed());
+            }
+        }
+        put_len(&mut buf, self.attrs.len());
+        for x in self.attrs.iter() {
+            buf.extend_from_slice(&x.0.to_bytes());
+        }
 
-        assert_eq!(ss, ss2);
+        buf
     }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+
+        let mut k0 = [G2Affine::default(); 2];
+        let mut k1 = [G2Affine::default(); 2];
+
+        for i in 0..2 {
+            k0[i] = r.read_g2()?;
+            k1[i] = r.read_g2()?;
+        }
+        let k1_attrs = r.read_vec(|r| Some([r.read_g2()?, r.read_g2()?]))?;
+        let k2_attrs = r.read_vec(|r| Some([r.read_g2()?, r.read_g2()?]))?;
+        let attrs = r.read_vec(|r| r.read_scalar().map(Attribute))?;
+
+        if k1_attrs.len() != attrs.len() || k2_attrs.len() != attrs.len() {
+            return None;
+        }
+
+        r.finish(UserSecretKey {
+            k0,
+            k1,
+            k1_attrs,
+            k2_attrs,
+            attrs,
+        })
+    }
+}
+
+impl CompressVar for CipherText {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+
+        self.ap.write(&mut buf);
+        for el in self.c0.iter() {
+            buf.extend_from_slice(&el.to_compressed());
+        }
+        write_rows(&mut buf, [&self.c1, &self.c2, &self.c3]);
+
+        buf
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+
+        let ap = AccessPolicy::read(&mut r)?;
+        let n1 = ap.a.len();
+
+        let c0 = [r.read_g1()?, r.read_g1()?];
+
+        let [c1, c2, c3] = read_rows(&mut r, n1)?;
+
+        r.finish(CipherText { c0, c1, c2, c3, ap })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Derive;
+
+    test_abkem!(RWACCPA);
 }
@@ -0,0 +1,380 @@
+//! IND-CCA2 secure KEM from ABE from Rouselakis and Waters (RW13), obtained via delegation.
+//!
+//! CCA security due to the general approach by Canetti, Halevi and Katz (CHK).
+//! * From: "[Chosen-Ciphertext Security from Identity-Based Encryption](https://eprint.iacr.org/2003/182.pdf)"
+//!
+//! Instead of a one-time signature, the ciphertext is authenticated using a commitment and a MAC,
+//! as proposed by Boneh, Canetti, Halevi and Katz (BCHK).
+//!
+//! Every ciphertext is bound to a one-time commitment, which takes the role of the one-time
+//! verification key. The commitment is encoded as 128 extra attributes, one per bit, which are
+//! added to the policy using an AND-gate. A user secret key contains the key components for both
+//! values of each bit, such that the user can always satisfy the extension of the policy.
+//!
+//! The commitment and MAC key are derived from the decommitment using SHAKE256,
+//! the MAC is instantiated using sha3_256.
+
+use crate::kem::rwac_cpa::{
+    decrypt_rows, encrypt_rows, omega_indices, policy_rows, read_rows, write_rows, RWACCPA,
+};
+pub use crate::kem::rwac_cpa::{
+    gen_a, AccessPolicy, Attribute, LSSSMatrix, PublicKey, SecretKey, UserSecretKey,
+};
+use crate::kem::{Error, SharedSecret, ABKEM, SS_BYTES};
+use crate::util::*;
+use crate::CompressVar;
+use alloc::vec::Vec;
+use irmaseal_curve::{G1Affine, Gt, Scalar};
+use rand::{CryptoRng, Rng};
+use subtle::ConstantTimeEq;
+use tiny_keccak::Hasher;
+
+/// Size of the commitment in bytes.
+const COM_BYTES: usize = 16;
+
+/// Size of the decommitment in bytes.
+const DEC_BYTES: usize = 32;
+
+/// Number of extra attributes that encode the commitment.
+const COM_BITS: usize = 8 * COM_BYTES;
+
+/// Size of the MAC key in bytes.
+const MAC_KEY_BYTES: usize = 32;
+
+/// Size of the MAC tag in bytes.
+const TAG_BYTES: usize = 32;
+
+/// Domain separation tags for the attributes, commitment and MAC key.
+const BIT_DST: &[u8] = b"cca_transforms-rwac_del-bit";
+const COM_DST: &[u8] = b"cca_transforms-rwac_del-com";
+const MAC_DST: &[u8] = b"cca_transforms-rwac_del-mac";
+
+/// Encrypted message. Can only be decapsed with a corresponding user secret key.
+/// Also known as CT_{A}
+#[derive(Clone, Debug, PartialEq)]
+pub struct CipherText {
+    /// C'_i
+    c0: [G1Affine; 2],
+    /// C1,i,j, including a row for each bit of the commitment
+    c1: [Vec<G1Affine>; 2],
+    /// C2,i,j, including a row for each bit of the commitment
+    c2: [Vec<G1Affine>; 2],
+    /// C3,i,j, including a row for each bit of the commitment
+    c3: [Vec<G1Affine>; 2],
+    /// Ap = (A, rho), without the commitment
+    ap: AccessPolicy,
+    /// Commitment
+    com: [u8; COM_BYTES],
+    /// Encrypted decommitment
+    dec: [u8; DEC_BYTES],
+    /// MAC over all other components
+    tag: [u8; TAG_BYTES],
+}
+
+/// The CCA2 secure ABE KEM that results by applying the CHK transform,
+/// using a commitment and MAC (BCHK), to RW13.
+#[derive(Clone)]
+pub struct RWACDEL;
+
+/// The attribute encoding that bit j of the commitment has value b.
+fn bit_attribute(j: usize, b: u8) -> Attribute {
+    let mut digest = tiny_keccak::Sha3::v512();
+    digest.update(BIT_DST);
+    digest.update(&(j as u16).to_be_bytes());
+    digest.update(&[b]);
+
+    let mut buf = [0u8; 64];
+    digest.finalize(&mut buf);
+
+    Attribute(Scalar::from_bytes_wide(&buf))
+}
+
+/// Bit j of the commitment.
+fn bit(com: &[u8; COM_BYTES], j: usize) -> u8 {
+    (com[j / 8] >> (7 - j % 8)) & 1
+}
+
+/// Hashes the decommitment with a domain separation tag.
+fn hash_dec<const N: usize>(dst: &[u8], dec: &[u8; DEC_BYTES]) -> [u8; N] {
+    let mut digest = tiny_keccak::Shake::v256();
+    digest.update(dst);
+    digest.update(dec);
+
+    let mut buf = [0u8; N];
+    digest.finalize(&mut buf);
+
+    buf
+}
+
+/// Derives the shared secret and the pad for the decommitment from the key.
+fn kdf(k: &Gt) -> (SharedSecret, [u8; DEC_BYTES]) {
+    let buf = shake256::<{ SS_BYTES + DEC_BYTES }>(&k.to_compressed());
+
+    let mut ss = [0u8; SS_BYTES];
+    let mut pad = [0u8; DEC_BYTES];
+    ss.copy_from_slice(&buf[..SS_BYTES]);
+    pad.copy_from_slice(&buf[SS_BYTES..]);
+
+    (SharedSecret(ss), pad)
+}
+
+fn xor(a: &[u8; DEC_BYTES], b: &[u8; DEC_BYTES]) -> [u8; DEC_BYTES] {
+    let mut res = [0u8; DEC_BYTES];
+    for (r, (x, y)) in res.iter_mut().zip(a.iter().zip(b.iter())) {
+        *r = x ^ y;
+    }
+
+    res
+}
+
+impl CipherText {
+    /// Serializes all components except the tag.
+    fn write_body(&self, buf: &mut Vec<u8>) {
+        self.ap.write(buf);
+        buf.extend_from_slice(&self.com);
+        for el in self.c0.iter() {
+            buf.extend_from_slice(&el.to_compressed());
+        }
+        write_rows(buf, [&self.c1, &self.c2, &self.c3]);
+        buf.extend_from_slice(&self.dec);
+    }
+
+    /// Computes the MAC over all components except the tag.
+    fn mac(&self, key: &[u8; MAC_KEY_BYTES]) -> [u8; TAG_BYTES] {
+        let mut body = Vec::new();
+        self.write_body(&mut body);
+
+        let mut digest = tiny_keccak::Sha3::v256();
+        digest.update(key);
+        digest.update(&body);
+
+        let mut buf = [0u8; TAG_BYTES];
+        digest.finalize(&mut buf);
+
+        buf
+    }
+}
+
+impl ABKEM for RWACDEL {
+    const IDENTIFIER: &'static str = "rwac_del";
+
+    type Pk = PublicKey;
+    type Sk = SecretKey;
+    type Usk = UserSecretKey;
+    type Ct = CipherText;
+    type Policy = AccessPolicy;
+    type AttributeSet = [Attribute];
+    type Ss = SharedSecret;
+
+    /// Generate a keypair used by the Private Key Generator (PKG).
+    fn setup<R: Rng + CryptoRng>(rng: &mut R) -> (PublicKey, SecretKey) {
+        RWACCPA::setup(rng)
+    }
+
+    /// Extract a user secret key for a set of attributes.
+    ///
+    /// The key additionally contains the attributes for both values of every bit of the commitment.
+    fn extract_usk<R: Rng + CryptoRng>(
+        _pk: Option<&PublicKey>,
+        sk: &SecretKey,
+        attrs: &[Attribute],
+        rng: &mut R,
+    ) -> UserSecretKey {
+        let mut all = attrs.to_vec();
+        all.extend((0..COM_BITS).flat_map(|j| [bit_attribute(j, 0), bit_attribute(j, 1)]));
+
+        RWACCPA::extract_usk(None, sk, &all, rng)
+    }
+
+    fn encaps<R: Rng + CryptoRng>(
+        pk: &PublicKey,
+        ap: &AccessPolicy,
+        rng: &mut R,
+    ) -> Result<(CipherText, SharedSecret), Error> {
+        ap.validate()?;
+
+        let mut dec = [0u8; DEC_BYTES];
+        rng.fill(&mut dec);
+        let com = hash_dec(COM_DST, &dec);
+
+        // The policy is extended by an AND-gate with the bits of the commitment,
+        // a row of A gets -A_j,1 in each of the new columns and each bit gets a unit row.
+        // Hence the shares are λ_j - A_j,1 z for the rows of A and z_l for the bits, with z = Σ z_l.
+        let s = rand_scalar(rng);
+        let k = pk.a * s;
+
+        let z: Vec<Scalar> = (0..COM_BITS).map(|_| rand_scalar(rng)).collect();
+        let zsum = z.iter().fold(Scalar::zero(), |acc, z_l| acc + z_l);
+
+        let rows: Vec<(Scalar, Scalar, Attribute)> = policy_rows(ap, rng)
+            .into_iter()
+            .map(|(a, λ, attr)| (a, λ - a * zsum, attr))
+            .chain(
+                z.iter()
+                    .enumerate()
+                    .map(|(l, z_l)| (Scalar::zero(), *z_l, bit_attribute(l, bit(&com, l)))),
+            )
+            .collect();
+
+        let (c0, [c1, c2, c3]) = encrypt_rows(pk, &s, &rows, rng);
+
+        let (ss, pad) = kdf(&k);
+
+        let mut ct = CipherText {
+            c0,
+            c1,
+            c2,
+            c3,
+            ap: ap.clone(),
+            com,
+            dec: xor(&dec, &pad),
+            tag: [0u8; TAG_BYTES],
+        };
+        ct.tag = ct.mac(&hash_dec(MAC_DST, &dec));
+
+        Ok((ct, ss))
+    }
+
+    /// Derive the same SharedSecret from the CipherText using a UserSecretKey.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an [`Error::PolicyNotSatisfied`] when the attributes of the
+    /// user secret key do not satisfy the access policy,
+    /// an [`Error::MalformedPolicy`] when the access policy itself is malformed,
+    /// and an [`Error::Decapsulation`] when the commitment or MAC does not verify (explicit rejection).
+    fn decaps(
+        _pk: Option<&PublicKey>,
+        usk: &UserSecretKey,
+        ct: &CipherText,
+    ) -> Result<SharedSecret, Error> {
+        ct.ap.validate()?;
+
+        // The attributes of the user come before the attributes for the bits.
+        let n_user = usk
+            .attrs
+            .len()
+            .checked_sub(2 * COM_BITS)
+            .ok_or(Error::PolicyNotSatisfied)?;
+        let user_attrs = &usk.attrs[..n_user];
+        let n1 = ct.ap.a.len();
+
+        // Every bit row has coefficient one.
+        let omega: Vec<(usize, Scalar, usize)> = omega_indices(user_attrs, &ct.ap)
+            .ok_or(Error::PolicyNotSatisfied)?
+            .into_iter()
+            .chain((0..COM_BITS).map(|l| {
+                (
+                    n1 + l,
+                    Scalar::one(),
+                    n_user + 2 * l + bit(&ct.com, l) as usize,
+                )
+            }))
+            .collect();
+
+        if [&ct.c1, &ct.c2, &ct.c3]
+            .iter()
+            .flat_map(|c| c.iter())
+            .any(|row| row.len() != n1 + COM_BITS)
+        {
+            return Err(Error::Decapsulation);
+        }
+
+        let k = decrypt_rows(usk, &ct.c0, [&ct.c1, &ct.c2, &ct.c3], &omega);
+        let (ss, pad) = kdf(&k);
+
+        let dec = xor(&ct.dec, &pad);
+        let com_ok = hash_dec::<COM_BYTES>(COM_DST, &dec).ct_eq(&ct.com);
+        let tag_ok = ct.mac(&hash_dec(MAC_DST, &dec)).ct_eq(&ct.tag);
+
+        if (com_ok & tag_ok).into() {
+            Ok(ss)
+        } else {
+            Err(Error::Decapsulation)
+        }
+    }
+}
+
+impl CompressVar for CipherText {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+
+        self.write_body(&mut buf);
+        buf.extend_from_slice(&self.tag);
+
+        buf
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+
+        let ap = AccessPolicy::read(&mut r)?;
+        let n1 = ap.a.len() + COM_BITS;
+
+        let com = *r.read_bytes::<COM_BYTES>()?;
+        let c0 = [r.read_g1()?, r.read_g1()?];
+
+        let [c1, c2, c3] = read_rows(&mut r, n1)?;
+
+        let dec = *r.read_bytes::<DEC_BYTES>()?;
+        let tag = *r.read_bytes::<TAG_BYTES>()?;
+
+        r.finish(CipherText {
+            c0,
+            c1,
+            c2,
+            c3,
+            ap,
+            com,
+            dec,
+            tag,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Derive;
+
+    test_abkem!(RWACDEL);
+
+    #[test]
+    fn rejects_modified_ciphertext() {
+        let mut rng = rand::thread_rng();
+        let attrs = [Attribute::derive_str("role:admin")];
+
+        let (pk, sk) = RWACDEL::setup(&mut rng);
+        let usk = RWACDEL::extract_usk(None, &sk, &attrs, &mut rng);
+        let ap = AccessPolicy {
+            a: gen_a(1),
+            rho: attrs.to_vec(),
+        };
+
+        let (ct, k) = RWACDEL::encaps(&pk, &ap, &mut rng).unwrap();
+        assert_eq!(RWACDEL::decaps(None, &usk, &ct).unwrap(), k);
+
+        // Rerandomizing the key encapsulation breaks the MAC.
+        let mut c = ct.clone();
+        c.c0[0] = (c.c0[0] * Scalar::from(2)).into();
+        assert!(matches!(
+            RWACDEL::decaps(None, &usk, &c),
+            Err(Error::Decapsulation)
+        ));
+
+        // Flipping a bit of the commitment requires different rows.
+        let mut c = ct.clone();
+        c.com[0] ^= 1;
+        assert!(matches!(
+            RWACDEL::decaps(None, &usk, &c),
+            Err(Error::Decapsulation)
+        ));
+
+        let mut c = ct;
+        c.tag[0] ^= 1;
+        assert!(matches!(
+            RWACDEL::decaps(None, &usk, &c),
+            Err(Error::Decapsulation)
+        ));
+    }
+}
@@ -0,0 +1,234 @@
+//! IND-CCA2 secure KEM from ABE from Rouselakis and Waters (RW13).
+//!
+//! CCA security due to a general approach by Fujisaki and Okamoto.
+//! * From: "[A Modular Analysis of the Fujisaki-Okamoto Transformation](https://eprint.iacr.org/2017/604.pdf)"
+//!
+//! RW13 is turned into a PKE by additionally encrypting a random message using C = m · A^s.
+//! All randomness of the encryption is derived from the message and the access policy,
+//! including the labeling of its rows by attributes, using SHAKE256 (G).
+//! The shared secret is derived from the message using SHAKE256 (H).
+//!
+//! Like for CGW, decapsulation now requires the public key to re-encrypt.
+
+use crate::kem::rwac_cpa::{
+    decrypt_rows, encrypt_rows, omega_indices, policy_rows, read_rows, write_rows, RWACCPA,
+};
+pub use crate::kem::rwac_cpa::{
+    gen_a, AccessPolicy, Attribute, LSSSMatrix, PublicKey, SecretKey, UserSecretKey,
+};
+use crate::kem::{Error, SharedSecret, ABKEM};
+use crate::pke::{Coins, XofRng};
+use crate::util::*;
+use crate::CompressVar;
+use alloc::vec::Vec;
+use group::Group;
+use irmaseal_curve::{G1Affine, Gt};
+use rand::{CryptoRng, Rng};
+
+/// Domain separation tag for deriving the encryption coins.
+const COINS_DST: &[u8] = b"cca_transforms-rwac_fo-coins";
+
+/// Encrypted message. Can only be decapsed with a corresponding user secret key.
+/// Also known as CT_{A}
+#[derive(Clone, Debug, PartialEq)]
+pub struct CipherText {
+    /// C'_i
+    c0: [G1Affine; 2],
+    /// C1,i,j
+    c1: [Vec<G1Affine>; 2],
+    /// C2,i,j
+    c2: [Vec<G1Affine>; 2],
+    /// C3,i,j
+    c3: [Vec<G1Affine>; 2],
+    /// Ap = (A, rho)
+    ap: AccessPolicy,
+    /// C = m · A^s
+    cm: Gt,
+}
+
+/// The CCA2 secure ABE KEM that results by applying the Fujisaki-Okamoto transform to RW13.
+#[derive(Clone)]
+pub struct RWACFO;
+
+/// Derives the encryption coins from a message and the access policy (G).
+fn coins(m: &Gt, ap: &AccessPolicy) -> XofRng {
+    XofRng::derive_coins(&[COINS_DST, &m.to_compressed(), &ap.to_bytes()])
+}
+
+/// Encrypts a message under the access policy, deterministically given the coins.
+fn encrypt(pk: &PublicKey, ap: &AccessPolicy, m: &Gt, coins: &mut XofRng) -> CipherText {
+    let s = rand_scalar(coins);
+
+    let (c0, [c1, c2, c3]) = encrypt_rows(pk, &s, &policy_rows(ap, coins), coins);
+
+    CipherText {
+        c0,
+        c1,
+        c2,
+        c3,
+        ap: ap.clone(),
+        cm: m + pk.a * s,
+    }
+}
+
+impl ABKEM for RWACFO {
+    const IDENTIFIER: &'static str = "rwac_fo";
+
+    type Pk = PublicKey;
+    type Sk = SecretKey;
+    type Usk = UserSecretKey;
+    type Ct = CipherText;
+    type Policy = AccessPolicy;
+    type AttributeSet = [Attribute];
+    type Ss = SharedSecret;
+
+    /// Generate a keypair used by the Private Key Generator (PKG).
+    fn setup<R: Rng + CryptoRng>(rng: &mut R) -> (PublicKey, SecretKey) {
+        RWACCPA::setup(rng)
+    }
+
+    /// Extract a user secret key for a set of attributes.
+    fn extract_usk<R: Rng + CryptoRng>(
+        _pk: Option<&PublicKey>,
+        sk: &SecretKey,
+        attrs: &[Attribute],
+        rng: &mut R,
+    ) -> UserSecretKey {
+        RWACCPA::extract_usk(None, sk, attrs, rng)
+    }
+
+    fn encaps<R: Rng + CryptoRng>(
+        pk: &PublicKey,
+        ap: &AccessPolicy,
+        rng: &mut R,
+    ) -> Result<(CipherText, SharedSecret), Error> {
+        ap.validate()?;
+
+        let m = Gt::random(rng);
+        let ct = encrypt(pk, ap, &m, &mut coins(&m, ap));
+
+        Ok((ct, SharedSecret::from(&m)))
+    }
+
+    /// Derive the same SharedSecret from the CipherText using a UserSecretKey.
+    ///
+    /// # Panics
+    ///
+    /// This scheme **does** require the master public key due to usage the Fujisaki-Okamoto transform.
+    /// This function panics if no master public key is provided.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an [`Error::PolicyNotSatisfied`] when the attributes of the
+    /// user secret key do not satisfy the access policy,
+    /// an [`Error::MalformedPolicy`] when the access policy itself is malformed,
+    /// and an [`Error::Decapsulation`] when an illegitimate ciphertext is encountered (explicit rejection).
+    fn decaps(
+        opk: Option<&PublicKey>,
+        usk: &UserSecretKey,
+        ct: &CipherText,
+    ) -> Result<SharedSecret, Error> {
+        let pk = opk.unwrap();
+
+        ct.ap.validate()?;
+
+        let omega = omega_indices(&usk.attrs, &ct.ap).ok_or(Error::PolicyNotSatisfied)?;
+
+        let k = decrypt_rows(usk, &ct.c0, [&ct.c1, &ct.c2, &ct.c3], &omega);
+        let m = ct.cm - k;
+
+        // Whether decapsulation fails only depends on the public ciphertext,
+        // hence the comparison does not need to be constant-time.
+        if encrypt(pk, &ct.ap, &m, &mut coins(&m, &ct.ap)) == *ct {
+            Ok(SharedSecret::from(&m))
+        } else {
+            Err(Error::Decapsulation)
+        }
+    }
+}
+
+impl CompressVar for CipherText {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+
+        self.ap.write(&mut buf);
+        for el in self.c0.iter() {
+            buf.extend_from_slice(&el.to_compressed());
+        }
+        write_rows(&mut buf, [&self.c1, &self.c2, &self.c3]);
+        buf.extend_from_slice(&self.cm.to_compressed());
+
+        buf
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+
+        let ap = AccessPolicy::read(&mut r)?;
+        let n1 = ap.a.len();
+
+        let c0 = [r.read_g1()?, r.read_g1()?];
+
+        let [c1, c2, c3] = read_rows(&mut r, n1)?;
+
+        let cm = r.read_gt()?;
+
+        r.finish(CipherText {
+            c0,
+            c1,
+            c2,
+            c3,
+            ap,
+            cm,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Derive;
+
+    test_abkem!(RWACFO);
+
+    #[test]
+    fn rejects_modified_ciphertext() {
+        let mut rng = rand::thread_rng();
+        let attrs: Vec<Attribute> = ["role:admin", "dept:eng"]
+            .iter()
+            .map(|a| Attribute::derive_str(a))
+            .collect();
+
+        let (pk, sk) = RWACFO::setup(&mut rng);
+        let usk = RWACFO::extract_usk(Some(&pk), &sk, &attrs, &mut rng);
+        let ap = AccessPolicy {
+            a: gen_a(2),
+            rho: attrs.clone(),
+        };
+
+        let (ct, k) = RWACFO::encaps(&pk, &ap, &mut rng).unwrap();
+        assert_eq!(RWACFO::decaps(Some(&pk), &usk, &ct).unwrap(), k);
+
+        // Changing the message changes the coins.
+        let mut c = ct.clone();
+        c.cm += pk.a;
+        assert!(matches!(
+            RWACFO::decaps(Some(&pk), &usk, &c),
+            Err(Error::Decapsulation)
+        ));
+
+        // The policy is bound into the coins, also when the ciphertext still decrypts.
+        let mut c = ct;
+        c.ap.rho.swap(0, 1);
+        c.ap.a.swap(0, 1);
+        for i in 0..2 {
+            c.c1[i].swap(0, 1);
+            c.c2[i].swap(0, 1);
+            c.c3[i].swap(0, 1);
+        }
+        assert!(matches!(
+            RWACFO::decaps(Some(&pk), &usk, &c),
+            Err(Error::Decapsulation)
+        ));
+    }
+}
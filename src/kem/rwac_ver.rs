@@ -0,0 +1,329 @@
+//! IND-CCA2 secure KEM from ABE from Rouselakis and Waters (RW13), obtained via verifiability.
+//!
+//! Every ciphertext is extended with a dummy attribute row for the tag τ = H(CT),
+//! where H hashes all other components of the ciphertext.
+//! This row reuses the randomness s of C'_i, hence it only consists of C_tag,i = (U_i^τ V_i)^s.
+//! During decapsulation the well-formedness of this row is verified using the pairing equation
+//! e(C_tag,i, h) = e(C'_i, Û^τ V̂), which binds the tag to C'_i.
+//! Ciphertexts that do not pass this verification are rejected (explicit rejection).
+//!
+//! The public key is extended with U_i, V_i and their counterparts Û, V̂ in G2.
+//! These are sampled independently of the parameters of RW13.
+//! The remaining rows are not verified: the public key deliberately contains no counterparts
+//! of g_i and the other bases of RW13 in G2, since these would make it publicly checkable
+//! whether (C'_0, C'_1) lies in the span of (g_0, g_1), which the security argument of RW13 rules out.
+//!
+//! This technique is due to Boyen, Mei and Waters.
+//! * From: "Direct Chosen Ciphertext Security from Identity-Based Techniques"
+//!
+//! The tag is obtained by hashing using sha3_512 and reducing the digest to a scalar.
+
+use crate::kem::rwac_cpa::{
+    decrypt_rows, encrypt_rows, omega_indices, policy_rows, read_rows, write_rows, RWACCPA,
+};
+pub use crate::kem::rwac_cpa::{
+    gen_a, AccessPolicy, Attribute, LSSSMatrix, SecretKey, UserSecretKey,
+};
+use crate::kem::{Error, SharedSecret, ABKEM};
+use crate::util::*;
+use crate::CompressVar;
+use alloc::vec::Vec;
+use group::Group;
+use irmaseal_curve::{multi_miller_loop, G1Affine, G2Affine, G2Prepared, Gt, Scalar};
+use rand::{CryptoRng, Rng};
+use tiny_keccak::Hasher;
+
+/// Domain separation tag for hashing the ciphertext to a tag.
+const TAG_DST: &[u8] = b"cca_transforms-rwac_ver-tag";
+
+/// Public key parameters generated by the PKG used to encaps messages.
+/// Also known as MPK.
+#[derive(Clone, PartialEq)]
+pub struct PublicKey {
+    /// MPK of RW13
+    pk: crate::kem::rwac_cpa::PublicKey,
+    /// U_i
+    u: [G1Affine; 2],
+    /// V_i
+    v: [G1Affine; 2],
+    /// Û
+    uhat: G2Affine,
+    /// V̂
+    vhat: G2Affine,
+}
+
+/// Encrypted message. Can only be decapsed with a corresponding user secret key.
+/// Also known as CT_{A}
+#[derive(Clone, Debug, PartialEq)]
+pub struct CipherText {
+    /// C'_i
+    c0: [G1Affine; 2],
+    /// C1,i,j
+    c1: [Vec<G1Affine>; 2],
+    /// C2,i,j
+    c2: [Vec<G1Affine>; 2],
+    /// C3,i,j
+    c3: [Vec<G1Affine>; 2],
+    /// Ap = (A, rho)
+    ap: AccessPolicy,
+    /// C_tag,i
+    tag: [G1Affine; 2],
+}
+
+/// The CCA2 secure ABE KEM that results by adding a verifiable dummy attribute row to RW13.
+#[derive(Clone)]
+pub struct RWACVER;
+
+impl CipherText {
+    /// Serializes all components except the tag.
+    fn write_body(&self, buf: &mut Vec<u8>) {
+        self.ap.write(buf);
+        for el in self.c0.iter() {
+            buf.extend_from_slice(&el.to_compressed());
+        }
+        write_rows(buf, [&self.c1, &self.c2, &self.c3]);
+    }
+
+    /// Hashes all components except the tag to the attribute of the dummy row (τ).
+    fn tau(&self) -> Scalar {
+        let mut body = Vec::new();
+        self.write_body(&mut body);
+
+        let mut digest = tiny_keccak::Sha3::v512();
+        digest.update(TAG_DST);
+        digest.update(&body);
+
+        let mut buf = [0u8; 64];
+        digest.finalize(&mut buf);
+
+        Scalar::from_bytes_wide(&buf)
+    }
+}
+
+impl ABKEM for RWACVER {
+    const IDENTIFIER: &'static str = "rwac_ver";
+
+    type Pk = PublicKey;
+    type Sk = SecretKey;
+    type Usk = UserSecretKey;
+    type Ct = CipherText;
+    type Policy = AccessPolicy;
+    type AttributeSet = [Attribute];
+    type Ss = SharedSecret;
+
+    /// Generate a keypair used by the Private Key Generator (PKG).
+    fn setup<R: Rng + CryptoRng>(rng: &mut R) -> (PublicKey, SecretKey) {
+        let h = G2Affine::generator();
+        let (pk, sk) = RWACCPA::setup(rng);
+
+        let u = rand_scalar(rng);
+        let v = rand_scalar(rng);
+
+        (
+            PublicKey {
+                u: [(pk.g[0] * u).into(), (pk.g[1] * u).into()],
+                v: [(pk.g[0] * v).into(), (pk.g[1] * v).into()],
+                uhat: (h * u).into(),
+                vhat: (h * v).into(),
+                pk,
+            },
+            sk,
+        )
+    }
+
+    /// Extract a user secret key for a set of attributes.
+    fn extract_usk<R: Rng + CryptoRng>(
+        _pk: Option<&PublicKey>,
+        sk: &SecretKey,
+        attrs: &[Attribute],
+        rng: &mut R,
+    ) -> UserSecretKey {
+        RWACCPA::extract_usk(None, sk, attrs, rng)
+    }
+
+    fn encaps<R: Rng + CryptoRng>(
+        pk: &PublicKey,
+        ap: &AccessPolicy,
+        rng: &mut R,
+    ) -> Result<(CipherText, SharedSecret), Error> {
+        ap.validate()?;
+
+        let s = rand_scalar(rng);
+        let k = pk.pk.a * s;
+
+        let (c0, [c1, c2, c3]) = encrypt_rows(&pk.pk, &s, &policy_rows(ap, rng), rng);
+
+        let mut ct = CipherText {
+            c0,
+            c1,
+            c2,
+            c3,
+            ap: ap.clone(),
+            tag: [G1Affine::default(); 2],
+        };
+
+        let tau = ct.tau();
+        ct.tag = [0, 1].map(|i| multi_mul_g1(&[(pk.u[i], tau * s), (pk.v[i], s)]).into());
+
+        Ok((ct, SharedSecret::from(&k)))
+    }
+
+    /// Derive the same SharedSecret from the CipherText using a UserSecretKey.
+    ///
+    /// # Panics
+    ///
+    /// This scheme **does** require the master public key to verify the ciphertext.
+    /// This function panics if no master public key is provided.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an [`Error::PolicyNotSatisfied`] when the attributes of the
+    /// user secret key do not satisfy the access policy,
+    /// an [`Error::MalformedPolicy`] when the access policy itself is malformed,
+    /// and an [`Error::Decapsulation`] when the ciphertext does not pass verification.
+    fn decaps(
+        opk: Option<&PublicKey>,
+        usk: &UserSecretKey,
+        ct: &CipherText,
+    ) -> Result<SharedSecret, Error> {
+        let pk = opk.unwrap();
+
+        ct.ap.validate()?;
+
+        // Check e(C_tag,i, h) = e(C'_i, Û^τ V̂) for both i.
+        let w = G2Prepared::from(G2Affine::from(pk.uhat * ct.tau() + pk.vhat));
+        let minus_h = G2Prepared::from(-G2Affine::generator());
+
+        let check = multi_miller_loop(&[
+            (&ct.tag[0], &minus_h),
+            (&ct.c0[0], &w),
+            (&ct.tag[1], &minus_h),
+            (&ct.c0[1], &w),
+        ])
+        .final_exponentiation();
+
+        if !bool::from(check.is_identity()) {
+            return Err(Error::Decapsulation);
+        }
+
+        let omega = omega_indices(&usk.attrs, &ct.ap).ok_or(Error::PolicyNotSatisfied)?;
+
+        let k: Gt = decrypt_rows(usk, &ct.c0, [&ct.c1, &ct.c2, &ct.c3], &omega);
+
+        Ok(SharedSecret::from(&k))
+    }
+}
+
+impl CompressVar for PublicKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = self.pk.to_bytes();
+
+        for el in self.u.iter().chain(self.v.iter()) {
+            buf.extend_from_slice(&el.to_compressed());
+        }
+        buf.extend_from_slice(&self.uhat.to_compressed());
+        buf.extend_from_slice(&self.vhat.to_compressed());
+
+        buf
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+
+        let pk = crate::kem::rwac_cpa::PublicKey::read(&mut r)?;
+        let u = [r.read_g1()?, r.read_g1()?];
+        let v = [r.read_g1()?, r.read_g1()?];
+        let uhat = r.read_g2()?;
+        let vhat = r.read_g2()?;
+
+        r.finish(PublicKey {
+            pk,
+            u,
+            v,
+            uhat,
+            vhat,
+        })
+    }
+}
+
+impl CompressVar for CipherText {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+
+        self.write_body(&mut buf);
+        for el in self.tag.iter() {
+            buf.extend_from_slice(&el.to_compressed());
+        }
+
+        buf
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+
+        let ap = AccessPolicy::read(&mut r)?;
+        let n1 = ap.a.len();
+
+        let c0 = [r.read_g1()?, r.read_g1()?];
+
+        let [c1, c2, c3] = read_rows(&mut r, n1)?;
+
+        let tag = [r.read_g1()?, r.read_g1()?];
+
+        r.finish(CipherText {
+            c0,
+            c1,
+            c2,
+            c3,
+            ap,
+            tag,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Derive;
+
+    test_abkem!(RWACVER);
+
+    #[test]
+    fn rejects_modified_ciphertext() {
+        let mut rng = rand::thread_rng();
+        let attrs = [Attribute::derive_str("role:admin")];
+
+        let (pk, sk) = RWACVER::setup(&mut rng);
+        let usk = RWACVER::extract_usk(Some(&pk), &sk, &attrs, &mut rng);
+        let ap = AccessPolicy {
+            a: gen_a(1),
+            rho: attrs.to_vec(),
+        };
+
+        let (ct, k) = RWACVER::encaps(&pk, &ap, &mut rng).unwrap();
+        assert_eq!(RWACVER::decaps(Some(&pk), &usk, &ct).unwrap(), k);
+
+        // Modifying a row changes the tag.
+        let mut c = ct.clone();
+        c.c1[0][0] = (c.c1[0][0] * Scalar::from(2)).into();
+        assert!(matches!(
+            RWACVER::decaps(Some(&pk), &usk, &c),
+            Err(Error::Decapsulation)
+        ));
+
+        // Rerandomizing C' without the tag.
+        let mut c = ct.clone();
+        c.c0[1] = (c.c0[1] * Scalar::from(2)).into();
+        assert!(matches!(
+            RWACVER::decaps(Some(&pk), &usk, &c),
+            Err(Error::Decapsulation)
+        ));
+
+        let mut c = ct;
+        c.tag[0] = (c.tag[0] * Scalar::from(2)).into();
+        assert!(matches!(
+            RWACVER::decaps(Some(&pk), &usk, &c),
+            Err(Error::Decapsulation)
+        ));
+    }
+}
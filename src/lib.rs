@@ -5,11 +5,13 @@
 #![no_std]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
-#[cfg(test)]
+#[cfg(any(test, feature = "std"))]
 extern crate std;
 
-#[cfg(any(feature = "rwac", feature = "rwac_cpa"))]
-#[macro_use]
+#[cfg_attr(
+    any(feature = "rwac", feature = "rwac_cpa", feature = "pe_kv"),
+    macro_use
+)]
 extern crate alloc;
 
 #[cfg(test)]
@@ -20,9 +22,19 @@ mod test_macros;
 #[allow(unused)]
 mod util;
 
+#[cfg(feature = "hybrid")]
+#[cfg_attr(docsrs, doc(cfg(feature = "hybrid")))]
+pub mod hybrid;
+
 pub mod kem;
 pub mod pke;
 
+pub use util::{Epoch, Identity, IdentityBuilder};
+
+// Not part of the public API, only exported for the benchmarks.
+#[doc(hidden)]
+pub use util::multi_mul_g1;
+
 /// Artifacts of the system.
 ///
 /// Can be compressed to byte format and back. Each scheme has its own associated types and
@@ -30,11 +42,41 @@ pub mod pke;
 pub trait Compress: Copy {
     const OUTPUT_SIZE: usize;
     type Output: Copy + Clone + AsRef<[u8]>;
-    fn to_bytes(self: &Self) -> Self::Output;
+    fn to_bytes(&self) -> Self::Output;
     fn from_bytes(output: &Self::Output) -> subtle::CtOption<Self>;
 }
 
-pub trait Derive {
+/// Variable-size artifacts of the system.
+///
+/// The artifacts of the attribute-based schemes grow with the number of attributes and
+/// the size of the access policy. They are encoded using length-prefixed vectors.
+/// Decoding is strict: trailing bytes and vectors of mismatching lengths are rejected.
+#[cfg(any(feature = "rwac", feature = "rwac_cpa", feature = "pe_kv"))]
+pub trait CompressVar: Sized {
+    fn to_bytes(&self) -> alloc::vec::Vec<u8>;
+    fn from_bytes(bytes: &[u8]) -> Option<Self>;
+}
+
+/// Derivation of identities (or attributes) from byte strings.
+///
+/// The plain [`Derive::derive`] is shared by all schemes, such that the same string
+/// results in the same identity everywhere. Use [`Derive::derive_tagged`] to separate domains,
+/// e.g., using the scheme's [`IDENTIFIER`](kem::IBKEM::IDENTIFIER) and an application label.
+pub trait Derive: Sized {
     fn derive(b: &[u8]) -> Self;
     fn derive_str(s: &str) -> Self;
+
+    /// Derive from a byte slice, separated by a list of domain separation tags.
+    ///
+    /// The tags and the input are length-prefixed, such that distinct tags and inputs never
+    /// result in the same encoding. By default, the plain derivation is applied to a sha3_512
+    /// digest of this encoding.
+    fn derive_tagged(tags: &[&[u8]], b: &[u8]) -> Self {
+        Self::derive(&util::tagged_digest(tags, b))
+    }
+
+    /// Derive from a string slice, separated by a list of domain separation tags.
+    fn derive_str_tagged(tags: &[&[u8]], s: &str) -> Self {
+        Self::derive_tagged(tags, s.as_bytes())
+    }
 }
@@ -5,6 +5,7 @@
 
 use crate::util::*;
 use crate::{pke::IBE, Compress};
+use alloc::vec::Vec;
 use arrayref::{array_refs, mut_array_refs};
 use core::convert::TryInto;
 use irmaseal_curve::{
@@ -36,46 +37,208 @@ pub const CT_BYTES: usize = 4 * G1_BYTES + GT_BYTES;
 /// Also known as MPK.
 #[derive(Clone, Copy, PartialEq)]
 pub struct PublicKey {
-    a_1: [G1Affine; 2],
-    w0ta_1: [G1Affine; 2],
-    w1ta_1: [G1Affine; 2],
-    kta_t: Gt,
+    pub(crate) a_1: [G1Affine; 2],
+    pub(crate) w0ta_1: [G1Affine; 2],
+    pub(crate) w1ta_1: [G1Affine; 2],
+    pub(crate) kta_t: Gt,
 }
 
 /// Secret key parameter generated by the PKG used to extract user secret keys.
 /// Also known as MSK.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct SecretKey {
-    b: [Scalar; 2],
-    k: [Scalar; 2],
-    w0: [[Scalar; 2]; 2],
-    w1: [[Scalar; 2]; 2],
+    pub(crate) b: [Scalar; 2],
+    pub(crate) k: [Scalar; 2],
+    pub(crate) w0: [[Scalar; 2]; 2],
+    pub(crate) w1: [[Scalar; 2]; 2],
 }
 
 /// User secret key. Can be used to decrypt the corresponding ciphertext.
 /// Also known as USK_{id}.
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
 pub struct UserSecretKey {
-    d0: [G2Affine; 2],
-    d1: [G2Affine; 2],
+    pub(crate) d0: [G2Affine; 2],
+    pub(crate) d1: [G2Affine; 2],
+}
+
+/// Public key with fixed-base tables for all its elements.
+///
+/// Speeds up encrypting many messages using the same public key,
+/// see [`CGW::encrypt_prepared`]. Takes about 1.4 MiB of memory.
+#[derive(Clone, Debug)]
+pub struct PreparedPublicKey {
+    a_1: [FixedBase<G1Projective>; 2],
+    w0ta_1: [FixedBase<G1Projective>; 2],
+    w1ta_1: [FixedBase<G1Projective>; 2],
+    kta_t: FixedBase<Gt>,
+}
+
+impl From<&PublicKey> for PreparedPublicKey {
+    fn from(pk: &PublicKey) -> Self {
+        let prepare = |x: &[G1Affine; 2]| {
+            [
+                FixedBase::new(&G1Projective::from(x[0])),
+                FixedBase::new(&G1Projective::from(x[1])),
+            ]
+        };
+
+        PreparedPublicKey {
+            a_1: prepare(&pk.a_1),
+            w0ta_1: prepare(&pk.w0ta_1),
+            w1ta_1: prepare(&pk.w1ta_1),
+            kta_t: FixedBase::new(&pk.kta_t),
+        }
+    }
+}
+
+/// User secret key with the line coefficients of the Miller loop precomputed.
+///
+/// Speeds up decrypting many ciphertexts using the same user secret key,
+/// see [`CGW::decrypt_prepared`].
+#[derive(Clone, Debug)]
+pub struct PreparedUserSecretKey {
+    d0: [G2Prepared; 2],
+    d1: [G2Prepared; 2],
+}
+
+impl From<&UserSecretKey> for PreparedUserSecretKey {
+    fn from(usk: &UserSecretKey) -> Self {
+        PreparedUserSecretKey {
+            d0: [G2Prepared::from(usk.d0[0]), G2Prepared::from(usk.d0[1])],
+            d1: [G2Prepared::from(usk.d1[0]), G2Prepared::from(usk.d1[1])],
+        }
+    }
 }
 
 /// Encrypted message. Can only be decrypted with a corresponding user secret key.
 /// Also known as CT_{id}
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub struct CipherText {
-    c0: [G1Affine; 2],
-    c1: [G1Affine; 2],
-    cprime: Gt,
+    pub(crate) c0: [G1Affine; 2],
+    pub(crate) c1: [G1Affine; 2],
+    pub(crate) cprime: Gt,
 }
 
 /// A message that can be encrypted using the PKE.
 pub type Msg = Gt;
 
+/// Computes the discrete logarithms of the components of a user secret key.
+fn usk_exponents<R: Rng + CryptoRng>(sk: &SecretKey, v: &Identity, rng: &mut R) -> [Scalar; 4] {
+    let r = rand_scalar(rng);
+    let id = v.to_scalar();
+
+    let br = [sk.b[0] * r, sk.b[1] * r];
+
+    [
+        br[0],
+        br[1],
+        -(sk.k[0]
+            + (br[0] * sk.w0[0][0]
+                + br[1] * sk.w0[0][1]
+                + id * (br[0] * sk.w1[0][0] + br[1] * sk.w1[0][1]))),
+        -(sk.k[1]
+            + (br[0] * sk.w0[1][0]
+                + br[1] * sk.w0[1][1]
+                + id * (br[0] * sk.w1[1][0] + br[1] * sk.w1[1][1]))),
+    ]
+}
+
 /// The Chen-Gay-Wee identity-based encryption scheme.
 pub struct CGW;
 
+impl CGW {
+    /// Extract user secret keys for many identities using the MSK.
+    ///
+    /// Uses a fixed-base table of the generator, and normalizes all keys using one inversion.
+    pub fn extract_usk_batch<R: Rng + CryptoRng>(
+        sk: &SecretKey,
+        ids: &[Identity],
+        rng: &mut R,
+    ) -> Vec<UserSecretKey> {
+        let g2 = FixedBase::new(&G2Projective::generator());
+
+        let batch: Vec<G2Projective> = ids
+            .iter()
+            .flat_map(|v| usk_exponents(sk, v, rng).map(|e| g2.mul(&e)))
+            .collect();
+        let mut out = alloc::vec![G2Affine::default(); batch.len()];
+        G2Projective::batch_normalize(&batch, &mut out);
+
+        out.chunks_exact(4)
+            .map(|d| UserSecretKey {
+                d0: [d[0], d[1]],
+                d1: [d[2], d[3]],
+            })
+            .collect()
+    }
+
+    /// Encrypt a message, without normalizing the group elements of the ciphertext.
+    ///
+    /// Returns C_0 and C_1 in projective coordinates, such that they can be normalized in batch.
+    pub(crate) fn encrypt_projective(
+        pk: &PublicKey,
+        v: &Identity,
+        message: &Msg,
+        rng: &<Self as IBE>::RngBytes,
+    ) -> ([G1Projective; 4], Gt) {
+        let s = Scalar::from_bytes_wide(rng);
+        let sid = s * v.to_scalar();
+
+        let batch = [
+            pk.a_1[0] * s,
+            pk.a_1[1] * s,
+            multi_mul_g1(&[(pk.w0ta_1[0], s), (pk.w1ta_1[0], sid)]),
+            multi_mul_g1(&[(pk.w0ta_1[1], s), (pk.w1ta_1[1], sid)]),
+        ];
+
+        (batch, pk.kta_t * s + message)
+    }
+
+    /// Encrypt a message using a PreparedPublicKey and an identity.
+    pub fn encrypt_prepared(
+        pk: &PreparedPublicKey,
+        v: &Identity,
+        message: &Msg,
+        rng: &<Self as IBE>::RngBytes,
+    ) -> CipherText {
+        let s = Scalar::from_bytes_wide(rng);
+        let sid = s * v.to_scalar();
+
+        let batch = [
+            pk.a_1[0].mul(&s),
+            pk.a_1[1].mul(&s),
+            pk.w0ta_1[0].mul(&s) + pk.w1ta_1[0].mul(&sid),
+            pk.w0ta_1[1].mul(&s) + pk.w1ta_1[1].mul(&sid),
+        ];
+
+        let mut out = [G1Affine::default(); 4];
+        G1Projective::batch_normalize(&batch, &mut out);
+
+        let cprime = pk.kta_t.mul(&s) + message;
+
+        CipherText {
+            c0: [out[0], out[1]],
+            c1: [out[2], out[3]],
+            cprime,
+        }
+    }
+
+    /// Derive the same message from the CipherText using a PreparedUserSecretKey.
+    pub fn decrypt_prepared(usk: &PreparedUserSecretKey, ct: &CipherText) -> Msg {
+        ct.cprime
+            + multi_miller_loop(&[
+                (&ct.c0[0], &usk.d1[0]),
+                (&ct.c0[1], &usk.d1[1]),
+                (&ct.c1[0], &usk.d0[0]),
+                (&ct.c1[1], &usk.d0[1]),
+            ])
+            .final_exponentiation()
+    }
+}
+
 impl IBE for CGW {
+    const IDENTIFIER: &'static str = "cgw";
+
     type Pk = PublicKey;
     type Sk = SecretKey;
     type Usk = UserSecretKey;
@@ -151,23 +314,8 @@ impl IBE for CGW {
         rng: &mut R,
     ) -> UserSecretKey {
         let g2 = G2Affine::generator();
-        let r = rand_scalar(rng);
-        let id = v.to_scalar();
 
-        let br = [sk.b[0] * r, sk.b[1] * r];
-
-        let batch = [
-            g2 * br[0],
-            g2 * br[1],
-            g2 * -(sk.k[0]
-                + (br[0] * sk.w0[0][0]
-                    + br[1] * sk.w0[0][1]
-                    + id * (br[0] * sk.w1[0][0] + br[1] * sk.w1[0][1]))),
-            g2 * -(sk.k[1]
-                + (br[0] * sk.w0[1][0]
-                    + br[1] * sk.w0[1][1]
-                    + id * (br[0] * sk.w1[1][0] + br[1] * sk.w1[1][1]))),
-        ];
+        let batch = usk_exponents(sk, v, rng).map(|e| g2 * e);
         let mut out = [G2Affine::default(); 4];
         G2Projective::batch_normalize(&batch, &mut out);
 
@@ -179,21 +327,11 @@ impl IBE for CGW {
 
     /// Encrypt a message using the PKG public key and an identity.
     fn encrypt(pk: &PublicKey, v: &Identity, message: &Msg, rng: &Self::RngBytes) -> CipherText {
-        let s = Scalar::from_bytes_wide(rng);
-        let id = v.to_scalar();
-
-        let batch = [
-            pk.a_1[0] * s,
-            pk.a_1[1] * s,
-            (pk.w0ta_1[0] * s) + (pk.w1ta_1[0] * (s * id)),
-            (pk.w0ta_1[1] * s) + (pk.w1ta_1[1] * (s * id)),
-        ];
+        let (batch, cprime) = Self::encrypt_projective(pk, v, message, rng);
 
         let mut out = [G1Affine::default(); 4];
         G1Projective::batch_normalize(&batch, &mut out);
 
-        let cprime = pk.kta_t * s + message;
-
         CipherText {
             c0: [out[0], out[1]],
             c1: [out[2], out[3]],
@@ -441,15 +579,6 @@ impl ConditionallySelectable for CipherText {
     }
 }
 
-impl Default for UserSecretKey {
-    fn default() -> Self {
-        UserSecretKey {
-            d0: [G2Affine::default(); 2],
-            d1: [G2Affine::default(); 2],
-        }
-    }
-}
-
 impl ConditionallySelectable for UserSecretKey {
     fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
         UserSecretKey {
@@ -468,4 +597,43 @@ impl ConditionallySelectable for UserSecretKey {
 #[cfg(test)]
 mod tests {
     test_ibe!(CGW);
+
+    #[test]
+    fn eq_extract_usk_batch() {
+        let mut rng = rand::thread_rng();
+        let (pk, sk) = CGW::setup(&mut rng);
+        let ids = [ID, b"email:l.botros@cs.ru.nl"].map(Identity::derive);
+        let usks = CGW::extract_usk_batch(&sk, &ids, &mut rng);
+        assert_eq!(usks.len(), 2);
+
+        for (id, usk) in ids.iter().zip(usks.iter()) {
+            let m = Msg::random(&mut rng);
+            let c = CGW::encrypt(&pk, id, &m, &[7u8; 64]);
+            assert_eq!(CGW::decrypt(usk, &c), m);
+        }
+    }
+
+    #[test]
+    fn eq_encrypt_prepared() {
+        let results = perform_default();
+        let prepared = PreparedPublicKey::from(&results.pk);
+        let kid = Identity::derive(ID);
+
+        let c = CGW::encrypt_prepared(&prepared, &kid, &results.m, &[7u8; 64]);
+        assert_eq!(c, CGW::encrypt(&results.pk, &kid, &results.m, &[7u8; 64]));
+        assert_eq!(CGW::decrypt(&results.usk, &c), results.m);
+    }
+
+    #[test]
+    fn eq_decrypt_prepared() {
+        let results = perform_default();
+        let prepared = PreparedUserSecretKey::from(&results.usk);
+
+        assert_eq!(CGW::decrypt_prepared(&prepared, &results.c), results.m);
+
+        // The prepared key can be reused.
+        let m = Msg::random(&mut rand::thread_rng());
+        let c = CGW::encrypt(&results.pk, &Identity::derive(ID), &m, &[7u8; 64]);
+        assert_eq!(CGW::decrypt_prepared(&prepared, &c), m);
+    }
 }
@@ -0,0 +1,773 @@
+//! IND-ID-CPA secure hierarchical IBE (HIBE) based on the IBE by Chen, Gay and Wee.
+//! * From: "[Improved Dual System ABE in Prime-Order Groups via Predicate Encodings](https://link.springer.com/chapter/10.1007/978-3-540-79263-5_14)"
+//!
+//! Identities consist of up to [`MAX_DEPTH`] levels, e.g., `org/dept/user`.
+//! Every level is derived to an [`Identity`] separately and has its own parameter W_i,
+//! such that the ciphertext carries [(W_0 + Σ_i id_i W_i)^T A s]_1 instead of [(W_0 + id W_1)^T A s]_1.
+//! Ciphertexts have the same size as those of CGW.
+//!
+//! A user secret key for a prefix of an identity can be delegated to a key of the next level,
+//! without involving the PKG. Therefore, user secret keys additionally contain [-W_i B r]_2
+//! for all levels below their own, and the public key contains [B]_2, [W_0 B]_2 and [W_i B]_2
+//! to rerandomize delegated keys.
+//! Delegated keys are distributed identically to keys extracted by the PKG.
+//!
+//! All structs' byte serialization use compression.
+
+use crate::util::*;
+use crate::{pke::IBE, Compress, Derive};
+use arrayref::{array_refs, mut_array_refs};
+use core::convert::TryInto;
+use irmaseal_curve::{
+    multi_miller_loop, pairing, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt,
+    Scalar,
+};
+use rand::{CryptoRng, Rng};
+use subtle::{Choice, ConstantTimeEq, CtOption};
+
+#[allow(unused_imports)]
+use group::Group;
+
+/// Maximum number of levels of a hierarchical identity.
+pub const MAX_DEPTH: usize = 4;
+
+/// Separator between the levels of a hierarchical identity, see [`HierarchicalIdentity::parse`].
+pub const SEPARATOR: u8 = b'/';
+
+/// Size of the byte representation of a hierarchical identity.
+pub const HID_BYTES: usize = 1 + MAX_DEPTH * ID_BYTES;
+
+/// Size of the compressed message in bytes.
+pub const MSG_BYTES: usize = GT_BYTES;
+
+/// Size of the compressed master public key in bytes.
+pub const PK_BYTES: usize = (4 + 2 * MAX_DEPTH) * (G1_BYTES + G2_BYTES) + GT_BYTES;
+
+/// Size of the compressed master secret key in bytes.
+pub const SK_BYTES: usize = (8 + 4 * MAX_DEPTH) * SCALAR_BYTES;
+
+/// Size of the compressed user secret key in bytes.
+///
+/// The USK includes the hierarchical identity (needed for delegation).
+pub const USK_BYTES: usize = (4 + 2 * MAX_DEPTH) * G2_BYTES + HID_BYTES;
+
+/// Size of the compressed ciphertext key in bytes.
+pub const CT_BYTES: usize = 4 * G1_BYTES + GT_BYTES;
+
+/// Identity consisting of multiple levels, each derived to an [`Identity`].
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct HierarchicalIdentity {
+    ids: [Identity; MAX_DEPTH],
+    depth: usize,
+}
+
+impl HierarchicalIdentity {
+    /// Creates a hierarchical identity from already derived levels.
+    ///
+    /// Returns `None` if there are more than [`MAX_DEPTH`] levels.
+    pub fn new(levels: &[Identity]) -> Option<Self> {
+        let mut res = Self::default();
+        for level in levels {
+            res = res.child(level)?;
+        }
+
+        Some(res)
+    }
+
+    /// The levels of this identity.
+    pub fn levels(&self) -> &[Identity] {
+        &self.ids[..self.depth]
+    }
+
+    /// Number of levels of this identity.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Appends a level to this identity.
+    ///
+    /// Returns `None` if this identity is already at [`MAX_DEPTH`].
+    pub fn child(&self, id: &Identity) -> Option<Self> {
+        if self.depth == MAX_DEPTH {
+            return None;
+        }
+
+        let mut res = *self;
+        res.ids[self.depth] = *id;
+        res.depth += 1;
+
+        Some(res)
+    }
+
+    /// Hashes the identity to the scalars id_i, one per level.
+    fn to_scalars(self) -> [Scalar; MAX_DEPTH] {
+        let mut res = [Scalar::zero(); MAX_DEPTH];
+        for (x, id) in res.iter_mut().zip(self.levels()) {
+            *x = id.to_scalar();
+        }
+
+        res
+    }
+
+    fn to_bytes(self) -> [u8; HID_BYTES] {
+        let mut res = [0u8; HID_BYTES];
+        res[0] = self.depth as u8;
+        for (chunk, id) in res[1..].chunks_exact_mut(ID_BYTES).zip(self.ids.iter()) {
+            chunk.copy_from_slice(&id.0);
+        }
+
+        res
+    }
+
+    /// Also returns whether the depth is valid.
+    fn from_bytes(bytes: &[u8; HID_BYTES]) -> (Self, Choice) {
+        let mut res = Self::default();
+        let depth = bytes[0] as usize;
+        for (id, chunk) in res.ids.iter_mut().zip(bytes[1..].chunks_exact(ID_BYTES)) {
+            id.0.copy_from_slice(chunk);
+        }
+        res.depth = depth.min(MAX_DEPTH);
+
+        (res, Choice::from((depth <= MAX_DEPTH) as u8))
+    }
+}
+
+impl Derive for HierarchicalIdentity {
+    /// Splits a byte slice into levels at [`SEPARATOR`] and derives every level separately.
+    ///
+    /// Beyond [`MAX_DEPTH`] levels, the remainder (including separators) is derived as the last level.
+    /// Use [`HierarchicalIdentity::parse`] to reject such input instead.
+    fn derive(b: &[u8]) -> HierarchicalIdentity {
+        Self::derive_levels(b.splitn(MAX_DEPTH, |&c| c == SEPARATOR), Identity::derive)
+    }
+
+    /// Splits a string slice into levels at [`SEPARATOR`] and derives every level separately,
+    /// see [`HierarchicalIdentity::derive`].
+    fn derive_str(s: &str) -> HierarchicalIdentity {
+        Self::derive(s.as_bytes())
+    }
+
+    /// Splits a byte slice into levels at [`SEPARATOR`] and derives every level separately,
+    /// using the domain separation tags, see [`HierarchicalIdentity::derive`].
+    fn derive_tagged(tags: &[&[u8]], b: &[u8]) -> HierarchicalIdentity {
+        Self::derive_levels(b.splitn(MAX_DEPTH, |&c| c == SEPARATOR), |level| {
+            Identity::derive_tagged(tags, level)
+        })
+    }
+}
+
+impl HierarchicalIdentity {
+    /// Splits a byte slice into levels at [`SEPARATOR`] and derives every level separately.
+    ///
+    /// Returns `None` if there are more than [`MAX_DEPTH`] levels.
+    pub fn parse(b: &[u8]) -> Option<Self> {
+        b.split(|&c| c == SEPARATOR)
+            .try_fold(Self::default(), |hid, level| {
+                hid.child(&Identity::derive(level))
+            })
+    }
+
+    /// Derives the levels, of which there are at most [`MAX_DEPTH`].
+    fn derive_levels<'a>(
+        levels: impl Iterator<Item = &'a [u8]>,
+        derive: impl Fn(&[u8]) -> Identity,
+    ) -> Self {
+        levels.fold(Self::default(), |hid, level| {
+            hid.child(&derive(level))
+                .expect("levels are split at most MAX_DEPTH times")
+        })
+    }
+}
+
+/// Public key parameters generated by the PKG used to encrypt messages.
+/// Also known as MPK.
+#[derive(Clone, Copy, PartialEq)]
+pub struct PublicKey {
+    a_1: [G1Affine; 2],
+    w0ta_1: [G1Affine; 2],
+    wta_1: [[G1Affine; 2]; MAX_DEPTH],
+    kta_t: Gt,
+    b_2: [G2Affine; 2],
+    w0b_2: [G2Affine; 2],
+    wb_2: [[G2Affine; 2]; MAX_DEPTH],
+}
+
+/// Secret key parameter generated by the PKG used to extract user secret keys.
+/// Also known as MSK.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SecretKey {
+    b: [Scalar; 2],
+    k: [Scalar; 2],
+    w0: [[Scalar; 2]; 2],
+    w: [[[Scalar; 2]; 2]; MAX_DEPTH],
+}
+
+/// User secret key. Can be used to decrypt the corresponding ciphertext,
+/// and to delegate keys for identities one level deeper.
+/// Also known as USK_{id}.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct UserSecretKey {
+    d0: [G2Affine; 2],
+    d1: [G2Affine; 2],
+    /// [-W_i B r]_2, the identity for levels up to the depth of the key.
+    e: [[G2Affine; 2]; MAX_DEPTH],
+    id: HierarchicalIdentity,
+}
+
+impl UserSecretKey {
+    /// The hierarchical identity of this key.
+    pub fn id(&self) -> &HierarchicalIdentity {
+        &self.id
+    }
+}
+
+/// Encrypted message. Can only be decrypted with a corresponding user secret key.
+/// Also known as CT_{id}
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct CipherText {
+    c0: [G1Affine; 2],
+    c1: [G1Affine; 2],
+    cprime: Gt,
+}
+
+/// A message that can be encrypted using the PKE.
+pub type Msg = Gt;
+
+/// The hierarchical variant of the Chen-Gay-Wee identity-based encryption scheme.
+pub struct CGWHIBE;
+
+/// Computes W v for a 2x2 matrix W.
+fn mul(w: &[[Scalar; 2]; 2], v: &[Scalar; 2]) -> [Scalar; 2] {
+    [
+        w[0][0] * v[0] + w[0][1] * v[1],
+        w[1][0] * v[0] + w[1][1] * v[1],
+    ]
+}
+
+/// Computes W^T v for a 2x2 matrix W.
+fn mul_t(w: &[[Scalar; 2]; 2], v: &[Scalar; 2]) -> [Scalar; 2] {
+    [
+        w[0][0] * v[0] + w[1][0] * v[1],
+        w[0][1] * v[0] + w[1][1] * v[1],
+    ]
+}
+
+impl CGWHIBE {
+    /// Delegate a user secret key to the child identity `id` one level below it.
+    ///
+    /// Does not require the master secret key. Returns `None` if the key is already at [`MAX_DEPTH`].
+    pub fn delegate<R: Rng + CryptoRng>(
+        pk: &PublicKey,
+        usk: &UserSecretKey,
+        id: &Identity,
+        rng: &mut R,
+    ) -> Option<UserSecretKey> {
+        let hid = usk.id.child(id)?;
+        let level = usk.id.depth;
+        let r = rand_scalar(rng);
+        let child = id.to_scalar();
+
+        // (W_0 + Σ_i id_i W_i) B, over all levels of the child identity.
+        let mut wb = [G2Projective::from(pk.w0b_2[0]), pk.w0b_2[1].into()];
+        for (x, wb_i) in hid.to_scalars().iter().zip(pk.wb_2.iter()).take(hid.depth) {
+            wb[0] += wb_i[0] * x;
+            wb[1] += wb_i[1] * x;
+        }
+
+        let mut batch = [G2Projective::identity(); 4 + 2 * MAX_DEPTH];
+        batch[0] = usk.d0[0] + pk.b_2[0] * r;
+        batch[1] = usk.d0[1] + pk.b_2[1] * r;
+        batch[2] = usk.d1[0] + usk.e[level][0] * child - wb[0] * r;
+        batch[3] = usk.d1[1] + usk.e[level][1] * child - wb[1] * r;
+        for i in hid.depth..MAX_DEPTH {
+            batch[4 + 2 * i] = usk.e[i][0] - pk.wb_2[i][0] * r;
+            batch[5 + 2 * i] = usk.e[i][1] - pk.wb_2[i][1] * r;
+        }
+
+        let mut out = [G2Affine::default(); 4 + 2 * MAX_DEPTH];
+        G2Projective::batch_normalize(&batch, &mut out);
+
+        Some(UserSecretKey::from_parts(&out, hid))
+    }
+}
+
+impl UserSecretKey {
+    fn from_parts(out: &[G2Affine; 4 + 2 * MAX_DEPTH], id: HierarchicalIdentity) -> Self {
+        let mut e = [[G2Affine::identity(); 2]; MAX_DEPTH];
+        for (e_i, o) in e.iter_mut().zip(out[4..].chunks_exact(2)) {
+            *e_i = [o[0], o[1]];
+        }
+
+        UserSecretKey {
+            d0: [out[0], out[1]],
+            d1: [out[2], out[3]],
+            e,
+            id,
+        }
+    }
+}
+
+impl IBE for CGWHIBE {
+    const IDENTIFIER: &'static str = "cgw_hibe";
+
+    type Pk = PublicKey;
+    type Sk = SecretKey;
+    type Usk = UserSecretKey;
+    type Ct = CipherText;
+    type Msg = Msg;
+    type Id = HierarchicalIdentity;
+    type RngBytes = [u8; 64];
+
+    const PK_BYTES: usize = PK_BYTES;
+    const SK_BYTES: usize = SK_BYTES;
+    const USK_BYTES: usize = USK_BYTES;
+    const CT_BYTES: usize = CT_BYTES;
+    const MSG_BYTES: usize = MSG_BYTES;
+
+    /// Generate a keypair used by the Private Key Generator (PKG).
+    fn setup<R: Rng + CryptoRng>(rng: &mut R) -> (PublicKey, SecretKey) {
+        let g1 = G1Affine::generator();
+        let g2 = G2Affine::generator();
+
+        let a = [rand_scalar(rng), rand_scalar(rng)];
+        let b = [rand_scalar(rng), rand_scalar(rng)];
+        let k = [rand_scalar(rng), rand_scalar(rng)];
+
+        let mut rand_mat = || {
+            [
+                [rand_scalar(rng), rand_scalar(rng)],
+                [rand_scalar(rng), rand_scalar(rng)],
+            ]
+        };
+        let w0 = rand_mat();
+        let mut w = [[[Scalar::zero(); 2]; 2]; MAX_DEPTH];
+        for w_i in w.iter_mut() {
+            *w_i = rand_mat();
+        }
+
+        let mut batch1 = [G1Projective::identity(); 4 + 2 * MAX_DEPTH];
+        let mut batch2 = [G2Projective::identity(); 4 + 2 * MAX_DEPTH];
+        for (j, w_j) in [&w0].into_iter().chain(w.iter()).enumerate() {
+            let wta = mul_t(w_j, &a);
+            let wb = mul(w_j, &b);
+            batch1[2 + 2 * j] = g1 * wta[0];
+            batch1[3 + 2 * j] = g1 * wta[1];
+            batch2[2 + 2 * j] = g2 * wb[0];
+            batch2[3 + 2 * j] = g2 * wb[1];
+        }
+        batch1[0] = g1 * a[0];
+        batch1[1] = g1 * a[1];
+        batch2[0] = g2 * b[0];
+        batch2[1] = g2 * b[1];
+
+        let mut out1 = [G1Affine::default(); 4 + 2 * MAX_DEPTH];
+        let mut out2 = [G2Affine::default(); 4 + 2 * MAX_DEPTH];
+        G1Projective::batch_normalize(&batch1, &mut out1);
+        G2Projective::batch_normalize(&batch2, &mut out2);
+
+        let mut wta_1 = [[G1Affine::default(); 2]; MAX_DEPTH];
+        let mut wb_2 = [[G2Affine::default(); 2]; MAX_DEPTH];
+        for i in 0..MAX_DEPTH {
+            wta_1[i] = [out1[4 + 2 * i], out1[5 + 2 * i]];
+            wb_2[i] = [out2[4 + 2 * i], out2[5 + 2 * i]];
+        }
+
+        let kta_t = pairing(&g1, &g2) * (k[0] * a[0] + k[1] * a[1]);
+
+        (
+            PublicKey {
+                a_1: [out1[0], out1[1]],
+                w0ta_1: [out1[2], out1[3]],
+                wta_1,
+                kta_t,
+                b_2: [out2[0], out2[1]],
+                w0b_2: [out2[2], out2[3]],
+                wb_2,
+            },
+            SecretKey { b, k, w0, w },
+        )
+    }
+
+    /// Extract a user secret key for a given hierarchical identity.
+    fn extract_usk<R: Rng + CryptoRng>(
+        _opk: Option<&Self::Pk>,
+        sk: &SecretKey,
+        v: &HierarchicalIdentity,
+        rng: &mut R,
+    ) -> UserSecretKey {
+        let g2 = G2Affine::generator();
+        let r = rand_scalar(rng);
+        let ids = v.to_scalars();
+
+        let br = [sk.b[0] * r, sk.b[1] * r];
+
+        // (W_0 + Σ_i id_i W_i) B r
+        let mut wbr = mul(&sk.w0, &br);
+        for (x, w_i) in ids.iter().zip(sk.w.iter()).take(v.depth) {
+            let w_ibr = mul(w_i, &br);
+            wbr[0] += x * w_ibr[0];
+            wbr[1] += x * w_ibr[1];
+        }
+
+        let mut batch = [G2Projective::identity(); 4 + 2 * MAX_DEPTH];
+        batch[0] = g2 * br[0];
+        batch[1] = g2 * br[1];
+        batch[2] = g2 * -(sk.k[0] + wbr[0]);
+        batch[3] = g2 * -(sk.k[1] + wbr[1]);
+        for i in v.depth..MAX_DEPTH {
+            let w_ibr = mul(&sk.w[i], &br);
+            batch[4 + 2 * i] = g2 * -w_ibr[0];
+            batch[5 + 2 * i] = g2 * -w_ibr[1];
+        }
+
+        let mut out = [G2Affine::default(); 4 + 2 * MAX_DEPTH];
+        G2Projective::batch_normalize(&batch, &mut out);
+
+        UserSecretKey::from_parts(&out, *v)
+    }
+
+    /// Encrypt a message using the PKG public key and a hierarchical identity.
+    fn encrypt(
+        pk: &PublicKey,
+        v: &HierarchicalIdentity,
+        message: &Msg,
+        rng: &Self::RngBytes,
+    ) -> CipherText {
+        let s = Scalar::from_bytes_wide(rng);
+        let ids = v.to_scalars();
+
+        // Levels below the depth of the identity are multiplied by zero.
+        let c1 = [0, 1].map(|i| {
+            let mut terms = [(pk.w0ta_1[i], s); MAX_DEPTH + 1];
+            for (l, (x, wta_l)) in ids.iter().zip(pk.wta_1.iter()).enumerate() {
+                let sx = if l < v.depth { s * x } else { Scalar::zero() };
+                terms[l + 1] = (wta_l[i], sx);
+            }
+
+            multi_mul_g1(&terms)
+        });
+
+        let batch = [pk.a_1[0] * s, pk.a_1[1] * s, c1[0], c1[1]];
+
+        let mut out = [G1Affine::default(); 4];
+        G1Projective::batch_normalize(&batch, &mut out);
+
+        let cprime = pk.kta_t * s + message;
+
+        CipherText {
+            c0: [out[0], out[1]],
+            c1: [out[2], out[3]],
+            cprime,
+        }
+    }
+
+    /// Derive the same message from the CipherText using a UserSecretKey.
+    fn decrypt(usk: &UserSecretKey, ct: &CipherText) -> Msg {
+        ct.cprime
+            + multi_miller_loop(&[
+                (&ct.c0[0], &G2Prepared::from(usk.d1[0])),
+                (&ct.c0[1], &G2Prepared::from(usk.d1[1])),
+                (&ct.c1[0], &G2Prepared::from(usk.d0[0])),
+                (&ct.c1[1], &G2Prepared::from(usk.d0[1])),
+            ])
+            .final_exponentiation()
+    }
+}
+
+fn write_g1(out: &mut [u8], els: &[G1Affine]) {
+    for (chunk, el) in out.chunks_exact_mut(G1_BYTES).zip(els.iter()) {
+        chunk.copy_from_slice(&el.to_compressed());
+    }
+}
+
+fn write_g2(out: &mut [u8], els: &[G2Affine]) {
+    for (chunk, el) in out.chunks_exact_mut(G2_BYTES).zip(els.iter()) {
+        chunk.copy_from_slice(&el.to_compressed());
+    }
+}
+
+fn read_g1(bytes: &[u8], els: &mut [G1Affine]) -> Choice {
+    let mut is_some = Choice::from(1u8);
+    for (chunk, el) in bytes.chunks_exact(G1_BYTES).zip(els.iter_mut()) {
+        is_some &= G1Affine::from_compressed(chunk.try_into().unwrap())
+            .map(|x| *el = x)
+            .is_some();
+    }
+
+    is_some
+}
+
+fn read_g2(bytes: &[u8], els: &mut [G2Affine]) -> Choice {
+    let mut is_some = Choice::from(1u8);
+    for (chunk, el) in bytes.chunks_exact(G2_BYTES).zip(els.iter_mut()) {
+        is_some &= G2Affine::from_compressed(chunk.try_into().unwrap())
+            .map(|x| *el = x)
+            .is_some();
+    }
+
+    is_some
+}
+
+impl Compress for PublicKey {
+    const OUTPUT_SIZE: usize = PK_BYTES;
+    type Output = [u8; Self::OUTPUT_SIZE];
+
+    fn to_bytes(&self) -> [u8; PK_BYTES] {
+        let mut res = [0u8; PK_BYTES];
+        let (g1s, g2s, kta_t) = mut_array_refs![
+            &mut res,
+            (4 + 2 * MAX_DEPTH) * G1_BYTES,
+            (4 + 2 * MAX_DEPTH) * G2_BYTES,
+            GT_BYTES
+        ];
+
+        write_g1(g1s, &self.a_1);
+        write_g1(&mut g1s[2 * G1_BYTES..], &self.w0ta_1);
+        write_g1(&mut g1s[4 * G1_BYTES..], self.wta_1.as_flattened());
+        write_g2(g2s, &self.b_2);
+        write_g2(&mut g2s[2 * G2_BYTES..], &self.w0b_2);
+        write_g2(&mut g2s[4 * G2_BYTES..], self.wb_2.as_flattened());
+        *kta_t = self.kta_t.to_compressed();
+
+        res
+    }
+
+    fn from_bytes(bytes: &[u8; PK_BYTES]) -> CtOption<Self> {
+        let (g1s, g2s, kta_t) = array_refs![
+            bytes,
+            (4 + 2 * MAX_DEPTH) * G1_BYTES,
+            (4 + 2 * MAX_DEPTH) * G2_BYTES,
+            GT_BYTES
+        ];
+
+        let mut out1 = [G1Affine::default(); 4 + 2 * MAX_DEPTH];
+        let mut out2 = [G2Affine::default(); 4 + 2 * MAX_DEPTH];
+        let mut is_some = read_g1(g1s, &mut out1) & read_g2(g2s, &mut out2);
+
+        let mut wta_1 = [[G1Affine::default(); 2]; MAX_DEPTH];
+        let mut wb_2 = [[G2Affine::default(); 2]; MAX_DEPTH];
+        for i in 0..MAX_DEPTH {
+            wta_1[i] = [out1[4 + 2 * i], out1[5 + 2 * i]];
+            wb_2[i] = [out2[4 + 2 * i], out2[5 + 2 * i]];
+        }
+
+        let mut pk_kta_t = Gt::default();
+        is_some &= Gt::from_compressed(kta_t).map(|el| pk_kta_t = el).is_some();
+
+        CtOption::new(
+            PublicKey {
+                a_1: [out1[0], out1[1]],
+                w0ta_1: [out1[2], out1[3]],
+                wta_1,
+                kta_t: pk_kta_t,
+                b_2: [out2[0], out2[1]],
+                w0b_2: [out2[2], out2[3]],
+                wb_2,
+            },
+            is_some,
+        )
+    }
+}
+
+impl Compress for SecretKey {
+    const OUTPUT_SIZE: usize = SK_BYTES;
+    type Output = [u8; Self::OUTPUT_SIZE];
+
+    fn to_bytes(&self) -> [u8; SK_BYTES] {
+        let mut res = [0u8; SK_BYTES];
+
+        let scalars = self
+            .b
+            .iter()
+            .chain(self.k.iter())
+            .chain(self.w0.as_flattened())
+            .chain(self.w.as_flattened().as_flattened());
+        for (chunk, x) in res.chunks_exact_mut(SCALAR_BYTES).zip(scalars) {
+            chunk.copy_from_slice(&x.to_bytes());
+        }
+
+        res
+    }
+
+    fn from_bytes(bytes: &[u8; SK_BYTES]) -> CtOption<Self> {
+        let mut sk = SecretKey {
+            b: [Scalar::default(); 2],
+            k: [Scalar::default(); 2],
+            w0: [[Scalar::default(); 2]; 2],
+            w: [[[Scalar::default(); 2]; 2]; MAX_DEPTH],
+        };
+
+        let mut is_some = Choice::from(1u8);
+        let scalars =
+            sk.b.iter_mut()
+                .chain(sk.k.iter_mut())
+                .chain(sk.w0.as_flattened_mut())
+                .chain(sk.w.as_flattened_mut().as_flattened_mut());
+        for (chunk, x) in bytes.chunks_exact(SCALAR_BYTES).zip(scalars) {
+            is_some &= Scalar::from_bytes(chunk.try_into().unwrap())
+                .map(|s| *x = s)
+                .is_some();
+        }
+
+        CtOption::new(sk, is_some)
+    }
+}
+
+impl Compress for UserSecretKey {
+    const OUTPUT_SIZE: usize = USK_BYTES;
+    type Output = [u8; Self::OUTPUT_SIZE];
+
+    fn to_bytes(&self) -> [u8; USK_BYTES] {
+        let mut res = [0u8; USK_BYTES];
+        let (g2s, id) = mut_array_refs![&mut res, (4 + 2 * MAX_DEPTH) * G2_BYTES, HID_BYTES];
+
+        write_g2(g2s, &self.d0);
+        write_g2(&mut g2s[2 * G2_BYTES..], &self.d1);
+        write_g2(&mut g2s[4 * G2_BYTES..], self.e.as_flattened());
+        *id = self.id.to_bytes();
+
+        res
+    }
+
+    fn from_bytes(bytes: &[u8; USK_BYTES]) -> CtOption<Self> {
+        let (g2s, id) = array_refs![bytes, (4 + 2 * MAX_DEPTH) * G2_BYTES, HID_BYTES];
+
+        let mut out = [G2Affine::default(); 4 + 2 * MAX_DEPTH];
+        let is_some = read_g2(g2s, &mut out);
+        let (id, valid) = HierarchicalIdentity::from_bytes(id);
+
+        CtOption::new(UserSecretKey::from_parts(&out, id), is_some & valid)
+    }
+}
+
+impl Compress for CipherText {
+    const OUTPUT_SIZE: usize = CT_BYTES;
+    type Output = [u8; Self::OUTPUT_SIZE];
+
+    fn to_bytes(&self) -> [u8; CT_BYTES] {
+        let mut res = [0u8; CT_BYTES];
+        let (g1s, cprime) = mut_array_refs![&mut res, 4 * G1_BYTES, GT_BYTES];
+
+        write_g1(g1s, &self.c0);
+        write_g1(&mut g1s[2 * G1_BYTES..], &self.c1);
+        *cprime = self.cprime.to_compressed();
+
+        res
+    }
+
+    fn from_bytes(bytes: &[u8; CT_BYTES]) -> CtOption<Self> {
+        let (g1s, cprime) = array_refs![bytes, 4 * G1_BYTES, GT_BYTES];
+
+        let mut out = [G1Affine::default(); 4];
+        let mut is_some = read_g1(g1s, &mut out);
+
+        let mut ct_cprime = Gt::default();
+        is_some &= Gt::from_compressed(cprime)
+            .map(|el| ct_cprime = el)
+            .is_some();
+
+        CtOption::new(
+            CipherText {
+                c0: [out[0], out[1]],
+                c1: [out[2], out[3]],
+                cprime: ct_cprime,
+            },
+            is_some,
+        )
+    }
+}
+
+impl ConstantTimeEq for CipherText {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.c0[0].ct_eq(&other.c0[0])
+            & self.c0[1].ct_eq(&other.c0[1])
+            & self.c1[0].ct_eq(&other.c1[0])
+            & self.c1[1].ct_eq(&other.c1[1])
+            & self.cprime.ct_eq(&other.cprime)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    test_ibe!(CGWHIBE);
+
+    fn encrypt_random(pk: &PublicKey, id: &HierarchicalIdentity) -> (CipherText, Msg) {
+        let mut rng = rand::thread_rng();
+        let m = Msg::random(&mut rng);
+        let c = CGWHIBE::encrypt(pk, id, &m, &[7u8; 64]);
+
+        (c, m)
+    }
+
+    #[test]
+    fn eq_delegate_decrypt() {
+        let mut rng = rand::thread_rng();
+        let (pk, sk) = CGWHIBE::setup(&mut rng);
+
+        let org = HierarchicalIdentity::derive_str("org");
+        let user = HierarchicalIdentity::derive_str("org/dept/user");
+        assert_eq!(user.depth(), 3);
+
+        let usk_org = CGWHIBE::extract_usk(None, &sk, &org, &mut rng);
+        let usk_dept =
+            CGWHIBE::delegate(&pk, &usk_org, &Identity::derive_str("dept"), &mut rng).unwrap();
+        let usk_user =
+            CGWHIBE::delegate(&pk, &usk_dept, &Identity::derive_str("user"), &mut rng).unwrap();
+        assert_eq!(usk_user.id(), &user);
+
+        let (c, m) = encrypt_random(&pk, &user);
+        assert_eq!(CGWHIBE::decrypt(&usk_user, &c), m);
+        assert_eq!(
+            CGWHIBE::decrypt(&CGWHIBE::extract_usk(None, &sk, &user, &mut rng), &c),
+            m
+        );
+
+        // Keys for ancestors cannot decrypt directly.
+        assert_ne!(CGWHIBE::decrypt(&usk_dept, &c), m);
+        assert_ne!(CGWHIBE::decrypt(&usk_org, &c), m);
+
+        // Neither can keys for siblings.
+        let usk_other =
+            CGWHIBE::delegate(&pk, &usk_dept, &Identity::derive_str("other"), &mut rng).unwrap();
+        assert_ne!(CGWHIBE::decrypt(&usk_other, &c), m);
+
+        assert_eq!(
+            usk_user,
+            UserSecretKey::from_bytes(&usk_user.to_bytes()).unwrap()
+        );
+    }
+
+    #[test]
+    fn delegate_max_depth() {
+        let mut rng = rand::thread_rng();
+        let (pk, sk) = CGWHIBE::setup(&mut rng);
+
+        let leaf = HierarchicalIdentity::derive_str("a/b/c/d");
+        let usk = CGWHIBE::extract_usk(None, &sk, &leaf, &mut rng);
+
+        assert!(CGWHIBE::delegate(&pk, &usk, &Identity::derive_str("e"), &mut rng).is_none());
+        assert!(leaf.child(&Identity::derive_str("e")).is_none());
+
+        let mut bytes = usk.to_bytes();
+        bytes[(4 + 2 * MAX_DEPTH) * G2_BYTES] = MAX_DEPTH as u8 + 1;
+        assert!(bool::from(UserSecretKey::from_bytes(&bytes).is_none()));
+    }
+
+    #[test]
+    fn derive_too_many_levels() {
+        let hid = HierarchicalIdentity::derive_str("a/b/c/d/e");
+        assert_eq!(hid.depth(), MAX_DEPTH);
+        assert_eq!(hid.levels()[MAX_DEPTH - 1], Identity::derive_str("d/e"));
+        assert!(HierarchicalIdentity::parse(b"a/b/c/d/e").is_none());
+
+        let tagged = HierarchicalIdentity::derive_tagged(&[b"tag"], b"a/b/c/d/e");
+        assert_eq!(tagged.depth(), MAX_DEPTH);
+        assert_eq!(
+            tagged.levels()[MAX_DEPTH - 1],
+            Identity::derive_tagged(&[b"tag"], b"d/e")
+        );
+
+        let leaf = HierarchicalIdentity::parse(b"a/b/c/d").unwrap();
+        assert_eq!(leaf, HierarchicalIdentity::derive_str("a/b/c/d"));
+    }
+}
@@ -0,0 +1,562 @@
+//! Threshold key extraction for the IBE by Chen, Gay and Wee.
+//!
+//! The master secret is Shamir-shared among n servers, such that any t of them can jointly
+//! extract a user secret key, while fewer than t learn nothing about the master secret.
+//! Every server issues a partial user secret key, which can be verified individually.
+//! A combiner verifies t partial keys and aggregates them into a [`UserSecretKey`] of [`CGW`].
+//!
+//! Extraction in CGW is not linear in the scalars b, W_0 and W_1, since it computes W_i B r.
+//! Therefore, only k is Shamir-shared, not (b, k, W_0, W_1) as a whole.
+//! The dealer instead publishes [B]_2, [W_0 B]_2 and [W_1 B]_2 in the [`ExtractionParams`].
+//! The master secret key is not zeroized, the trusted dealer must discard it after setup.
+//! Every server i then extracts ([B r_i]_2, [-(k_i + (W_0 + id W_1) B r_i)]_2)
+//! using its own randomness r_i, which interpolates to a user secret key with randomness Σ λ_i r_i.
+//! Note that these parameters also allow anyone to rerandomize user secret keys.
+//!
+//! A partial key of server i is verified against its verification key [k_i^T A]_T using
+//! e([A]_1, D1_i) · e([(W_0 + id W_1)^T A]_1, D0_i) = [-k_i^T A]_T.
+//! The combined key is verified in the same way against [k^T A]_T from the public key.
+//!
+//! All structs' byte serialization use compression.
+
+use crate::pke::cgw::{PublicKey, SecretKey, UserSecretKey, CGW};
+use crate::pke::IBE;
+use crate::util::*;
+use crate::Compress;
+use alloc::vec::Vec;
+use arrayref::{array_refs, mut_array_refs};
+use core::convert::TryInto;
+use irmaseal_curve::{
+    multi_miller_loop, pairing, G1Affine, G2Affine, G2Prepared, G2Projective, Gt, Scalar,
+};
+use rand::{CryptoRng, Rng};
+use subtle::{Choice, CtOption};
+
+#[allow(unused_imports)]
+use group::Group;
+
+/// Size of the serialized index of a server.
+pub const INDEX_BYTES: usize = 4;
+
+/// Size of the compressed extraction parameters in bytes.
+pub const PARAMS_BYTES: usize = 6 * G2_BYTES;
+
+/// Size of the compressed secret key share in bytes.
+pub const SHARE_BYTES: usize = INDEX_BYTES + 2 * SCALAR_BYTES;
+
+/// Size of the compressed verification key in bytes.
+pub const VK_BYTES: usize = INDEX_BYTES + GT_BYTES;
+
+/// Size of the compressed partial user secret key in bytes.
+pub const PARTIAL_USK_BYTES: usize = INDEX_BYTES + 4 * G2_BYTES;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// Error indicating that the threshold is zero or exceeds the number of servers.
+    InvalidThreshold,
+    /// Error indicating that a partial user secret key does not pass verification,
+    /// or that no verification key is known for it. Contains the index of the server.
+    InvalidShare(u32),
+    /// Error indicating that multiple partial user secret keys of the same server are given.
+    DuplicateShare(u32),
+    /// Error indicating that the partial user secret keys do not combine to a valid key,
+    /// i.e., that less than the threshold are given.
+    NotEnoughShares,
+}
+
+/// Public parameters used by the servers to extract partial user secret keys.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExtractionParams {
+    b_2: [G2Affine; 2],
+    w0b_2: [G2Affine; 2],
+    w1b_2: [G2Affine; 2],
+}
+
+/// Share of the master secret held by a single server.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SecretKeyShare {
+    index: u32,
+    k: [Scalar; 2],
+}
+
+/// Verification key of a single server, used to verify its partial user secret keys.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VerificationKey {
+    index: u32,
+    kta_t: Gt,
+}
+
+/// Partial user secret key issued by a single server.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PartialUserSecretKey {
+    index: u32,
+    usk: UserSecretKey,
+}
+
+impl SecretKeyShare {
+    /// Index of the server holding this share.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+impl VerificationKey {
+    /// Index of the server this verification key belongs to.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+impl PartialUserSecretKey {
+    /// Index of the server that issued this partial key.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+/// Computes W b for a 2x2 matrix W.
+fn mul(w: &[[Scalar; 2]; 2], b: &[Scalar; 2]) -> [Scalar; 2] {
+    [
+        w[0][0] * b[0] + w[0][1] * b[1],
+        w[1][0] * b[0] + w[1][1] * b[1],
+    ]
+}
+
+/// Computes [k^T A]_T from a (partial) key k.
+fn verification_key(pk: &PublicKey, k: &[Scalar; 2]) -> Gt {
+    let g2 = G2Affine::generator();
+
+    pairing(&pk.a_1[0], &(g2 * k[0]).into()) + pairing(&pk.a_1[1], &(g2 * k[1]).into())
+}
+
+/// Checks e([A]_1, D1) · e([(W_0 + id W_1)^T A]_1, D0) · [k^T A]_T = 1.
+fn verify(pk: &PublicKey, kta_t: &Gt, id: &Identity, usk: &UserSecretKey) -> bool {
+    let x = id.to_scalar();
+    let v = [
+        G1Affine::from(pk.w0ta_1[0] + pk.w1ta_1[0] * x),
+        G1Affine::from(pk.w0ta_1[1] + pk.w1ta_1[1] * x),
+    ];
+
+    let res = multi_miller_loop(&[
+        (&pk.a_1[0], &G2Prepared::from(usk.d1[0])),
+        (&pk.a_1[1], &G2Prepared::from(usk.d1[1])),
+        (&v[0], &G2Prepared::from(usk.d0[0])),
+        (&v[1], &G2Prepared::from(usk.d0[1])),
+    ])
+    .final_exponentiation();
+
+    bool::from((res + kta_t).is_identity())
+}
+
+/// Lagrange coefficient of index `i` for interpolation at 0 over the given indices.
+fn lagrange(i: u32, indices: &[u32]) -> Scalar {
+    let x_i = Scalar::from(i as u64);
+
+    let (num, den) = indices.iter().filter(|&&j| j != i).fold(
+        (Scalar::one(), Scalar::one()),
+        |(num, den), &j| {
+            let x_j = Scalar::from(j as u64);
+            (num * x_j, den * (x_j - x_i))
+        },
+    );
+
+    // Cannot fail, the indices are distinct.
+    num * den.invert().unwrap()
+}
+
+/// The Chen-Gay-Wee identity-based encryption scheme with a threshold PKG.
+pub struct ThresholdCGW;
+
+impl ThresholdCGW {
+    /// Generate the public key and n shares of the master secret, any t of which can extract.
+    ///
+    /// Acts as a trusted dealer. The servers are given indices 1 to n.
+    /// The master secret key is not erased from memory by this function,
+    /// the dealer must discard it after the shares are distributed.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an [`Error::InvalidThreshold`] unless 0 < t <= n.
+    #[allow(clippy::type_complexity)]
+    pub fn setup<R: Rng + CryptoRng>(
+        t: usize,
+        n: usize,
+        rng: &mut R,
+    ) -> Result<
+        (
+            PublicKey,
+            ExtractionParams,
+            Vec<(SecretKeyShare, VerificationKey)>,
+        ),
+        Error,
+    > {
+        if t == 0 || t > n || n > u32::MAX as usize {
+            return Err(Error::InvalidThreshold);
+        }
+
+        let g2 = G2Affine::generator();
+        let (pk, sk) = CGW::setup(rng);
+        let SecretKey { b, k, w0, w1 } = sk;
+
+        let w0b = mul(&w0, &b);
+        let w1b = mul(&w1, &b);
+
+        let batch = [
+            g2 * b[0],
+            g2 * b[1],
+            g2 * w0b[0],
+            g2 * w0b[1],
+            g2 * w1b[0],
+            g2 * w1b[1],
+        ];
+        let mut out = [G2Affine::default(); 6];
+        G2Projective::batch_normalize(&batch, &mut out);
+
+        let params = ExtractionParams {
+            b_2: [out[0], out[1]],
+            w0b_2: [out[2], out[3]],
+            w1b_2: [out[4], out[5]],
+        };
+
+        // One polynomial of degree t - 1 per component of k, with k as constant term.
+        let coeffs: Vec<[Scalar; 2]> = core::iter::once(k)
+            .chain((1..t).map(|_| [rand_scalar(rng), rand_scalar(rng)]))
+            .collect();
+
+        let shares = (1..=n as u32)
+            .map(|index| {
+                let x = Scalar::from(index as u64);
+                let k_i = coeffs.iter().rev().fold([Scalar::zero(); 2], |acc, c| {
+                    [acc[0] * x + c[0], acc[1] * x + c[1]]
+                });
+
+                (
+                    SecretKeyShare { index, k: k_i },
+                    VerificationKey {
+                        index,
+                        kta_t: verification_key(&pk, &k_i),
+                    },
+                )
+            })
+            .collect();
+
+        Ok((pk, params, shares))
+    }
+
+    /// Extract a partial user secret key for a given identity using a share of the master secret.
+    pub fn extract_usk<R: Rng + CryptoRng>(
+        params: &ExtractionParams,
+        share: &SecretKeyShare,
+        id: &Identity,
+        rng: &mut R,
+    ) -> PartialUserSecretKey {
+        let g2 = G2Affine::generator();
+        let r = rand_scalar(rng);
+        let x = id.to_scalar();
+
+        let batch = [
+            params.b_2[0] * r,
+            params.b_2[1] * r,
+            -(g2 * share.k[0] + (params.w0b_2[0] + params.w1b_2[0] * x) * r),
+            -(g2 * share.k[1] + (params.w0b_2[1] + params.w1b_2[1] * x) * r),
+        ];
+        let mut out = [G2Affine::default(); 4];
+        G2Projective::batch_normalize(&batch, &mut out);
+
+        PartialUserSecretKey {
+            index: share.index,
+            usk: UserSecretKey {
+                d0: [out[0], out[1]],
+                d1: [out[2], out[3]],
+            },
+        }
+    }
+
+    /// Verify a partial user secret key for an identity against the verification key of its server.
+    pub fn verify_partial(
+        pk: &PublicKey,
+        vk: &VerificationKey,
+        id: &Identity,
+        partial: &PartialUserSecretKey,
+    ) -> bool {
+        vk.index == partial.index && verify(pk, &vk.kta_t, id, &partial.usk)
+    }
+
+    /// Verify partial user secret keys and combine them into a user secret key for an identity.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an [`Error::InvalidShare`] or [`Error::DuplicateShare`] when a partial
+    /// key is invalid, and an [`Error::NotEnoughShares`] when less than the threshold are given.
+    pub fn combine(
+        pk: &PublicKey,
+        vks: &[VerificationKey],
+        id: &Identity,
+        partials: &[PartialUserSecretKey],
+    ) -> Result<UserSecretKey, Error> {
+        let indices: Vec<u32> = partials.iter().map(|p| p.index).collect();
+
+        for (n, partial) in partials.iter().enumerate() {
+            if indices[..n].contains(&partial.index) {
+                return Err(Error::DuplicateShare(partial.index));
+            }
+
+            let valid = vks
+                .iter()
+                .find(|vk| vk.index == partial.index)
+                .is_some_and(|vk| Self::verify_partial(pk, vk, id, partial));
+            if !valid {
+                return Err(Error::InvalidShare(partial.index));
+            }
+        }
+
+        let mut batch = [G2Projective::identity(); 4];
+        for partial in partials {
+            let l = lagrange(partial.index, &indices);
+            let usk = &partial.usk;
+            for (acc, el) in batch.iter_mut().zip(usk.d0.iter().chain(usk.d1.iter())) {
+                *acc += el * l;
+            }
+        }
+        let mut out = [G2Affine::default(); 4];
+        G2Projective::batch_normalize(&batch, &mut out);
+
+        let usk = UserSecretKey {
+            d0: [out[0], out[1]],
+            d1: [out[2], out[3]],
+        };
+
+        if verify(pk, &pk.kta_t, id, &usk) {
+            Ok(usk)
+        } else {
+            Err(Error::NotEnoughShares)
+        }
+    }
+}
+
+impl Compress for ExtractionParams {
+    const OUTPUT_SIZE: usize = PARAMS_BYTES;
+    type Output = [u8; Self::OUTPUT_SIZE];
+
+    fn to_bytes(&self) -> [u8; PARAMS_BYTES] {
+        let mut res = [0u8; PARAMS_BYTES];
+        let els = self
+            .b_2
+            .iter()
+            .chain(self.w0b_2.iter())
+            .chain(self.w1b_2.iter());
+        for (chunk, el) in res.chunks_exact_mut(G2_BYTES).zip(els) {
+            chunk.copy_from_slice(&el.to_compressed());
+        }
+
+        res
+    }
+
+    fn from_bytes(bytes: &[u8; PARAMS_BYTES]) -> CtOption<Self> {
+        let mut out = [G2Affine::default(); 6];
+
+        let mut is_some = Choice::from(1u8);
+        for (chunk, el) in bytes.chunks_exact(G2_BYTES).zip(out.iter_mut()) {
+            is_some &= G2Affine::from_compressed(chunk.try_into().unwrap())
+                .map(|x| *el = x)
+                .is_some();
+        }
+
+        CtOption::new(
+            ExtractionParams {
+                b_2: [out[0], out[1]],
+                w0b_2: [out[2], out[3]],
+                w1b_2: [out[4], out[5]],
+            },
+            is_some,
+        )
+    }
+}
+
+impl Compress for SecretKeyShare {
+    const OUTPUT_SIZE: usize = SHARE_BYTES;
+    type Output = [u8; Self::OUTPUT_SIZE];
+
+    fn to_bytes(&self) -> [u8; SHARE_BYTES] {
+        let mut res = [0u8; SHARE_BYTES];
+        let (index, k0, k1) = mut_array_refs![&mut res, INDEX_BYTES, SCALAR_BYTES, SCALAR_BYTES];
+
+        *index = self.index.to_be_bytes();
+        *k0 = self.k[0].to_bytes();
+        *k1 = self.k[1].to_bytes();
+
+        res
+    }
+
+    fn from_bytes(bytes: &[u8; SHARE_BYTES]) -> CtOption<Self> {
+        let (index, k0, k1) = array_refs![bytes, INDEX_BYTES, SCALAR_BYTES, SCALAR_BYTES];
+
+        let index = u32::from_be_bytes(*index);
+        let mut k = [Scalar::default(); 2];
+
+        let mut is_some = Choice::from((index != 0) as u8);
+        is_some &= Scalar::from_bytes(k0).map(|s| k[0] = s).is_some();
+        is_some &= Scalar::from_bytes(k1).map(|s| k[1] = s).is_some();
+
+        CtOption::new(SecretKeyShare { index, k }, is_some)
+    }
+}
+
+impl Compress for VerificationKey {
+    const OUTPUT_SIZE: usize = VK_BYTES;
+    type Output = [u8; Self::OUTPUT_SIZE];
+
+    fn to_bytes(&self) -> [u8; VK_BYTES] {
+        let mut res = [0u8; VK_BYTES];
+        let (index, kta_t) = mut_array_refs![&mut res, INDEX_BYTES, GT_BYTES];
+
+        *index = self.index.to_be_bytes();
+        *kta_t = self.kta_t.to_compressed();
+
+        res
+    }
+
+    fn from_bytes(bytes: &[u8; VK_BYTES]) -> CtOption<Self> {
+        let (index, kta_t) = array_refs![bytes, INDEX_BYTES, GT_BYTES];
+
+        let index = u32::from_be_bytes(*index);
+        let mut vk = VerificationKey {
+            index,
+            kta_t: Gt::default(),
+        };
+
+        let mut is_some = Choice::from((index != 0) as u8);
+        is_some &= Gt::from_compressed(kta_t).map(|el| vk.kta_t = el).is_some();
+
+        CtOption::new(vk, is_some)
+    }
+}
+
+impl Compress for PartialUserSecretKey {
+    const OUTPUT_SIZE: usize = PARTIAL_USK_BYTES;
+    type Output = [u8; Self::OUTPUT_SIZE];
+
+    fn to_bytes(&self) -> [u8; PARTIAL_USK_BYTES] {
+        let mut res = [0u8; PARTIAL_USK_BYTES];
+        let (index, usk) = mut_array_refs![&mut res, INDEX_BYTES, 4 * G2_BYTES];
+
+        *index = self.index.to_be_bytes();
+        *usk = self.usk.to_bytes();
+
+        res
+    }
+
+    fn from_bytes(bytes: &[u8; PARTIAL_USK_BYTES]) -> CtOption<Self> {
+        let (index, usk) = array_refs![bytes, INDEX_BYTES, 4 * G2_BYTES];
+
+        let index = u32::from_be_bytes(*index);
+        let usk = UserSecretKey::from_bytes(usk);
+        let is_some = usk.is_some() & Choice::from((index != 0) as u8);
+
+        CtOption::new(
+            PartialUserSecretKey {
+                index,
+                usk: usk.unwrap_or(UserSecretKey::default()),
+            },
+            is_some,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pke::cgw::Msg;
+    use crate::Derive;
+
+    const ID: &str = "email:w.geraedts@sarif.nl";
+
+    #[test]
+    fn eq_combine_decrypt() {
+        let mut rng = rand::thread_rng();
+        let id = Identity::derive_str(ID);
+
+        let (pk, params, shares) = ThresholdCGW::setup(3, 5, &mut rng).unwrap();
+        let vks: Vec<VerificationKey> = shares.iter().map(|(_, vk)| *vk).collect();
+
+        let partials: Vec<PartialUserSecretKey> = shares
+            .iter()
+            .map(|(share, _)| ThresholdCGW::extract_usk(&params, share, &id, &mut rng))
+            .collect();
+        for (partial, vk) in partials.iter().zip(vks.iter()) {
+            assert!(ThresholdCGW::verify_partial(&pk, vk, &id, partial));
+        }
+
+        let m = Msg::random(&mut rng);
+        let c = CGW::encrypt(&pk, &id, &m, &[3u8; 64]);
+
+        // Any subset of at least t servers.
+        for subset in [&partials[..3], &partials[2..], &partials[..]] {
+            let usk = ThresholdCGW::combine(&pk, &vks, &id, subset).unwrap();
+            assert_eq!(CGW::decrypt(&usk, &c), m);
+        }
+
+        assert_eq!(
+            ThresholdCGW::combine(&pk, &vks, &id, &partials[..2]),
+            Err(Error::NotEnoughShares)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_shares() {
+        let mut rng = rand::thread_rng();
+        let id = Identity::derive_str(ID);
+        let other = Identity::derive_str("email:l.botros@cs.ru.nl");
+
+        assert!(ThresholdCGW::setup(0, 5, &mut rng).is_err());
+        assert!(ThresholdCGW::setup(6, 5, &mut rng).is_err());
+
+        let (pk, params, shares) = ThresholdCGW::setup(2, 3, &mut rng).unwrap();
+        let vks: Vec<VerificationKey> = shares.iter().map(|(_, vk)| *vk).collect();
+
+        let p1 = ThresholdCGW::extract_usk(&params, &shares[0].0, &id, &mut rng);
+        let p2 = ThresholdCGW::extract_usk(&params, &shares[1].0, &id, &mut rng);
+        let p3 = ThresholdCGW::extract_usk(&params, &shares[2].0, &other, &mut rng);
+
+        assert_eq!(
+            ThresholdCGW::combine(&pk, &vks, &id, &[p1, p3]),
+            Err(Error::InvalidShare(3))
+        );
+        assert_eq!(
+            ThresholdCGW::combine(&pk, &vks, &id, &[p1, p1]),
+            Err(Error::DuplicateShare(1))
+        );
+
+        let mut forged = p2;
+        forged.usk.d1[0] = (forged.usk.d1[0] * Scalar::from(2)).into();
+        assert_eq!(
+            ThresholdCGW::combine(&pk, &vks, &id, &[p1, forged]),
+            Err(Error::InvalidShare(2))
+        );
+        assert!(ThresholdCGW::combine(&pk, &vks, &id, &[p1, p2]).is_ok());
+    }
+
+    #[test]
+    fn eq_serialize_deserialize() {
+        let mut rng = rand::thread_rng();
+        let id = Identity::derive_str(ID);
+
+        let (_, params, shares) = ThresholdCGW::setup(2, 3, &mut rng).unwrap();
+        let (share, vk) = shares[0];
+        let partial = ThresholdCGW::extract_usk(&params, &share, &id, &mut rng);
+
+        assert_eq!(
+            params,
+            ExtractionParams::from_bytes(&params.to_bytes()).unwrap()
+        );
+        assert_eq!(
+            share,
+            SecretKeyShare::from_bytes(&share.to_bytes()).unwrap()
+        );
+        assert_eq!(vk, VerificationKey::from_bytes(&vk.to_bytes()).unwrap());
+        assert_eq!(
+            partial,
+            PartialUserSecretKey::from_bytes(&partial.to_bytes()).unwrap()
+        );
+    }
+}
@@ -4,12 +4,92 @@
 #[cfg_attr(docsrs, doc(cfg(feature = "cgw")))]
 pub mod cgw;
 
+#[cfg(feature = "cgw_hibe")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cgw_hibe")))]
+pub mod cgw_hibe;
+
+#[cfg(feature = "cgw_threshold")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cgw_threshold")))]
+pub mod cgw_threshold;
+
 use crate::{Compress, Derive};
 use group::Group;
-use rand::{CryptoRng, Rng};
+use rand::{CryptoRng, Rng, RngCore};
+use tiny_keccak::{Hasher, Shake, Xof};
+
+/// Randomness required to encrypt a message, which can be derived from a seed.
+///
+/// Used to derandomize encryption, e.g., in the Fujisaki-Okamoto transform.
+pub trait Coins: Sized {
+    /// Derive coins from the concatenation of the given byte slices.
+    fn derive_coins(seed: &[&[u8]]) -> Self;
+}
+
+/// Byte arrays are derived using SHAKE256.
+impl<const N: usize> Coins for [u8; N] {
+    fn derive_coins(seed: &[&[u8]]) -> Self {
+        let mut digest = Shake::v256();
+        for part in seed {
+            digest.update(part);
+        }
+
+        let mut buf = [0u8; N];
+        digest.finalize(&mut buf);
+
+        buf
+    }
+}
+
+/// Random number generator that squeezes SHAKE256.
+///
+/// Used as coins for schemes whose encryption samples its randomness from a generator.
+#[derive(Clone)]
+pub struct XofRng(Shake);
+
+impl Coins for XofRng {
+    fn derive_coins(seed: &[&[u8]]) -> Self {
+        let mut digest = Shake::v256();
+        for part in seed {
+            digest.update(part);
+        }
+
+        XofRng(digest)
+    }
+}
+
+impl RngCore for XofRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut buf = [0u8; 4];
+        self.fill_bytes(&mut buf);
+
+        u32::from_le_bytes(buf)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0u8; 8];
+        self.fill_bytes(&mut buf);
+
+        u64::from_le_bytes(buf)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.squeeze(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+
+        Ok(())
+    }
+}
+
+impl CryptoRng for XofRng {}
 
 /// Identity-based public key encryption scheme (IBPKE).
 pub trait IBE {
+    /// Scheme identifier.
+    const IDENTIFIER: &'static str;
+
     /// Master public key (Mpk).
     type Pk: Compress;
 
@@ -38,24 +38,24 @@ macro_rules! test_kem {
             assert_eq!(results.k, k2);
         }
 
-        //#[test]
-        //fn eq_serialize_deserialize() {
-        //    let result = perform_default();
-
-        //    assert!(result.pk == PublicKey::from_bytes(&result.pk.to_bytes()).unwrap());
-        //    assert_eq!(
-        //        result.sk,
-        //        SecretKey::from_bytes(&result.sk.to_bytes()).unwrap()
-        //    );
-        //    assert_eq!(
-        //        result.usk,
-        //        UserSecretKey::from_bytes(&result.usk.to_bytes()).unwrap()
-        //    );
-        //    assert_eq!(
-        //        result.c,
-        //        CipherText::from_bytes(&result.c.to_bytes()).unwrap()
-        //    );
-        //}
+        #[test]
+        fn eq_serialize_deserialize() {
+            let result = perform_default();
+
+            assert!(result.pk == PublicKey::from_bytes(&result.pk.to_bytes()).unwrap());
+            assert_eq!(
+                result.sk,
+                SecretKey::from_bytes(&result.sk.to_bytes()).unwrap()
+            );
+            assert_eq!(
+                result.usk,
+                UserSecretKey::from_bytes(&result.usk.to_bytes()).unwrap()
+            );
+            assert_eq!(
+                result.c,
+                CipherText::from_bytes(&result.c.to_bytes()).unwrap()
+            );
+        }
     };
 }
 
@@ -89,6 +89,196 @@ macro_rules! test_multi_kem {
     };
 }
 
+macro_rules! test_abkem {
+    ($name: ident) => {
+        const N: usize = 10;
+
+        #[allow(dead_code)]
+        struct DefaultSubResults {
+            attrs: Vec<Attribute>,
+            pk: PublicKey,
+            sk: SecretKey,
+            usk: UserSecretKey,
+            c: CipherText,
+            k: SharedSecret,
+        }
+
+        fn perform_default() -> DefaultSubResults {
+            let mut rng = rand::thread_rng();
+            let attrs: Vec<Attribute> = (0..N)
+                .map(|i| Attribute::derive_str(&format!("attr:{}", i)))
+                .collect();
+
+            let (pk, sk) = $name::setup(&mut rng);
+            let usk = $name::extract_usk(Some(&pk), &sk, &attrs[..], &mut rng);
+
+            // The order of the attributes in the policy should not matter.
+            let mut rho = attrs.clone();
+            rho.reverse();
+            let ap = AccessPolicy { a: gen_a(N), rho };
+
+            let (c, k) = $name::encaps(&pk, &ap, &mut rng).unwrap();
+
+            DefaultSubResults {
+                attrs,
+                pk,
+                sk,
+                usk,
+                c,
+                k,
+            }
+        }
+
+        #[test]
+        fn eq_encaps_decaps() {
+            let results = perform_default();
+            let k2 = $name::decaps(Some(&results.pk), &results.usk, &results.c).unwrap();
+
+            assert_eq!(results.k, k2);
+        }
+
+        #[test]
+        fn eq_encaps_decaps_superset() {
+            let mut rng = rand::thread_rng();
+            let attrs: Vec<Attribute> = (0..N)
+                .map(|i| Attribute::derive_str(&format!("attr:{}", i)))
+                .collect();
+
+            let (pk, sk) = $name::setup(&mut rng);
+            let usk = $name::extract_usk(Some(&pk), &sk, &attrs[..], &mut rng);
+
+            let ap = AccessPolicy {
+                a: gen_a(N / 2),
+                rho: attrs[..N / 2].to_vec(),
+            };
+
+            let (c, k) = $name::encaps(&pk, &ap, &mut rng).unwrap();
+            let k2 = $name::decaps(Some(&pk), &usk, &c).unwrap();
+
+            assert_eq!(k, k2);
+        }
+
+        #[test]
+        fn policy_satisfaction() {
+            let mut rng = rand::thread_rng();
+            let (pk, sk) = $name::setup(&mut rng);
+
+            let ap = crate::kem::policy::compile(
+                "(dept:eng AND role:admin) OR 2 of (clearance:top, site:nl, site:de)",
+            )
+            .unwrap();
+            let (c, k) = $name::encaps(&pk, &ap, &mut rng).unwrap();
+
+            let usk = |names: &[&str], rng: &mut rand::rngs::ThreadRng| {
+                let attrs: Vec<Attribute> =
+                    names.iter().map(|n| Attribute::derive_str(n)).collect();
+                $name::extract_usk(Some(&pk), &sk, &attrs[..], rng)
+            };
+
+            let authorized: [&[&str]; 3] = [
+                &["dept:eng", "role:admin"],
+                &["clearance:top", "site:de"],
+                &["site:nl", "site:de", "dept:eng"],
+            ];
+            for names in authorized {
+                let k2 = $name::decaps(Some(&pk), &usk(names, &mut rng), &c).unwrap();
+                assert_eq!(k, k2);
+            }
+
+            let unauthorized: [&[&str]; 3] = [&["dept:eng"], &["site:nl", "role:admin"], &[]];
+            for names in unauthorized {
+                assert!(matches!(
+                    $name::decaps(Some(&pk), &usk(names, &mut rng), &c),
+                    Err(Error::PolicyNotSatisfied)
+                ));
+            }
+        }
+
+        #[test]
+        fn malformed_policy() {
+            let mut rng = rand::thread_rng();
+            let (pk, _) = $name::setup(&mut rng);
+
+            let attrs: Vec<Attribute> = (0..N)
+                .map(|i| Attribute::derive_str(&format!("attr:{}", i)))
+                .collect();
+            let mut ragged = gen_a(N);
+            ragged[1].pop();
+
+            let policies = [
+                AccessPolicy {
+                    a: vec![],
+                    rho: vec![],
+                },
+                AccessPolicy {
+                    a: ragged,
+                    rho: attrs.clone(),
+                },
+                AccessPolicy {
+                    a: gen_a(N),
+                    rho: attrs[1..].to_vec(),
+                },
+            ];
+
+            for ap in policies.iter() {
+                assert!(matches!(
+                    $name::encaps(&pk, ap, &mut rng),
+                    Err(Error::MalformedPolicy)
+                ));
+            }
+        }
+
+        #[test]
+        fn eq_serialize_deserialize() {
+            let result = perform_default();
+
+            assert!(result.pk == PublicKey::from_bytes(&result.pk.to_bytes()).unwrap());
+            assert_eq!(
+                result.sk,
+                SecretKey::from_bytes(&result.sk.to_bytes()).unwrap()
+            );
+            assert_eq!(
+                result.usk,
+                UserSecretKey::from_bytes(&result.usk.to_bytes()).unwrap()
+            );
+            assert_eq!(
+                result.c,
+                CipherText::from_bytes(&result.c.to_bytes()).unwrap()
+            );
+        }
+
+        #[test]
+        fn serialize_rejects_malformed() {
+            let result = perform_default();
+            let bytes = result.c.to_bytes();
+
+            // Truncated and trailing bytes.
+            assert!(CipherText::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+            assert!(CipherText::from_bytes(&[&bytes[..], &[0u8]].concat()).is_none());
+
+            // Policy with a row missing, such that the number of rows and rho mismatch.
+            let mut ap = result.c.ap.clone();
+            ap.a.pop();
+            assert!(AccessPolicy::from_bytes(&ap.to_bytes()).is_none());
+
+            // Policy with a row of different length.
+            let mut ap = result.c.ap.clone();
+            ap.a[1].pop();
+            assert!(AccessPolicy::from_bytes(&ap.to_bytes()).is_none());
+
+            // User secret key with less key components than attributes.
+            let mut usk = result.usk.clone();
+            usk.k1_attrs.pop();
+            assert!(UserSecretKey::from_bytes(&usk.to_bytes()).is_none());
+
+            // Master secret key with a singular d, which extraction would fail to invert.
+            let mut sk = result.sk.to_bytes();
+            sk[2 * SCALAR_BYTES..6 * SCALAR_BYTES].fill(0);
+            assert!(SecretKey::from_bytes(&sk).is_none());
+        }
+    };
+}
+
 macro_rules! test_ibe {
     ($name: ident) => {
         use super::*;
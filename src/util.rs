@@ -1,8 +1,10 @@
 use crate::{Compress, Derive};
+use alloc::vec::Vec;
+use core::convert::TryInto;
 use group::{ff::Field, Group, UncompressedEncoding};
-use irmaseal_curve::{G1Affine, G1Projective, G2Projective, Gt, Scalar};
+use irmaseal_curve::{G1Affine, G1Projective, G2Affine, G2Projective, Gt, Scalar};
 use rand::{CryptoRng, RngCore};
-use subtle::CtOption;
+use subtle::{ConditionallySelectable, ConstantTimeEq, CtOption};
 use tiny_keccak::Hasher;
 
 /// Size of a compressed target group element.
@@ -18,7 +20,10 @@ pub(crate) const G2_BYTES: usize = 96;
 pub(crate) const SCALAR_BYTES: usize = 32;
 
 /// Size of the (default) identity buffer.
-pub(crate) const ID_BYTES: usize = 64;
+pub const ID_BYTES: usize = 64;
+
+/// Size of the length prefix of variable-size vectors.
+pub(crate) const LEN_BYTES: usize = 4;
 
 #[inline(always)]
 pub fn rand_scalar<R: RngCore + CryptoRng>(rng: &mut R) -> Scalar {
@@ -119,14 +124,173 @@ impl Derive for Identity {
     fn derive_str(s: &str) -> Identity {
         Self::derive(s.as_bytes())
     }
+
+    /// Hash a byte slice to a set of Identity parameters, separated by domain separation tags.
+    /// Uses sha3-512 over the length-prefixed tags and input.
+    fn derive_tagged(tags: &[&[u8]], b: &[u8]) -> Identity {
+        Identity(tagged_digest(tags, b))
+    }
+}
+
+/// Domain separation tag of tagged derivations.
+const DERIVE_DST: &[u8] = b"cca_transforms-derive-v1";
+
+/// Computes sha3_512 over the canonical encoding of tags and an input.
+///
+/// The number of tags, every tag and the input are prefixed by their (big-endian) length.
+pub(crate) fn tagged_digest(tags: &[&[u8]], b: &[u8]) -> [u8; 64] {
+    let mut digest = tiny_keccak::Sha3::v512();
+
+    digest.update(DERIVE_DST);
+    digest.update(&(tags.len() as u64).to_be_bytes());
+    for part in tags.iter().chain(core::iter::once(&b)) {
+        digest.update(&(part.len() as u64).to_be_bytes());
+        digest.update(part);
+    }
+
+    let mut buf = [0u8; 64];
+    digest.finalize(&mut buf);
+
+    buf
 }
 
 impl Identity {
     /// Create a scalar from an identity.
     #[allow(unused)]
-    pub(crate) fn to_scalar(&self) -> Scalar {
+    pub(crate) fn to_scalar(self) -> Scalar {
         Scalar::from_bytes_wide(&self.0)
     }
+
+    /// Start building a structured identity for an attribute, e.g., `b"email:x@y.nl"`.
+    pub fn builder(attribute: &[u8]) -> IdentityBuilder<'_> {
+        IdentityBuilder {
+            attribute,
+            epoch: None,
+        }
+    }
+}
+
+#[cfg(any(feature = "rwac", feature = "rwac_cpa", feature = "pe_kv"))]
+impl crate::CompressVar for Identity {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let id = Identity(*r.read_bytes::<ID_BYTES>()?);
+
+        r.finish(id)
+    }
+}
+
+/// Domain separation tag of structured identities.
+const IDENTITY_DST: &[u8] = b"cca_transforms-identity-v1";
+
+/// Number of seconds in a day.
+const DAY_SECS: u64 = 86_400;
+
+/// Validity period of an identity.
+///
+/// Epochs are consecutive periods of a fixed length, numbered from the Unix epoch.
+/// This makes it possible to extract keys for a range of epochs and to encapsulate
+/// for the epoch containing a point in time, without the parties communicating.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Epoch(pub u64);
+
+impl Epoch {
+    /// Epoch of `period` seconds that contains the Unix timestamp `secs`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the period is zero.
+    pub fn from_timestamp(secs: u64, period: u64) -> Self {
+        Epoch(secs / period)
+    }
+
+    /// ISO 8601 week that contains the Unix timestamp `secs`.
+    ///
+    /// Weeks start on Monday and are numbered from week 1 of 1970, which starts on 29 December 1969.
+    pub fn weekly(secs: u64) -> Self {
+        Epoch((secs / DAY_SECS + 3) / 7)
+    }
+
+    /// ISO 8601 week `week` of `year`, such that, e.g., `2026-W42` is `Epoch::iso_week(2026, 42)`.
+    ///
+    /// Returns `None` if the year is before 1970 or the year does not have this week.
+    pub fn iso_week(year: u32, week: u32) -> Option<Self> {
+        if year < 1970 || week == 0 || week > 53 {
+            return None;
+        }
+
+        let start = iso_year_start(year as i64);
+        if week as i64 > (iso_year_start(year as i64 + 1) - start) / 7 {
+            return None;
+        }
+
+        Some(Epoch(((start + 3) / 7) as u64 + week as u64 - 1))
+    }
+
+    /// The epoch following this epoch.
+    pub fn next(self) -> Self {
+        Epoch(self.0 + 1)
+    }
+}
+
+/// Days since the Unix epoch of the Monday on which ISO week 1 of the year starts.
+///
+/// Week 1 is the week containing 4 January.
+fn iso_year_start(year: i64) -> i64 {
+    // Days since the Unix epoch of 4 January, using the algorithms by Howard Hinnant.
+    let y = year - 1;
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * 10 + 2) / 5 + 3;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let jan4 = era * 146_097 + doe - 719_468;
+
+    // The Unix epoch is on a Thursday.
+    jan4 - (jan4 + 3).rem_euclid(7)
+}
+
+/// Builder of structured identities, binding an attribute and optionally a validity epoch.
+///
+/// The identity is derived by hashing a canonical encoding using sha3_512,
+/// which is the domain separation tag, the length-prefixed attribute, and the epoch (if any).
+/// This encoding is injective, unlike string concatenations such as `email:x@y.nl|2026-W42`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct IdentityBuilder<'a> {
+    attribute: &'a [u8],
+    epoch: Option<Epoch>,
+}
+
+impl<'a> IdentityBuilder<'a> {
+    /// Bind the identity to a validity epoch.
+    pub fn epoch(mut self, epoch: Epoch) -> Self {
+        self.epoch = Some(epoch);
+        self
+    }
+
+    /// Derive the identity.
+    pub fn build(&self) -> Identity {
+        let mut digest = tiny_keccak::Sha3::v512();
+
+        digest.update(IDENTITY_DST);
+        digest.update(&(self.attribute.len() as u64).to_be_bytes());
+        digest.update(self.attribute);
+        match self.epoch {
+            None => digest.update(&[0]),
+            Some(Epoch(e)) => {
+                digest.update(&[1]);
+                digest.update(&e.to_be_bytes());
+            }
+        }
+
+        let mut buf = [0u8; ID_BYTES];
+        digest.finalize(&mut buf);
+
+        Identity(buf)
+    }
 }
 
 impl Compress for Gt {
@@ -141,3 +305,402 @@ impl Compress for Gt {
         Self::from_compressed(bytes)
     }
 }
+
+/// Maximum length of a scheme identifier built at compile time.
+const MAX_IDENTIFIER_LEN: usize = 64;
+
+/// Scheme identifier built at compile time, for schemes generic over another scheme.
+///
+/// Used as `const ID: &'static IdentifierBuf = &IdentifierBuf::new().push_str("fo-").push_str(P::IDENTIFIER);`,
+/// such that [`IdentifierBuf::as_str`] returns a `&'static str`.
+pub(crate) struct IdentifierBuf {
+    buf: [u8; MAX_IDENTIFIER_LEN],
+    len: usize,
+}
+
+impl IdentifierBuf {
+    pub(crate) const fn new() -> Self {
+        IdentifierBuf {
+            buf: [0u8; MAX_IDENTIFIER_LEN],
+            len: 0,
+        }
+    }
+
+    /// Appends a string, fails to compile if the identifier becomes too long.
+    pub(crate) const fn push_str(mut self, s: &str) -> Self {
+        let bytes = s.as_bytes();
+        assert!(
+            self.len + bytes.len() <= MAX_IDENTIFIER_LEN,
+            "identifier too long"
+        );
+
+        let mut i = 0;
+        while i < bytes.len() {
+            self.buf[self.len + i] = bytes[i];
+            i += 1;
+        }
+        self.len += bytes.len();
+
+        self
+    }
+
+    /// Appends the decimal representation of a number.
+    pub(crate) const fn push_usize(self, n: usize) -> Self {
+        let mut digits = [0u8; 20];
+        let mut start = digits.len();
+        let mut n = n;
+        loop {
+            start -= 1;
+            digits[start] = b'0' + (n % 10) as u8;
+            n /= 10;
+            if n == 0 {
+                break;
+            }
+        }
+
+        match core::str::from_utf8(digits.split_at(start).1) {
+            Ok(s) => self.push_str(s),
+            Err(_) => unreachable!(),
+        }
+    }
+
+    pub(crate) const fn as_str(&self) -> &str {
+        match core::str::from_utf8(self.buf.split_at(self.len).0) {
+            Ok(s) => s,
+            // Cannot happen, only strings are appended.
+            Err(_) => unreachable!(),
+        }
+    }
+}
+
+/// Number of bits per window of a fixed-base table.
+const WINDOW_BITS: usize = 4;
+
+/// Number of multiples per window of a fixed-base table.
+const WINDOW_SIZE: usize = 1 << WINDOW_BITS;
+
+/// Number of windows of a fixed-base table, covering all bits of a scalar.
+const WINDOWS: usize = 8 * SCALAR_BYTES / WINDOW_BITS;
+
+/// Table of multiples of a fixed base, used for fast scalar multiplication.
+///
+/// Window i holds j 2^(4i) P for 0 <= j < 16, such that a multiplication takes
+/// one addition per window and no doublings. Entries are looked up in constant time.
+/// A table takes 1024 group elements, e.g., 144 KiB for G1 and 576 KiB for Gt.
+#[derive(Clone, Debug)]
+pub(crate) struct FixedBase<G>(Vec<[G; WINDOW_SIZE]>);
+
+impl<G: Group + ConditionallySelectable + Default> FixedBase<G> {
+    pub(crate) fn new(base: &G) -> Self {
+        let mut base = *base;
+        let mut table = Vec::with_capacity(WINDOWS);
+
+        for _ in 0..WINDOWS {
+            let mut window = [G::identity(); WINDOW_SIZE];
+            for j in 1..WINDOW_SIZE {
+                window[j] = window[j - 1] + base;
+            }
+            table.push(window);
+
+            for _ in 0..WINDOW_BITS {
+                base = base.double();
+            }
+        }
+
+        FixedBase(table)
+    }
+
+    pub(crate) fn mul(&self, s: &Scalar) -> G {
+        let digits = digits(s);
+
+        self.0
+            .iter()
+            .zip(digits.iter())
+            .fold(G::identity(), |acc, (window, digit)| {
+                acc + select(window, *digit)
+            })
+    }
+}
+
+/// Splits a scalar into its 4-bit digits, least significant first.
+fn digits(s: &Scalar) -> [u8; WINDOWS] {
+    let mut res = [0u8; WINDOWS];
+    for (d, b) in res.chunks_exact_mut(2).zip(s.to_bytes().iter()) {
+        d[0] = b & 0xf;
+        d[1] = b >> WINDOW_BITS;
+    }
+
+    res
+}
+
+/// Selects the entry of a window in constant time.
+fn select<G: ConditionallySelectable + Default>(window: &[G; WINDOW_SIZE], digit: u8) -> G {
+    let mut el = G::default();
+    for (j, x) in window.iter().enumerate() {
+        el.conditional_assign(x, (j as u8).ct_eq(&digit));
+    }
+
+    el
+}
+
+/// Computes the sum of the multiples s_i P_i using Straus' method, also known as Shamir's trick.
+///
+/// Shares the doublings between all terms, using a window of 4 bits per term.
+/// Runs in constant time: the entries of the windows are looked up in constant time.
+pub fn multi_mul<G: Group + ConditionallySelectable + Default, const N: usize>(
+    terms: &[(G, Scalar); N],
+) -> G {
+    let mut tables = [[G::identity(); WINDOW_SIZE]; N];
+    let mut scalars = [[0u8; WINDOWS]; N];
+
+    for ((table, digits_i), (p, s)) in tables.iter_mut().zip(scalars.iter_mut()).zip(terms) {
+        for j in 1..WINDOW_SIZE {
+            table[j] = table[j - 1] + p;
+        }
+        *digits_i = digits(s);
+    }
+
+    (0..WINDOWS).rev().fold(G::identity(), |mut acc, w| {
+        for _ in 0..WINDOW_BITS {
+            acc = acc.double();
+        }
+        for (table, digits_i) in tables.iter().zip(scalars.iter()) {
+            acc += select(table, digits_i[w]);
+        }
+
+        acc
+    })
+}
+
+/// Computes the sum of the multiples s_i P_i for points in G1, see [`multi_mul`].
+pub fn multi_mul_g1<const N: usize>(terms: &[(G1Affine, Scalar); N]) -> G1Projective {
+    multi_mul(&terms.map(|(p, s)| (G1Projective::from(p), s)))
+}
+
+#[cfg(any(feature = "rwac", feature = "rwac_cpa", feature = "pe_kv"))]
+pub(crate) use var::*;
+
+/// Helpers to encode and strictly decode variable-size artifacts.
+#[cfg(any(feature = "rwac", feature = "rwac_cpa", feature = "pe_kv"))]
+mod var {
+    use super::*;
+    use crate::CompressVar;
+    use alloc::vec::Vec;
+    use byteorder::{BigEndian, ByteOrder};
+
+    /// Appends a big-endian length prefix to the buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length does not fit in the prefix.
+    pub(crate) fn put_len(buf: &mut Vec<u8>, len: usize) {
+        let len = u32::try_from(len).expect("length exceeds the 32-bit length prefix");
+        let mut prefix = [0u8; LEN_BYTES];
+        BigEndian::write_u32(&mut prefix, len);
+        buf.extend_from_slice(&prefix);
+    }
+
+    /// Appends a length-prefixed encoding of a variable-size artifact to the buffer.
+    pub(crate) fn put_var<T: CompressVar>(buf: &mut Vec<u8>, t: &T) {
+        let bytes = t.to_bytes();
+        put_len(buf, bytes.len());
+        buf.extend_from_slice(&bytes);
+    }
+
+    /// Cursor over a byte slice.
+    ///
+    /// Every read fails when not enough bytes remain or the bytes do not encode a valid element.
+    /// Group elements are checked to be in the correct subgroup.
+    pub(crate) struct Reader<'a>(&'a [u8]);
+
+    impl<'a> Reader<'a> {
+        pub(crate) fn new(bytes: &'a [u8]) -> Self {
+            Reader(bytes)
+        }
+
+        pub(crate) fn read_bytes<const N: usize>(&mut self) -> Option<&'a [u8; N]> {
+            if self.0.len() < N {
+                return None;
+            }
+            let (head, tail) = self.0.split_at(N);
+            self.0 = tail;
+
+            head.try_into().ok()
+        }
+
+        pub(crate) fn read_len(&mut self) -> Option<usize> {
+            self.read_bytes::<LEN_BYTES>()
+                .map(|b| BigEndian::read_u32(b) as usize)
+        }
+
+        pub(crate) fn read_scalar(&mut self) -> Option<Scalar> {
+            self.read_bytes::<SCALAR_BYTES>()
+                .and_then(|b| Scalar::from_bytes(b).into())
+        }
+
+        pub(crate) fn read_g1(&mut self) -> Option<G1Affine> {
+            self.read_bytes::<G1_BYTES>()
+                .and_then(|b| G1Affine::from_compressed(b).into())
+        }
+
+        pub(crate) fn read_g2(&mut self) -> Option<G2Affine> {
+            self.read_bytes::<G2_BYTES>()
+                .and_then(|b| G2Affine::from_compressed(b).into())
+        }
+
+        pub(crate) fn read_gt(&mut self) -> Option<Gt> {
+            self.read_bytes::<GT_BYTES>()
+                .and_then(|b| Gt::from_compressed(b).into())
+        }
+
+        /// Reads a length-prefixed encoding of a variable-size artifact.
+        pub(crate) fn read_var<T: CompressVar>(&mut self) -> Option<T> {
+            let len = self.read_len()?;
+            if self.0.len() < len {
+                return None;
+            }
+            let (head, tail) = self.0.split_at(len);
+            self.0 = tail;
+
+            T::from_bytes(head)
+        }
+
+        /// Reads a length-prefixed vector, decoding each element using `f`.
+        pub(crate) fn read_vec<T>(
+            &mut self,
+            mut f: impl FnMut(&mut Self) -> Option<T>,
+        ) -> Option<Vec<T>> {
+            let len = self.read_len()?;
+
+            // Do not trust the length prefix to preallocate.
+            let mut res = Vec::new();
+            for _ in 0..len {
+                res.push(f(self)?);
+            }
+
+            Some(res)
+        }
+
+        /// Finishes decoding, failing if there are bytes left.
+        pub(crate) fn finish<T>(self, t: T) -> Option<T> {
+            if self.0.is_empty() {
+                Some(t)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iso_weeks() {
+        assert_eq!(Epoch::iso_week(1970, 1), Some(Epoch(0)));
+        assert_eq!(Epoch::weekly(0), Epoch(0));
+
+        // Saturday 17 October 2026 and Sunday 3 January 2021.
+        assert_eq!(
+            Epoch::iso_week(2026, 42),
+            Some(Epoch::weekly(1_792_238_400))
+        );
+        assert_eq!(
+            Epoch::iso_week(2020, 53),
+            Some(Epoch::weekly(1_609_632_000))
+        );
+        assert_eq!(
+            Epoch::iso_week(2021, 1),
+            Epoch::iso_week(2020, 53).map(Epoch::next)
+        );
+
+        assert_eq!(Epoch::iso_week(2021, 53), None);
+        assert_eq!(Epoch::iso_week(1969, 1), None);
+        assert_eq!(Epoch::iso_week(2026, 0), None);
+    }
+
+    const ID: &str = "email:w.geraedts@sarif.nl";
+
+    #[test]
+    fn eq_multi_mul() {
+        let mut rng = rand::thread_rng();
+        let ps = [0; 3].map(|_| rand_g1(&mut rng));
+        let ss = [rand_scalar(&mut rng), Scalar::zero(), -Scalar::one()];
+
+        let terms = [(ps[0], ss[0]), (ps[1], ss[1]), (ps[2], ss[2])];
+        let expected = ps[0] * ss[0] + ps[1] * ss[1] + ps[2] * ss[2];
+        assert_eq!(multi_mul(&terms), expected);
+
+        let affine = terms.map(|(p, s)| (G1Affine::from(p), s));
+        assert_eq!(multi_mul_g1(&affine), expected);
+
+        let table = FixedBase::new(&ps[0]);
+        assert_eq!(table.mul(&ss[0]), ps[0] * ss[0]);
+        assert_eq!(table.mul(&ss[2]), -ps[0]);
+    }
+
+    #[test]
+    fn kat_derive() {
+        let plain = [
+            121, 118, 228, 135, 32, 243, 40, 142, 11, 162, 153, 90, 3, 187, 119, 118, 75, 150, 203,
+            64, 172, 195, 173, 89, 105, 121, 186, 136, 197, 10, 19, 13,
+        ];
+        let untagged = [
+            122, 22, 57, 106, 86, 177, 171, 29, 55, 14, 151, 221, 229, 124, 174, 219, 58, 21, 11,
+            106, 131, 47, 209, 248, 177, 245, 100, 229, 74, 2, 53, 81,
+        ];
+        let tagged = [
+            61, 59, 213, 100, 81, 238, 61, 53, 186, 94, 7, 35, 178, 47, 92, 70, 143, 109, 157, 101,
+            237, 118, 217, 137, 138, 81, 254, 14, 46, 253, 219, 9,
+        ];
+
+        assert_eq!(Identity::derive_str(ID).to_scalar().to_bytes(), plain);
+        assert_eq!(
+            Identity::derive_str_tagged(&[], ID).to_scalar().to_bytes(),
+            untagged
+        );
+        assert_eq!(
+            Identity::derive_str_tagged(&[b"cgwfo", b"irma"], ID)
+                .to_scalar()
+                .to_bytes(),
+            tagged
+        );
+    }
+
+    #[test]
+    fn separated_derive() {
+        let id = Identity::derive_str_tagged(&[b"cgwfo", b"irma"], ID);
+
+        assert_ne!(id, Identity::derive_str_tagged(&[b"cgwkv1", b"irma"], ID));
+        assert_ne!(id, Identity::derive_str_tagged(&[b"cgwfoirma"], ID));
+        assert_ne!(
+            id,
+            Identity::derive_str_tagged(&[b"cgwfo", b"irma", b""], ID)
+        );
+        assert_ne!(
+            Identity::derive_tagged(&[b"a"], b"bc"),
+            Identity::derive_tagged(&[b"ab"], b"c")
+        );
+    }
+
+    #[test]
+    fn injective_identities() {
+        let epoch = Epoch::iso_week(2026, 42).unwrap();
+        let id = Identity::builder(b"email:x@y.nl").epoch(epoch).build();
+
+        assert_eq!(id, Identity::builder(b"email:x@y.nl").epoch(epoch).build());
+        assert_ne!(id, Identity::builder(b"email:x@y.nl").build());
+        assert_ne!(
+            id,
+            Identity::builder(b"email:x@y.nl")
+                .epoch(epoch.next())
+                .build()
+        );
+        assert_ne!(id, Identity::derive_str("email:x@y.nl|2026-W42"));
+        assert_ne!(
+            Identity::builder(b"").epoch(Epoch(0)).build(),
+            Identity::builder(b"").build()
+        );
+    }
+}